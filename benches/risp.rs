@@ -0,0 +1,101 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use risp::internals::{Lexer, Parser, Token};
+use risp::{Engine, Interpreter};
+
+const DEFINITIONS: &str = "
+(defun fib (n) (if (< n 2) n (+ (fib (- n 1)) (fib (- n 2)))))
+(defun ack (m n)
+  (if (= m 0)
+      (+ n 1)
+      (if (= n 0)
+          (ack (- m 1) 1)
+          (ack (- m 1) (ack m (- n 1))))))
+(defun squares (n)
+  (mapcar (lambda (x) (* x x)) (coerce (make-array n :initial-element 3) 'list)))
+";
+
+// A program of `n` copies of the definitions.
+fn program(n: usize) -> String {
+    DEFINITIONS.repeat(n)
+}
+
+// A form nested `depth` lists deep.
+fn nested(depth: usize) -> String {
+    format!("{}1{}", "(+ ".repeat(depth), ")".repeat(depth))
+}
+
+fn lexer(c: &mut Criterion) {
+    let input = program(100);
+    c.bench_function("lexer/next_token", |b| {
+        b.iter(|| {
+            let mut lexer = Lexer::new(input.clone()).unwrap();
+            while lexer.next_token().unwrap() != Token::EOF {}
+        })
+    });
+}
+
+fn parser(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parser/parse");
+    for depth in [10, 100, 1000] {
+        let input = nested(depth);
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &input, |b, input| {
+            b.iter(|| {
+                Parser::new(Lexer::new(input.clone()).unwrap())
+                    .parse()
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn evaluator(c: &mut Criterion) {
+    for (name, form) in [
+        ("fib", "(fib 20)"),
+        ("ackermann", "(ack 2 9)"),
+        ("list-building", "(squares 1000)"),
+    ] {
+        let mut group = c.benchmark_group(format!("eval/{}", name));
+        for (engine_name, engine) in [
+            ("interpreter", Engine::Interpreter),
+            ("bytecode", Engine::Bytecode),
+        ] {
+            let mut interp = Interpreter::new().with_engine(engine);
+            interp.eval_str(&program(1)).unwrap();
+            group.bench_function(engine_name, |b| b.iter(|| interp.eval_str(form).unwrap()));
+        }
+        group.finish();
+    }
+}
+
+// List-heavy code, where passing a list or a symbol around only copies a
+// handle.
+fn lists(c: &mut Criterion) {
+    let mut interp = Interpreter::new();
+    interp
+        .eval_str(
+            "(defparameter *xs* (coerce (make-array 100000 :initial-element 'item) 'list))
+             (defun pass (n xs) (if (= n 0) (find 'other xs) (pass (- n 1) xs)))",
+        )
+        .unwrap();
+    let mut group = c.benchmark_group("eval/lists");
+    group.sample_size(10);
+    for (name, form) in [
+        ("pass", "(pass 100 *xs*)"),
+        ("mapcar", "(find 'other (mapcar (lambda (x) x) *xs*))"),
+        (
+            "remove-if",
+            "(find 'other (remove-if (lambda (x) (eq x 'other)) *xs*))",
+        ),
+        (
+            "sort",
+            "(find 'other (sort (mapcar (lambda (x) :key) *xs*) (lambda (a b) nil)))",
+        ),
+    ] {
+        group.bench_function(name, |b| b.iter(|| interp.eval_str(form).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lexer, parser, evaluator, lists);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+//! A Common Lisp interpreter which can be embedded in Rust programs through
+//! [`Interpreter`].
+//!
+//! ```
+//! use risp::{FromExpr, Interpreter, IntoExpr};
+//!
+//! let mut interp = Interpreter::new();
+//! interp.register("host-version", || "1.0");
+//! interp.set_global("*retries*", 3.into_expr()).unwrap();
+//! interp
+//!     .eval_str("(defun retries-left (n) (- *retries* n))")
+//!     .unwrap();
+//! let left = interp.eval_str("(retries-left 1)").unwrap();
+//! assert_eq!(i64::from_expr(&left).unwrap(), 2);
+//! assert_eq!(interp.eval_str("(host-version)").unwrap().to_string(), "1.0");
+//! ```
+
+mod modules;
+
+pub use modules::ast::Expr;
+pub use modules::convert::{FromExpr, HostFn, IntoExpr, IntoResult};
+pub use modules::error::RispError;
+pub use modules::eval::Engine;
+pub use modules::interpreter::Interpreter;
+pub use modules::printer::prin1_to_string;
+
+// The internals the command line and the benchmarks use, which are not part
+// of the API.
+#[doc(hidden)]
+pub mod internals {
+    pub use crate::modules::gc::set_stress as set_gc_stress;
+    pub use crate::modules::lexer::Lexer;
+    pub use crate::modules::parser::Parser;
+    pub use crate::modules::token::Token;
+}
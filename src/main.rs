@@ -1,32 +1,83 @@
-mod modules;
-
 use std::fs::File;
 use std::io::BufRead;
-use std::{env, io};
+use std::{env, io, process};
 
 use rustyline::error::ReadlineError;
 use rustyline::{Editor, Result as RustyResult};
 
-use modules::{ast, error, eval, lexer, parser, token};
-
-fn eval(
-    evaluator: &mut eval::Evaluator,
-    env: &mut eval::ExprEnv,
-    line: &String,
-) -> Result<String, error::RispError> {
-    let lexer = lexer::Lexer::new(line.clone())?;
-    let mut parser = parser::Parser::new(lexer);
-    let expr = parser.parse()?;
-    let result = evaluator.eval(&expr, env)?;
-    Ok(result.to_string())
+use risp::internals::set_gc_stress;
+use risp::{prin1_to_string, Engine, Interpreter, RispError};
+
+// Evaluates a line, returning all the values of its last form as printed.
+fn eval(interp: &mut Interpreter, line: &str) -> Result<String, RispError> {
+    let values = interp.eval_str_values(line)?;
+    Ok(values
+        .iter()
+        .map(prin1_to_string)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn print_warnings(interp: &mut Interpreter) {
+    for warning in interp.take_warnings() {
+        eprintln!("WARNING: {}", warning);
+    }
+}
+
+// Checks a script without running it. Definitions are evaluated so that the
+// calls after them can be checked, other forms are only linted. Returns the
+// number of problems found.
+fn lint(interp: &mut Interpreter, filename: &str) -> io::Result<usize> {
+    let mut count = 0;
+    let file = File::open(filename)?;
+    for (i, line) in io::BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let warnings = interp.lint_str(&line);
+        for warning in &warnings {
+            println!("{}:{}: {}", filename, i + 1, warning);
+        }
+        count += warnings.len();
+    }
+    Ok(count)
 }
 
 fn main() -> RustyResult<()> {
-    let mut evaluator = eval::Evaluator::new();
-    let mut env: eval::ExprEnv = eval::default_env();
+    let mut interp = Interpreter::new();
+
+    // Each --load-path option adds a directory searched by REQUIRE, before
+    // the ones listed in RISP_PATH.
+    let mut args = env::args().collect::<Vec<String>>();
+    while let Some(i) = args.iter().position(|arg| arg == "--load-path") {
+        if i + 1 == args.len() {
+            eprintln!("--load-path expects a directory");
+            process::exit(2);
+        }
+        interp.add_load_path(args.remove(i + 1));
+        args.remove(i);
+    }
+    // --bytecode compiles functions as they are defined.
+    if let Some(i) = args.iter().position(|arg| arg == "--bytecode") {
+        args.remove(i);
+        interp = interp.with_engine(Engine::Bytecode);
+    }
+    // --gc-stress collects garbage on every allocation.
+    if let Some(i) = args.iter().position(|arg| arg == "--gc-stress") {
+        args.remove(i);
+        set_gc_stress(true);
+    }
+
+    if args.get(1).map(String::as_str) == Some("--lint") {
+        let mut count = 0;
+        for filename in &args[2..] {
+            count += lint(&mut interp, filename)?;
+        }
+        process::exit(if count == 0 { 0 } else { 1 });
+    }
 
     if atty::is(atty::Stream::Stdin) {
-        let args = env::args().collect::<Vec<String>>();
         if args.len() == 1 {
             let mut rl = Editor::<()>::new()?;
             _ = rl.load_history("history.txt");
@@ -35,7 +86,9 @@ fn main() -> RustyResult<()> {
                 match readline {
                     Ok(line) => {
                         rl.add_history_entry(line.as_str());
-                        if let Some(result) = eval(&mut evaluator, &mut env, &line).ok() {
+                        let result = eval(&mut interp, &line);
+                        print_warnings(&mut interp);
+                        if let Ok(result) = result {
                             println!("{}", result);
                         } else {
                             continue;
@@ -58,19 +111,24 @@ fn main() -> RustyResult<()> {
             let arg = args.get(1);
             if let Some(filename) = arg {
                 let file = File::open(filename)?;
+                // The files the script loads are found next to it.
+                interp
+                    .begin_file(filename)
+                    .map_err(|_| ReadlineError::Interrupted)?;
                 for line in io::BufReader::new(file).lines() {
-                    let result = eval(&mut evaluator, &mut env, &line?)
-                        .map_err(|_| ReadlineError::Interrupted)?;
-                    println!("{}", result);
+                    let result = eval(&mut interp, &line?);
+                    print_warnings(&mut interp);
+                    println!("{}", result.map_err(|_| ReadlineError::Interrupted)?);
                 }
+                interp.end_file();
             }
         }
     } else {
         let stdin = io::stdin();
         for line in stdin.lines() {
-            let result =
-                eval(&mut evaluator, &mut env, &line?).map_err(|_| ReadlineError::Interrupted)?;
-            println!("{}", result);
+            let result = eval(&mut interp, &line?);
+            print_warnings(&mut interp);
+            println!("{}", result.map_err(|_| ReadlineError::Interrupted)?);
         }
     }
     Ok(())
@@ -80,17 +138,1155 @@ fn main() -> RustyResult<()> {
 mod tests {
     use super::*;
 
-    fn test() {}
+    // Runs the cases with both engines, which must give the same results.
+    fn test(cases: &[(&str, &str)]) {
+        for engine in [Engine::Interpreter, Engine::Bytecode] {
+            let mut interp = Interpreter::new().with_engine(engine);
+            for (input, want) in cases {
+                let got = eval(&mut interp, input).unwrap();
+                assert_eq!(&got, want, "input: {}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn eval_basic_atom() {
+        test(&[
+            ("1", "1"),
+            ("1.5", "1.5"),
+            ("2/4", "1/2"),
+            ("t", "T"),
+            ("nil", "NIL"),
+        ]);
+    }
+
+    #[test]
+    fn eval_calc() {
+        test(&[
+            ("(+ 1 2)", "3"),
+            ("(* 10 (+ 2 40))", "420"),
+            ("(* 99999999999 99999999999)", "9999999999800000000001"),
+            ("(/ 1 3)", "1/3"),
+            ("(+ (/ 1 3) (/ 2 3))", "1"),
+            ("(/ 6 3)", "2"),
+            ("(+ 1 0.5)", "1.5"),
+            ("(- 10 2.5 (/ 1 2))", "7.0"),
+            ("(= 1 1.0 (/ 2 2))", "T"),
+            ("(< 1 2 2)", "NIL"),
+            ("(<= 1 2 2)", "T"),
+            ("(> 3 (/ 5 2) 2.0)", "T"),
+            ("(/= 1 2 1)", "NIL"),
+            ("(- 5)", "-5"),
+            ("(- 2.5)", "-2.5"),
+            ("(/ 2)", "1/2"),
+            ("(/ 0.5)", "2.0"),
+            ("(+)", "0"),
+            ("(*)", "1"),
+            ("(+ 3)", "3"),
+            ("(reduce #'+ '())", "0"),
+            ("(reduce #'* '())", "1"),
+            ("1.5e10", "15000000000.0"),
+            ("1.0e20", "1.0e20"),
+            ("(* 2 1d-7)", "2.0e-7"),
+            ("(= 2.0e-7 (* 2 1d-7))", "T"),
+        ]);
+        let mut interp = Interpreter::new();
+        assert!(eval(&mut interp, "(-)").is_err());
+        assert!(eval(&mut interp, "(/ 0)").is_err());
+    }
+
+    #[test]
+    fn eval_symbol() {
+        test(&[("(setq a 10)", "10"), ("(+ a a)", "20")]);
+    }
+
+    #[test]
+    fn eval_char() {
+        test(&[
+            (r"#\a", r"#\a"),
+            (r#"(char "hello" 1)"#, r"#\e"),
+            (r"(char-code #\A)", "65"),
+            ("(code-char 97)", r"#\a"),
+            (r"(char-upcase #\a)", r"#\A"),
+            (r"(char-downcase #\Space)", r"#\Space"),
+            (r"(char= #\a #\a #\a)", "T"),
+            (r"(char< #\a #\b #\b)", "NIL"),
+            (r"(alpha-char-p #\z)", "T"),
+            (r"(digit-char-p #\7)", "7"),
+            (r"(digit-char-p #\f 16)", "15"),
+            (r"(digit-char-p #\x)", "NIL"),
+        ]);
+    }
+
+    #[test]
+    fn eval_string() {
+        test(&[
+            (
+                r#"(concatenate 'string "foo" "bar" '(#\!))"#,
+                r#""foobar!""#,
+            ),
+            (r#"(subseq "héllo wörld" 6)"#, r#""wörld""#),
+            (r#"(subseq "héllo" 1 3)"#, r#""él""#),
+            (r#"(string-upcase "straße")"#, r#""STRASSE""#),
+            (r#"(string-downcase 'hello)"#, r#""hello""#),
+            (r#"(string-trim " -" "  -trim me- ")"#, r#""trim me""#),
+            (r#"(string= "abc" "abc")"#, "T"),
+            (r#"(string< "apple" "apricot")"#, "2"),
+            (r#"(string< "b" "a")"#, "NIL"),
+            (r#"(search "wö" "héllo wörld")"#, "6"),
+            (r#"(position #\l "héllo")"#, "2"),
+            (r#"(parse-integer " -42 ")"#, "-42\n5"),
+            (r#"(parse-integer "ff" :radix 16)"#, "255\n2"),
+            (r#"(parse-integer "12abc" :junk-allowed t)"#, "12\n2"),
+            (r#"(prin1-to-string "a\"b")"#, r#""\"a\\\"b\"""#),
+            (r#"(string #\x)"#, r#""x""#),
+            (r#"(split-string "a,b,,c" #\,)"#, r#"("a" "b" "" "c")"#),
+        ]);
+        let mut interp = Interpreter::new();
+        assert!(eval(&mut interp, r#"(parse-integer "12abc")"#).is_err());
+    }
+
+    #[test]
+    fn eval_printer() {
+        test(&[
+            (r#""line""#, r#""line""#),
+            ("'a", "A"),
+            ("''a", "'A"),
+            (r#"(princ "plain")"#, r#""plain""#),
+            (r#"(prin1 '("a" #\b))"#, r#"("a" #\b)"#),
+            ("(terpri)", "NIL"),
+            (r#"(write-string "out")"#, r#""out""#),
+            ("(defun double (num) (* num 2))", "DOUBLE"),
+            ("#'double", "#<FUNCTION DOUBLE>"),
+        ]);
+    }
+
+    #[test]
+    fn eval_format() {
+        test(&[
+            (r#"(format nil "~a: ~5,'0d" "id" 42)"#, r#""id: 00042""#),
+            (r#"(format nil "~{~a~^ ~}" '(1 2 3))"#, r#""1 2 3""#),
+            (r#"(format t "~%")"#, "NIL"),
+            (
+                "(setq out (make-string-output-stream))",
+                "#<STRING-OUTPUT-STREAM>",
+            ),
+            (r#"(format out "~r" 3)"#, "NIL"),
+            (r#"(princ "!" out)"#, r#""!""#),
+            ("(get-output-stream-string out)", r#""three!""#),
+        ]);
+    }
+
+    #[test]
+    fn eval_hash_table() {
+        test(&[
+            (
+                "(setq table (make-hash-table :test 'equal))",
+                "#<HASH-TABLE :TEST EQUAL :COUNT 0>",
+            ),
+            (r#"(setf (gethash "apple" table) 3)"#, "3"),
+            (
+                r#"(setf (gethash "pear" table) 5 (gethash '(1 2) table) 'list)"#,
+                "LIST",
+            ),
+            (r#"(gethash "apple" table)"#, "3\nT"),
+            ("(gethash '(1 2) table)", "LIST\nT"),
+            (r#"(gethash "APPLE" table 'none)"#, "NONE\nNIL"),
+            ("(hash-table-count table)", "3"),
+            (r#"(remhash "pear" table)"#, "T"),
+            (r#"(remhash "pear" table)"#, "NIL"),
+            (
+                "(setq out (make-string-output-stream))",
+                "#<STRING-OUTPUT-STREAM>",
+            ),
+            (
+                r#"(maphash (lambda (k v) (format out "~s=~s;" k v)) table)"#,
+                "NIL",
+            ),
+            (
+                "(get-output-stream-string out)",
+                r#""\"apple\"=3;(1 2)=LIST;""#,
+            ),
+            ("(clrhash table)", "#<HASH-TABLE :TEST EQUAL :COUNT 0>"),
+            (
+                "(setq folded (make-hash-table :test 'equalp))",
+                "#<HASH-TABLE :TEST EQUALP :COUNT 0>",
+            ),
+            (r#"(setf (gethash "Key" folded) 1.0)"#, "1.0"),
+            (r#"(gethash "KEY" folded)"#, "1.0\nT"),
+            ("(setf (gethash '(#(1)) folded) 'nested)", "NESTED"),
+            ("(gethash '(#(1.0)) folded)", "NESTED\nT"),
+            (r#"(setf (gethash '("Ab" #\c) folded) 'mixed)"#, "MIXED"),
+            (r#"(gethash '("aB" #\C) folded)"#, "MIXED\nT"),
+            ("(gethash #(#(1)) folded)", "NIL\nNIL"),
+            (
+                "(setq inner (make-hash-table))",
+                "#<HASH-TABLE :TEST EQL :COUNT 0>",
+            ),
+            ("(setf (gethash 'a inner) 1)", "1"),
+            ("(setf (gethash (vector inner) folded) 'table)", "TABLE"),
+            (
+                "(setq other (make-hash-table))",
+                "#<HASH-TABLE :TEST EQL :COUNT 0>",
+            ),
+            ("(setf (gethash 'a other) 1.0)", "1.0"),
+            ("(gethash (vector other) folded)", "TABLE\nT"),
+            (
+                "(make-hash-table :test #'equalp)",
+                "#<HASH-TABLE :TEST EQUALP :COUNT 0>",
+            ),
+            (
+                "(make-hash-table :test #'eq)",
+                "#<HASH-TABLE :TEST EQ :COUNT 0>",
+            ),
+        ]);
+        let mut interp = Interpreter::new();
+        let err = eval(&mut interp, "(make-hash-table :test #'vector)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "MAKE-HASH-TABLE: unsupported test #<SYSTEM-FUNCTION VECTOR>"
+        );
+    }
+
+    #[test]
+    fn eval_equality() {
+        test(&[
+            ("(setq x t)", "T"),
+            ("(eq x t)", "T"),
+            ("(setq l '(1 2))", "(1 2)"),
+            ("(eq l l)", "T"),
+            ("(eq l '(1 2))", "NIL"),
+            ("(equal l '(1 2))", "T"),
+            ("(eql 1 1)", "T"),
+            ("(eql 1 1.0)", "NIL"),
+            ("(equalp 1 1.0)", "T"),
+            ("(eql 1.5 1.5)", "T"),
+            (r#"(eql "a" "a")"#, "NIL"),
+            (r#"(equal "a" "A")"#, "NIL"),
+            (r#"(equalp "a" "A")"#, "T"),
+            ("(eq 'sym 'sym)", "T"),
+            ("(defun double (num) (* num 2))", "DOUBLE"),
+            ("(eq #'double #'double)", "T"),
+            ("(equalp (make-hash-table) (make-hash-table))", "T"),
+        ]);
+    }
+
+    #[test]
+    fn eval_array() {
+        test(&[
+            ("#(1 2 3)", "#(1 2 3)"),
+            (r#"(vector 1 "a" #\b)"#, r#"#(1 "a" #\b)"#),
+            ("(setq v (make-array 3 :initial-element 0))", "#(0 0 0)"),
+            ("(setf (aref v 1) 5)", "5"),
+            ("v", "#(0 5 0)"),
+            ("(aref v 1)", "5"),
+            (
+                "(setq m (make-array '(2 3) :initial-contents '((1 2 3) (4 5 6))))",
+                "#2A((1 2 3) (4 5 6))",
+            ),
+            ("(aref m 1 2)", "6"),
+            ("(array-dimensions m)", "(2 3)"),
+            ("(array-rank m)", "2"),
+            (
+                "(setq s (make-array 0 :adjustable t :fill-pointer 0))",
+                "#()",
+            ),
+            ("(vector-push-extend 'a s)", "0"),
+            ("(vector-push-extend 'b s)", "1"),
+            ("s", "#(A B)"),
+            ("(fill-pointer s)", "2"),
+            ("(vector-pop s)", "B"),
+            ("(array-dimensions s)", "(2)"),
+            ("(position 'a s)", "0"),
+            ("(subseq #(1 2 3) 1)", "#(2 3)"),
+            ("(concatenate 'vector #(1) '(2))", "#(1 2)"),
+            (r#"(equalp #(1 "a") (vector 1.0 "A"))"#, "T"),
+            ("(equal #(1) #(1))", "NIL"),
+        ]);
+        let mut interp = Interpreter::new();
+        for input in [
+            "(make-array '(4294967296 4294967296 2))",
+            "(make-array '(4294967296 4294967296) :initial-contents '())",
+            "(make-array 100000000)",
+        ] {
+            let err = eval(&mut interp, input).unwrap_err();
+            assert!(
+                err.to_string().contains("exceed the total size limit"),
+                "{}: {}",
+                input,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn eval_sequence() {
+        test(&[
+            ("(mapcar (lambda (x) (* x 2)) '(1 2 3))", "(2 4 6)"),
+            ("(mapcar #'+ '(1 2 3) #(10 20))", "(11 22)"),
+            ("(mapcan (lambda (x) (vector x x)) '(1 2))", "(1 1 2 2)"),
+            ("(reduce '+ '(1 2 3 4))", "10"),
+            ("(reduce #'+ '() :initial-value 5)", "5"),
+            ("(reduce (lambda (acc x) (concatenate 'list acc (vector x))) '(1 2 3) :initial-value '())", "(1 2 3)"),
+            ("(reduce (lambda (x acc) (- x acc)) '(1 2 3) :from-end t)", "2"),
+            ("(remove 2 '(1 2 3 2))", "(1 3)"),
+            (r#"(remove #\l "hello")"#, r#""heo""#),
+            ("(remove-if (lambda (x) (> x 1)) #(1 2 3))", "#(1)"),
+            (r#"(remove-if-not #'alpha-char-p "a1b2")"#, r#""ab""#),
+            ("(find 2.0 '(1 2 3) :test #'=)", "2"),
+            ("(find-if (lambda (x) (> x 1)) '(1 2 3))", "2"),
+            ("(count-if (lambda (x) (> x 1)) '(1 2 3))", "2"),
+            ("(every #'< '(1 2) '(2 3))", "T"),
+            ("(some (lambda (x) (find x '(3 4))) '(1 4 3))", "4"),
+            ("(sort '(3 1 2) #'<)", "(1 2 3)"),
+            (
+                "(sort '((b 1) (a 2) (c 1)) #'< :key (lambda (x) (reduce (lambda (a b) b) x)))",
+                "((B 1) (C 1) (A 2))",
+            ),
+            ("(setq v (vector 3 1 2))", "#(3 1 2)"),
+            ("(sort v #'>)", "#(3 2 1)"),
+            ("v", "#(3 2 1)"),
+            (r#"(sort "cab" #'char<)"#, r#""abc""#),
+            ("(assoc 'b '((a 1) (b 2)))", "(B 2)"),
+            (r#"(assoc "b" '(("a" 1) ("b" 2)) :test #'equal)"#, r#"("b" 2)"#),
+            ("(member 2 '(1 2 3))", "(2 3)"),
+            ("(member 4 '(1 2 3))", "NIL"),
+        ]);
+    }
 
     #[test]
-    fn eval_basic_atom() {}
+    fn eval_struct() {
+        test(&[
+            (r#"(defstruct point x (y 0) (label "origin"))"#, "POINT"),
+            (
+                "(setq p (make-point :x 1))",
+                r#"#S(POINT :X 1 :Y 0 :LABEL "origin")"#,
+            ),
+            ("(point-x p)", "1"),
+            ("(point-y p)", "0"),
+            ("(setf (point-y p) (+ (point-x p) 2))", "3"),
+            ("(point-y p)", "3"),
+            ("(point-p p)", "T"),
+            ("(point-p '(1 3))", "NIL"),
+            (
+                "(setq q (copy-point p))",
+                r#"#S(POINT :X 1 :Y 3 :LABEL "origin")"#,
+            ),
+            ("(setf (point-x q) 10)", "10"),
+            ("(point-x p)", "1"),
+            ("(equal p (copy-point p))", "NIL"),
+            ("(equalp p (copy-point p))", "T"),
+            (
+                "(make-point :y (* 2 2) :x nil)",
+                r#"#S(POINT :X NIL :Y 4 :LABEL "origin")"#,
+            ),
+            (
+                r#"(format nil "~a" (make-point))"#,
+                r##""#S(POINT :X NIL :Y 0 :LABEL origin)""##,
+            ),
+            // The printed form reads back as an equal structure.
+            (
+                r#"(setq r #S(POINT :X 1 :Y 3 :LABEL "origin"))"#,
+                r#"#S(POINT :X 1 :Y 3 :LABEL "origin")"#,
+            ),
+            ("(equalp p r)", "T"),
+            ("(point-p '#s(point y (1 2)))", "T"),
+            ("(point-y '#s(point y (1 2)))", "(1 2)"),
+            ("'#S(POINT :LABEL NIL)", "#S(POINT :X NIL :Y 0 :LABEL NIL)"),
+        ]);
+    }
 
     #[test]
-    fn eval_calc() {}
+    fn eval_struct_options() {
+        test(&[
+            (
+                "(defstruct (segment (:conc-name seg-) (:constructor new-segment) (:copier nil)) from to)",
+                "SEGMENT",
+            ),
+            ("(seg-to (new-segment :from 1 :to 2))", "2"),
+            ("(segment-p (new-segment))", "T"),
+            ("(fboundp 'make-segment)", "NIL"),
+            ("(fboundp 'copy-segment)", "NIL"),
+            ("(seg-from #S(segment :from 3))", "3"),
+            ("(defstruct (pair (:conc-name nil) (:predicate is-pair)) left right)", "PAIR"),
+            ("(right (make-pair :right 1))", "1"),
+            ("(is-pair (make-pair))", "T"),
+            (
+                "(defclass bar () ((n :initform 7 :accessor bar-n)))",
+                "BAR",
+            ),
+            ("(defstruct holder (item (make-instance 'bar)))", "HOLDER"),
+            ("(bar-n (holder-item #S(holder)))", "7"),
+            ("(defgeneric size (x))", "SIZE"),
+            ("(defmethod size ((x bar)) 3)", "SIZE"),
+            ("(defstruct sized (n (size (make-instance 'bar))))", "SIZED"),
+            ("(sized-n #S(sized))", "3"),
+        ]);
+        let mut interp = Interpreter::new();
+        for (input, err) in [
+            (
+                "(defstruct (bad (:include point)) x)",
+                "DEFSTRUCT: unsupported option :INCLUDE",
+            ),
+            (
+                "(defstruct (bad (:constructor make-bad (x))) x)",
+                "DEFSTRUCT: constructors with a lambda list are not supported: (:CONSTRUCTOR MAKE-BAD (X))",
+            ),
+            ("#S(undefined :x 1)", "#S: UNDEFINED is not a structure type"),
+            ("(defstruct (none (:constructor nil)) x)", ""),
+            ("#S(none :x 1)", "#S: NONE has no constructor"),
+        ] {
+            match eval(&mut interp, input) {
+                Ok(_) => assert_eq!(err, "", "input: {}", input),
+                Err(got) => assert_eq!(got.to_string(), err, "input: {}", input),
+            }
+        }
+    }
 
     #[test]
-    fn eval_symbol() {}
+    fn eval_clos() {
+        test(&[
+            (
+                r#"(defclass shape () ((name :initarg :name :initform "shape" :accessor shape-name)))"#,
+                "SHAPE",
+            ),
+            (
+                "(defclass circle (shape) ((radius :initarg :radius :reader circle-radius)))",
+                "CIRCLE",
+            ),
+            ("(setq c (make-instance 'circle :radius 2))", "#<CIRCLE>"),
+            ("(shape-name c)", r#""shape""#),
+            ("(circle-radius c)", "2"),
+            (r#"(setf (shape-name c) "disc")"#, r#""disc""#),
+            ("(slot-value c 'name)", r#""disc""#),
+            ("(setf (slot-value c 'radius) 3)", "3"),
+            ("(circle-radius c)", "3"),
+            ("(defgeneric area (shape))", "AREA"),
+            ("(defmethod area ((s shape)) 0)", "AREA"),
+            (
+                "(defmethod area ((c circle)) (* 3 (circle-radius c) (circle-radius c)))",
+                "AREA",
+            ),
+            ("(area c)", "27"),
+            ("(area (make-instance 'shape))", "0"),
+            ("(setq out (make-string-output-stream))", "#<STRING-OUTPUT-STREAM>"),
+            (
+                r#"(defmethod area :before ((c circle)) (princ "before " out))"#,
+                "AREA",
+            ),
+            (r#"(defmethod area :after ((s shape)) (princ "after" out))"#, "AREA"),
+            (
+                "(defmethod area :around ((c circle)) (* 2 (call-next-method)))",
+                "AREA",
+            ),
+            ("(area c)", "54"),
+            ("(get-output-stream-string out)", r#""before after""#),
+            ("(defmethod describe-it ((x t)) '(thing))", "DESCRIBE-IT"),
+            (
+                "(defmethod describe-it ((x number)) (concatenate 'list '(number) (call-next-method)))",
+                "DESCRIBE-IT",
+            ),
+            (
+                "(defmethod describe-it ((x integer)) (concatenate 'list '(integer) (call-next-method)))",
+                "DESCRIBE-IT",
+            ),
+            ("(describe-it 1)", "(INTEGER NUMBER THING)"),
+            ("(describe-it 1.5)", "(NUMBER THING)"),
+            (r#"(describe-it "s")"#, "(THING)"),
+            ("(defmethod has-next ((x t)) (next-method-p))", "HAS-NEXT"),
+            ("(defmethod has-next ((x number)) (next-method-p))", "HAS-NEXT"),
+            ("(has-next 1)", "T"),
+            ("(has-next 'a)", "NIL"),
+            (
+                "(defmethod combine ((x string) (y number)) 'string-number)",
+                "COMBINE",
+            ),
+            ("(defmethod combine ((x t) (y integer)) 't-integer)", "COMBINE"),
+            ("(defmethod combine ((x string) (y t)) 'string-t)", "COMBINE"),
+            (r#"(combine "a" 1)"#, "STRING-NUMBER"),
+            ("(combine 'a 1)", "T-INTEGER"),
+            (r#"(combine "a" 'b)"#, "STRING-T"),
+        ]);
+    }
 
     #[test]
-    fn eval_func() {}
+    fn eval_types() {
+        test(&[
+            ("(type-of 1)", "FIXNUM"),
+            ("(type-of 1.5)", "DOUBLE-FLOAT"),
+            (r#"(type-of "a")"#, "STRING"),
+            ("(type-of 'a)", "SYMBOL"),
+            ("(type-of '(1))", "CONS"),
+            ("(type-of nil)", "NULL"),
+            ("(typep 5 '(integer 0 10))", "T"),
+            ("(typep 11 '(integer 0 10))", "NIL"),
+            (r#"(typep "a" '(or number string))"#, "T"),
+            ("(typep 'a '(or number string))", "NIL"),
+            ("(defun small (x) (< x 3))", "SMALL"),
+            ("(typep 2 '(satisfies small))", "T"),
+            ("(typep 2 '(and integer (not (satisfies small))))", "NIL"),
+            ("(typep :a '(member :a :b))", "T"),
+            ("(setq n 1)", "1"),
+            ("(check-type n integer)", "NIL"),
+            ("(coerce '(1 2) 'vector)", "#(1 2)"),
+            (r#"(coerce "ab" 'list)"#, r"(#\a #\b)"),
+            (r"(coerce '(#\a #\b) 'string)", r#""ab""#),
+            ("(coerce 1/2 'float)", "0.5"),
+            (r#"(coerce "x" 'character)"#, r"#\x"),
+            ("(numberp 1)", "T"),
+            (r#"(stringp "a")"#, "T"),
+            ("(symbolp nil)", "T"),
+            ("(functionp (lambda (x) x))", "T"),
+            ("(functionp 'small)", "NIL"),
+        ]);
+
+        let mut interp = Interpreter::new();
+        eval(&mut interp, r#"(setq s "a")"#).unwrap();
+        let err = eval(&mut interp, "(check-type s number)").unwrap_err();
+        assert_eq!(err.to_string(), r#"The value "a" is not of type NUMBER"#);
+        assert!(eval(&mut interp, "(coerce 'a 'float)").is_err());
+
+        // Type names must be builtin types, structures or classes.
+        for input in [
+            "(typep 1 'bogus)",
+            "(check-type s bogus)",
+            "(typep 1 '(or integer bogus))",
+        ] {
+            let err = eval(&mut interp, input).unwrap_err();
+            assert_eq!(err.to_string(), "unknown type specifier BOGUS");
+        }
+        for (input, want) in [
+            ("(defstruct shape)", "SHAPE"),
+            ("(typep (make-shape) 'shape)", "T"),
+            ("(defclass animal () ())", "ANIMAL"),
+            ("(typep 1 'animal)", "NIL"),
+            ("(typep 1 '(or shape structure-object))", "NIL"),
+        ] {
+            assert_eq!(eval(&mut interp, input).unwrap(), want);
+        }
+    }
+
+    #[test]
+    fn eval_declarations() {
+        test(&[
+            ("(defun add1 (x) (declare (type fixnum x)) (+ x 1))", "ADD1"),
+            ("(add1 2)", "3"),
+            (
+                "(declaim (ftype (function (number number) number) add))",
+                "NIL",
+            ),
+            ("(defun add (a b) (+ a b))", "ADD"),
+            ("(add 1 2.5)", "3.5"),
+            (r#"((lambda (s) (declare (string s)) s) "a")"#, r#""a""#),
+        ]);
+
+        let mut interp = Interpreter::new();
+        for line in [
+            "(declaim (ftype (function (number number) number) add))",
+            "(defun add (a b) (+ a b))",
+            "(defun add1 (x) (declare (fixnum x)) (+ x 1))",
+            "(defun apply-add (add) (funcall add 1))",
+        ] {
+            eval(&mut interp, line).unwrap();
+        }
+        assert!(interp.take_warnings().is_empty());
+        let err = eval(&mut interp, "(add 1 'a)").unwrap_err();
+        assert_eq!(err.to_string(), "The value A is not of type NUMBER");
+
+        eval(&mut interp, r#"(defun bad () (add1 (add 1 "x")))"#).unwrap();
+        eval(&mut interp, "(defun worse (x) (add1 x 2))").unwrap();
+        assert_eq!(
+            interp.take_warnings(),
+            vec![
+                r#"In BAD: The argument 2 of ADD, "x", is not of type NUMBER"#,
+                "In WORSE: ADD1 is called with 2 arguments, but takes 1",
+            ]
+        );
+
+        assert_eq!(
+            interp.lint_str("(list (add1 1.5) (hash-table-p))"),
+            vec![
+                "The argument 1 of ADD1, 1.5, is not of type FIXNUM",
+                "HASH-TABLE-P is called with 0 arguments, but takes 1",
+            ]
+        );
+
+        for line in [
+            "(defstruct point x)",
+            "(defclass shape () ())",
+            "(defun known (p s n) (declare (point p) (type (or shape null) s) (fixnum n)) p)",
+            "(declaim (ftype (function (point shape) (integer 0 *)) known2))",
+        ] {
+            eval(&mut interp, line).unwrap();
+        }
+        assert!(interp.take_warnings().is_empty());
+        eval(
+            &mut interp,
+            "(defun odd-types (x y) (declare (type no-such-type x) (type (or string nope) y)) x)",
+        )
+        .unwrap();
+        eval(
+            &mut interp,
+            "(declaim (ftype (function (widget) no-result) draw))",
+        )
+        .unwrap();
+        assert_eq!(
+            interp.take_warnings(),
+            vec![
+                "In ODD-TYPES: Unknown type NO-SUCH-TYPE in the declaration of X",
+                "In ODD-TYPES: Unknown type NOPE in the declaration of Y",
+                "In DECLAIM: Unknown type WIDGET in the declaration of DRAW",
+                "In DECLAIM: Unknown type NO-RESULT in the declaration of DRAW",
+            ]
+        );
+        assert_eq!(
+            interp.lint_str("(lambda (v) (declare (vektor v)) v)"),
+            vec!["Unknown type VEKTOR in the declaration of V"]
+        );
+    }
+
+    #[test]
+    fn eval_values() {
+        test(&[
+            ("(values 1 2 3)", "1\n2\n3"),
+            ("(values)", ""),
+            ("(+ (values 1 2) 10)", "11"),
+            ("(floor 7 2)", "3\n1"),
+            ("(floor -7 2)", "-4\n1"),
+            ("(ceiling 7 2)", "4\n-1"),
+            ("(truncate -7 2)", "-3\n-1"),
+            ("(round 5 2)", "2\n1"),
+            ("(round 7 2)", "4\n-1"),
+            ("(floor 5.5)", "5\n0.5"),
+            ("(floor 7/2)", "3\n1/2"),
+            ("(mod -7 2)", "1"),
+            ("(rem -7 2)", "-1"),
+            ("(multiple-value-list (floor 7 2))", "(3 1)"),
+            ("(multiple-value-list (values))", "NIL"),
+            ("(multiple-value-list (setq q (floor 7 2)))", "(3)"),
+            ("(multiple-value-list (vector (floor 7 2)))", "(#(3))"),
+            ("(nth-value 1 (floor 7 2))", "1"),
+            ("(nth-value 2 (floor 7 2))", "NIL"),
+            (
+                "(multiple-value-bind (q r extra) (floor 7 2) (vector q r extra))",
+                "#(3 1 NIL)",
+            ),
+            ("(defun div2 (x) (floor x 2))", "DIV2"),
+            ("(multiple-value-list (div2 9))", "(4 1)"),
+            ("(multiple-value-list (values-list '(a b)))", "(A B)"),
+            ("(mapcar (lambda (x) (floor x 2)) '(3 4))", "(1 2)"),
+            (
+                "(setq h (make-hash-table))",
+                "#<HASH-TABLE :TEST EQL :COUNT 0>",
+            ),
+            ("(multiple-value-list (gethash 'missing h))", "(NIL NIL)"),
+            ("(nth-value 1 (parse-integer \"12 \"))", "3"),
+            ("(defgeneric split (x))", "SPLIT"),
+            ("(defmethod split ((x integer)) (floor x 10))", "SPLIT"),
+            (
+                "(defmethod split :after ((x integer)) (values 0 0 0))",
+                "SPLIT",
+            ),
+            ("(multiple-value-list (split 42))", "(4 2)"),
+        ]);
+    }
+
+    #[test]
+    fn eval_special() {
+        test(&[
+            ("(defvar *depth* 0)", "*DEPTH*"),
+            ("(defvar *depth* 10)", "*DEPTH*"),
+            ("*depth*", "0"),
+            ("(defparameter *limit* 3)", "*LIMIT*"),
+            ("(defparameter *limit* 5)", "*LIMIT*"),
+            ("*limit*", "5"),
+            ("(defun depth () *depth*)", "DEPTH"),
+            ("(let ((*depth* 1)) (depth))", "1"),
+            ("(depth)", "0"),
+            ("(defun nested (*depth*) (depth))", "NESTED"),
+            ("(nested 7)", "7"),
+            ("*depth*", "0"),
+            ("(setq x 'global)", "GLOBAL"),
+            ("(defun get-x () x)", "GET-X"),
+            ("(let ((x 'local)) (get-x))", "GLOBAL"),
+            ("(let ((x 1) (y 2)) (+ x y))", "3"),
+            ("(let ((x 1)) (let ((x 2) (y x)) y))", "1"),
+            ("(let* ((x 1) (y (+ x 1))) y)", "2"),
+            ("(let (a (b)) (vector a b))", "#(NIL NIL)"),
+            ("(defun make-adder (n) (lambda (x) (+ x n)))", "MAKE-ADDER"),
+            ("(setq add2 (make-adder 2))", "#<FUNCTION LAMBDA>"),
+            ("(funcall add2 40)", "42"),
+            (
+                "(setq counter (let ((n 0)) (lambda () (setq n (+ n 1)))))",
+                "#<FUNCTION LAMBDA>",
+            ),
+            ("(funcall counter)", "1"),
+            ("(funcall counter)", "2"),
+            ("(defun bump () (setq total (+ total 1)))", "BUMP"),
+            ("(setq total 0)", "0"),
+            ("(bump)", "1"),
+            ("total", "1"),
+            ("(defconstant +answer+ 42)", "+ANSWER+"),
+            ("(defconstant +answer+ 42)", "+ANSWER+"),
+            ("+answer+", "42"),
+            (
+                r#"(let ((s (make-string-output-stream))) (let ((*standard-output* s)) (princ "hi")) (get-output-stream-string s))"#,
+                r#""hi""#,
+            ),
+        ]);
+
+        let mut interp = Interpreter::new();
+        eval(&mut interp, "(defconstant +answer+ 42)").unwrap();
+        let err = eval(&mut interp, "(setq +answer+ 0)").unwrap_err();
+        assert_eq!(err.to_string(), "+ANSWER+ is a constant");
+        assert!(eval(&mut interp, "(let ((+answer+ 0)) 1)").is_err());
+        assert!(eval(&mut interp, "(defconstant +answer+ 0)").is_err());
+        assert_eq!(eval(&mut interp, "+answer+").unwrap(), "42");
+
+        // Special bindings are undone when the body fails.
+        eval(&mut interp, "(defvar *state* 'idle)").unwrap();
+        assert!(eval(&mut interp, "(let ((*state* 'busy)) (car 1))").is_err());
+        assert_eq!(eval(&mut interp, "*state*").unwrap(), "IDLE");
+        assert!(interp.take_warnings().is_empty());
+        eval(&mut interp, "(defvar plain 1)").unwrap();
+        assert_eq!(
+            interp.take_warnings(),
+            vec!["DEFVAR: special variable PLAIN is not named *PLAIN*"]
+        );
+    }
+
+    #[test]
+    fn eval_namespaces() {
+        test(&[
+            ("(setq vector 1)", "1"),
+            ("(vector vector 2)", "#(1 2)"),
+            ("(defun twice (x) (* x 2))", "TWICE"),
+            ("(setq twice 3)", "3"),
+            ("(twice twice)", "6"),
+            ("(funcall #'twice 4)", "8"),
+            ("(funcall 'twice 5)", "10"),
+            ("(funcall (lambda (x) (+ x 1)) 1)", "2"),
+            ("(funcall #'(lambda (x) (+ x 1)) 2)", "3"),
+            ("(apply #'+ 1 2 '(3 4))", "10"),
+            ("(apply #'vector '())", "#()"),
+            ("(multiple-value-list (funcall #'floor 7 2))", "(3 1)"),
+            ("(fboundp 'twice)", "T"),
+            ("(fboundp 'undefined)", "NIL"),
+            ("(boundp 'twice)", "T"),
+            ("(boundp 'undefined)", "NIL"),
+            ("(boundp :key)", "T"),
+            ("(symbol-value 'twice)", "3"),
+            ("(symbol-function 'twice)", "#<FUNCTION TWICE>"),
+            ("(let ((twice 0)) (symbol-value 'twice))", "3"),
+            ("(setf (symbol-value 'y) 7)", "7"),
+            ("y", "7"),
+            (
+                "(setf (symbol-function 'thrice) (lambda (x) (* x 3)))",
+                "#<FUNCTION LAMBDA>",
+            ),
+            ("(thrice 2)", "6"),
+            ("(fmakunbound 'twice)", "TWICE"),
+            ("(fboundp 'twice)", "NIL"),
+            ("twice", "3"),
+        ]);
+
+        let mut interp = Interpreter::new();
+        let err = eval(&mut interp, "(undefined 1)").unwrap_err();
+        assert_eq!(err.to_string(), "undefined function: UNDEFINED");
+        eval(&mut interp, "(setq f (lambda () 1))").unwrap();
+        assert!(eval(&mut interp, "(f)").is_err());
+        assert!(eval(&mut interp, "(symbol-value 'undefined)").is_err());
+    }
+
+    #[test]
+    fn eval_packages() {
+        test(&[
+            (
+                "(defpackage :shapes (:use :cl) (:export :area))",
+                r#"#<PACKAGE "SHAPES">"#,
+            ),
+            ("(in-package :shapes)", r#"#<PACKAGE "SHAPES">"#),
+            ("(defun area (w h) (* w h))", "SHAPES::AREA"),
+            ("(defun helper () 1)", "SHAPES::HELPER"),
+            ("(area 2 3)", "6"),
+            ("(in-package :cl-user)", r#"#<PACKAGE "COMMON-LISP-USER">"#),
+            ("(shapes:area 2 3)", "6"),
+            ("(shapes::helper)", "1"),
+            ("(defun area () 'mine)", "AREA"),
+            ("(area)", "MINE"),
+            ("(shapes:area 1 1)", "1"),
+            ("(risp:vector cl:nil)", "#(NIL)"),
+            ("(package-name (find-package 'cl))", r#""COMMON-LISP""#),
+            ("(find-package \"NONE\")", "NIL"),
+            (
+                "(defpackage :app (:use :cl :shapes))",
+                r#"#<PACKAGE "APP">"#,
+            ),
+            ("(in-package :app)", r#"#<PACKAGE "APP">"#),
+            ("(area 4 5)", "20"),
+            ("(defstruct point x y)", "APP::POINT"),
+            ("(point-x (make-point :x 1))", "1"),
+            ("(make-point :y 2)", "#S(APP::POINT :X NIL :Y 2)"),
+            (
+                "(defpackage #:util (:use #:cl) (:export #:twice))",
+                r#"#<PACKAGE "UTIL">"#,
+            ),
+            ("(in-package #:util)", r#"#<PACKAGE "UTIL">"#),
+            ("(defun twice (x) (* 2 x))", "UTIL::TWICE"),
+            ("(in-package :app)", r#"#<PACKAGE "APP">"#),
+            ("(util:twice 4)", "8"),
+            ("'#:area", "AREA"),
+        ]);
+
+        let mut interp = Interpreter::new();
+        eval(&mut interp, "(defpackage :shapes)").unwrap();
+        let err = eval(&mut interp, "(shapes:helper)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Symbol HELPER is not external in package SHAPES"
+        );
+        let err = eval(&mut interp, "(none::foo)").unwrap_err();
+        assert_eq!(err.to_string(), "Package NONE does not exist");
+        assert!(eval(&mut interp, "(in-package :none)").is_err());
+
+        eval(&mut interp, "(defpackage :geometry (:export :area))").unwrap();
+        eval(&mut interp, "(defpackage :shapes (:export :area))").unwrap();
+        let err = eval(&mut interp, "(defpackage :canvas (:use :shapes :geometry))").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Package CANVAS cannot use both SHAPES and GEOMETRY, which export different symbols named AREA"
+        );
+    }
+
+    #[test]
+    fn eval_load() {
+        use std::fs;
+
+        let dir = env::temp_dir().join(format!("risp-load-{}", process::id()));
+        let files = [
+            (
+                "main.lisp",
+                "(load \"lib/util.lisp\")\n(defun main () (twice 21))\n",
+            ),
+            (
+                "lib/util.lisp",
+                "; helpers\n(defun twice (x)\n  (* x 2))\n(require :greet)\n",
+            ),
+            (
+                "lib/greet.lisp",
+                "(provide :greet)\n(defun greet () \"hi\")\n",
+            ),
+            ("mods/extra.lisp", "(defvar *extra* 1)\n"),
+            (
+                "pkg.lisp",
+                "(defpackage :pkg)\n(in-package :pkg)\n(defun f () 1)\n",
+            ),
+            (
+                "mods/ping.lisp",
+                "(require :pong)\n(provide :ping)\n(defun ping (n) (if (= n 0) 'ping (pong (- n 1))))\n",
+            ),
+            (
+                "mods/pong.lisp",
+                "(require :ping)\n(provide :pong)\n(defun pong (n) (if (= n 0) 'pong (ping (- n 1))))\n",
+            ),
+            ("a.lisp", "(load \"b.lisp\")\n"),
+            ("b.lisp", "(load \"a.lisp\")\n"),
+        ];
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::create_dir_all(dir.join("mods")).unwrap();
+        for (name, source) in files {
+            fs::write(dir.join(name), source).unwrap();
+        }
+        let path = |name: &str| dir.join(name).display().to_string();
+
+        let mut interp = Interpreter::new();
+        interp.add_load_path(dir.join("mods"));
+        let cases = [
+            (format!("(load \"{}\")", path("main.lisp")), "T"),
+            ("(main)".to_string(), "42"),
+            ("(greet)".to_string(), r#""hi""#),
+            ("*modules*".to_string(), r#"("GREET")"#),
+            ("(require :greet)".to_string(), "NIL"),
+            ("(require \"extra\")".to_string(), "T"),
+            ("*extra*".to_string(), "1"),
+            ("(require :ping)".to_string(), "T"),
+            ("(ping 3)".to_string(), "PONG"),
+            ("(pong 3)".to_string(), "PING"),
+            ("(require :pong)".to_string(), "NIL"),
+            (format!("(load \"{}\")", path("pkg.lisp")), "T"),
+            ("(pkg::f)".to_string(), "1"),
+            ("(defun g () 2)".to_string(), "G"),
+        ];
+        for (input, want) in &cases {
+            let got = eval(&mut interp, input).unwrap();
+            assert_eq!(&got, want, "input: {}", input);
+        }
+        let err = eval(&mut interp, "(require 'missing)").unwrap_err();
+        assert_eq!(err.to_string(), "REQUIRE: module MISSING not found");
+        let input = format!("(load \"{}\")", path("a.lisp"));
+        let err = eval(&mut interp, &input).unwrap_err();
+        assert!(err.to_string().contains("circular load"), "{}", err);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn eval_func() {
+        test(&[
+            ("(defun double (num) (* num 2))", "DOUBLE"),
+            ("(double 21)", "42"),
+            ("(defun answer () 42)", "ANSWER"),
+            ("(answer)", "42"),
+            ("((lambda (x) (* x x)) 5)", "25"),
+        ]);
+    }
+
+    #[test]
+    fn eval_bytecode() {
+        test(&[
+            (
+                "(defun fib (n) (if (< n 2) n (+ (fib (- n 1)) (fib (- n 2)))))",
+                "FIB",
+            ),
+            ("(fib 20)", "6765"),
+            ("(if nil 1)", "NIL"),
+            (
+                "(defun sign (x) (if (< x 0) :negative (if (= x 0) :zero :positive)))",
+                "SIGN",
+            ),
+            ("(sign -3)", ":NEGATIVE"),
+            ("(sign 0)", ":ZERO"),
+            (
+                "(let ((count 0)) (defun next () (setq count (+ count 1))))",
+                "NEXT",
+            ),
+            ("(next)", "1"),
+            ("(next)", "2"),
+            (
+                "(defun swap (a b) (let ((a b) (b a)) (values a b)))",
+                "SWAP",
+            ),
+            ("(multiple-value-list (swap 1 2))", "(2 1)"),
+            (
+                "(defun shift (a b) (let* ((a b) (b a)) (values a b)))",
+                "SHIFT",
+            ),
+            ("(multiple-value-list (shift 1 2))", "(2 2)"),
+            ("(defun div (a b) (floor a b))", "DIV"),
+            ("(multiple-value-list (div 7 2))", "(3 1)"),
+            ("(defun quotient (a b) (values (floor a b)))", "QUOTIENT"),
+            ("(multiple-value-list (quotient 7 2))", "(3)"),
+            ("(defvar *total* 0)", "*TOTAL*"),
+            (
+                "(defun add-to-total (x) (setq *total* (+ *total* x)))",
+                "ADD-TO-TOTAL",
+            ),
+            ("(add-to-total 5)", "5"),
+            ("(let ((*total* 10)) (add-to-total 1))", "11"),
+            ("*total*", "5"),
+            ("(defun adder () #'add-to-total)", "ADDER"),
+            ("(funcall (adder) 1)", "6"),
+            ("(defun helper () 1)", "HELPER"),
+            ("(defun use-helper () (helper))", "USE-HELPER"),
+            ("(use-helper)", "1"),
+            ("(defun helper () 2)", "HELPER"),
+            ("(use-helper)", "2"),
+            (
+                "(defun deep (n) (if (= n 0) 0 (+ 1 (deep (- n 1)))))",
+                "DEEP",
+            ),
+            ("(deep 5000)", "5000"),
+            ("(defun add1 (n) (+ n 1))", "ADD1"),
+            ("(add1 2)", "3"),
+            ("(defun + (a b) (* a b))", "+"),
+            ("(add1 5)", "5"),
+        ]);
+
+        let mut interp = Interpreter::new().with_engine(Engine::Bytecode);
+        eval(&mut interp, "(defun add1 (n) (+ n 1))").unwrap();
+        eval(&mut interp, "(fmakunbound '+)").unwrap();
+        let err = eval(&mut interp, "(add1 2)").unwrap_err();
+        assert_eq!(err.to_string(), "undefined function: +");
+
+        // Runaway recursion signals an error on either engine.
+        for engine in [Engine::Interpreter, Engine::Bytecode] {
+            let mut interp = Interpreter::new().with_engine(engine);
+            let deep = "(defun deep (n) (if (= n 0) 0 (+ 1 (deep (- n 1)))))";
+            eval(&mut interp, deep).unwrap();
+            let err = eval(&mut interp, "(deep 20000)").unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "call depth limit exceeded: more than 10000 nested calls"
+            );
+            assert_eq!(eval(&mut interp, "(deep 3)").unwrap(), "3");
+
+            let method = "(defmethod gdeep ((n integer)) (if (= n 0) 0 (+ 1 (gdeep (- n 1)))))";
+            eval(&mut interp, method).unwrap();
+            let err = eval(&mut interp, "(gdeep 100000)").unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "call depth limit exceeded: more than 10000 nested calls"
+            );
+            assert_eq!(eval(&mut interp, "(gdeep 5000)").unwrap(), "5000");
+        }
+    }
+
+    #[test]
+    fn eval_gc() {
+        for engine in [Engine::Interpreter, Engine::Bytecode] {
+            let mut interp = Interpreter::new().with_engine(engine);
+            eval(&mut interp, "(gc)").unwrap();
+            let cases = [
+                (
+                    "(defun make-cycle () (let ((table (make-hash-table))) (setf (gethash :self table) table) nil))",
+                    "MAKE-CYCLE",
+                ),
+                ("(make-cycle)", "NIL"),
+                ("(make-cycle)", "NIL"),
+                ("(nth-value 1 (gc))", "2"),
+                ("(nth-value 1 (gc))", "0"),
+                ("(defparameter *table* (make-hash-table))", "*TABLE*"),
+                ("(setf (gethash :self *table*) (vector *table*))", "#(#<HASH-TABLE :TEST EQL :COUNT 1>)"),
+                ("(nth-value 1 (gc))", "0"),
+                ("(eq (aref (gethash :self *table*) 0) *table*)", "T"),
+                ("(defun make-closure () (let ((f nil)) (setq f (lambda () f))))", "MAKE-CLOSURE"),
+                ("(defparameter *f* (make-closure))", "*F*"),
+                ("(nth-value 1 (gc))", "0"),
+                ("(eq (funcall *f*) *f*)", "T"),
+                ("(setq *f* nil)", "NIL"),
+                ("(nth-value 1 (gc))", "1"),
+                (
+                    "(let ((*standard-output* (make-string-output-stream))) (room) (get-output-stream-string *standard-output*))",
+                    "\"       1 environments\n       1 hash tables\n       1 arrays\n       3 objects\n\"",
+                ),
+                // The variables of a running function stay alive while a
+                // closure capturing them is part of a cycle.
+                (
+                    "(defun f () (let ((n 1)) (let ((v (vector nil))) (setf (aref v 0) (lambda () n)) (gc) n)))",
+                    "F",
+                ),
+                ("(f)", "1"),
+            ];
+            for (input, want) in cases {
+                let got = eval(&mut interp, input).unwrap();
+                assert_eq!(got, want, "input: {}", input);
+            }
+        }
+    }
+
+    // Collecting on every allocation must not free anything in use.
+    #[test]
+    fn eval_gc_stress() {
+        set_gc_stress(true);
+        test(&[
+            ("(defstruct node value next)", "NODE"),
+            ("(defparameter *a* (make-node :value 1))", "*A*"),
+            (
+                "(node-value (setf (node-next *a*) (make-node :value 2 :next *a*)))",
+                "2",
+            ),
+            ("(node-value (node-next (node-next *a*)))", "1"),
+            ("(defun adder (n) (let ((total n)) (lambda (x) (setq total (+ total x)))))", "ADDER"),
+            ("(defparameter *add* (adder 10))", "*ADD*"),
+            ("(funcall *add* 1)", "11"),
+            ("(funcall *add* 2)", "13"),
+            ("(reduce #'+ (mapcar (lambda (x) (let ((y (* x x))) y)) '(1 2 3 4)))", "30"),
+            ("(let ((table (make-hash-table :test 'equal))) (setf (gethash \"k\" table) (vector 1 2)) (aref (gethash \"k\" table) 1))", "2"),
+            (
+                "(defun captured () (let ((n 1)) (let ((v (vector nil))) (setf (aref v 0) (lambda () n)) (make-hash-table) n)))",
+                "CAPTURED",
+            ),
+            ("(captured)", "1"),
+        ]);
+        set_gc_stress(false);
+    }
+
+    #[test]
+    fn eval_time() {
+        test(&[
+            ("(defparameter *out* (make-string-output-stream))", "*OUT*"),
+            (
+                "(let ((*standard-output* *out*)) (multiple-value-list (time (floor 7 2))))",
+                "(3 1)",
+            ),
+            (
+                "(defparameter *report* (get-output-stream-string *out*))",
+                "*REPORT*",
+            ),
+            ("(subseq *report* 0 16)", "\"Evaluation took:\""),
+            ("(if (search \"seconds of real time\" *report*) t nil)", "T"),
+            ("(search \"  0 objects\" *report*)", "49"),
+            (
+                "(let ((*standard-output* *out*)) (hash-table-count (time (make-hash-table))))",
+                "0",
+            ),
+            (
+                "(search \"  1 objects\" (get-output-stream-string *out*))",
+                "49",
+            ),
+            // Lists, strings and closures are counted too.
+            (
+                "(let ((*standard-output* *out*)) (time (concatenate 'list '(1) '(2))))",
+                "(1 2)",
+            ),
+            (
+                "(search \"  1 objects\" (get-output-stream-string *out*))",
+                "49",
+            ),
+            (
+                "(let ((*standard-output* *out*)) (time (string-upcase \"a\")))",
+                "\"A\"",
+            ),
+            (
+                "(search \"  1 objects\" (get-output-stream-string *out*))",
+                "49",
+            ),
+            (
+                "(let ((*standard-output* *out*)) (functionp (time (lambda (x) x))))",
+                "T",
+            ),
+            (
+                "(search \"  1 objects\" (get-output-stream-string *out*))",
+                "49",
+            ),
+        ]);
+    }
+
+    #[test]
+    fn eval_interpreter() {
+        use risp::Expr;
+
+        let mut interp = Interpreter::new().with_engine(Engine::Bytecode);
+        assert_eq!(interp.eval_str("").unwrap().to_string(), "NIL");
+        let value = interp.eval_str("(defun twice (x) (* x 2)) (twice 21)");
+        assert_eq!(value.unwrap().to_string(), "42");
+        let values = interp.eval_str_values("(floor 7 2)").unwrap();
+        assert_eq!(values.len(), 2);
+        assert!(interp.eval_str("(undefined 1)").is_err());
+
+        interp.define_fn("host-name", 0, 0, |_| Ok(Expr::String("test".into())));
+        assert_eq!(interp.eval_str("(host-name)").unwrap().to_string(), "test");
+        assert!(interp.eval_str("(host-name 1)").is_err());
+
+        interp.set_global("*limit*", Expr::Nil).unwrap();
+        interp.eval_str("(setq *limit* (twice 5))").unwrap();
+        assert_eq!(interp.get_global("*limit*").unwrap().to_string(), "10");
+        assert!(interp.get_global("*missing*").is_none());
+
+        let path = env::temp_dir().join(format!("risp-interpreter-{}.lisp", process::id()));
+        std::fs::write(
+            &path,
+            "(in-package :cl-user)\n(defvar *loaded* (twice 2))\n",
+        )
+        .unwrap();
+        interp.eval_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(interp.get_global("*loaded*").unwrap().to_string(), "4");
+        assert!(interp.eval_file(&path).is_err());
+
+        assert!(interp.lint_str("(defun thrice (x) (* x 3))").is_empty());
+        assert_eq!(
+            interp.lint_str("(thrice 1 2)"),
+            vec!["THRICE is called with 2 arguments, but takes 1"]
+        );
+    }
 }
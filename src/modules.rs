@@ -1,6 +1,23 @@
+pub mod array;
 pub mod ast;
+pub mod builtin;
+pub mod clos;
+pub mod compiler;
+pub mod convert;
+pub mod env;
+pub mod equality;
 pub mod error;
 pub mod eval;
+pub mod gc;
+pub mod hash_table;
+pub mod interpreter;
 pub mod lexer;
+pub mod lint;
+pub mod number;
+pub mod package;
 pub mod parser;
+pub mod printer;
+pub mod stream;
+pub mod structure;
 pub mod token;
+pub mod types;
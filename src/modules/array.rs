@@ -0,0 +1,255 @@
+use super::ast::Expr;
+use super::error::RispError;
+use super::gc::{alloc, Trace};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Largest number of elements an array may hold, checked before anything is
+// allocated so that huge dimensions fail with an error instead of aborting.
+pub const ARRAY_TOTAL_SIZE_LIMIT: usize = 1 << 26;
+
+// Arrays of any rank, stored in row-major order. One dimensional arrays are
+// vectors and may have a fill pointer, which marks the end of their active
+// elements, and may be adjustable, so that VECTOR-PUSH-EXTEND can grow them.
+pub struct Array {
+    pub dimensions: Vec<usize>,
+    data: Vec<Expr>,
+    pub fill_pointer: Option<usize>,
+    pub adjustable: bool,
+}
+
+impl Array {
+    pub fn new(dimensions: Vec<usize>, initial_element: Expr) -> Result<Self, RispError> {
+        let size = total_size(&dimensions)?;
+        Ok(Self {
+            dimensions,
+            data: vec![initial_element; size],
+            fill_pointer: None,
+            adjustable: false,
+        })
+    }
+
+    // A simple vector holding the given elements.
+    pub fn from_vec(data: Vec<Expr>) -> Self {
+        Self {
+            dimensions: vec![data.len()],
+            data,
+            fill_pointer: None,
+            adjustable: false,
+        }
+    }
+
+    // Builds an array whose elements are taken from nested sequences, one
+    // level of nesting per dimension.
+    pub fn from_contents(dimensions: Vec<usize>, contents: &Expr) -> Result<Self, RispError> {
+        let mut data = Vec::with_capacity(total_size(&dimensions)?);
+        collect_contents(&dimensions, contents, &mut data)?;
+        Ok(Self {
+            dimensions,
+            data,
+            fill_pointer: None,
+            adjustable: false,
+        })
+    }
+
+    pub fn rank(&self) -> usize {
+        self.dimensions.len()
+    }
+
+    // Number of active elements: the fill pointer if any, else the total size.
+    pub fn len(&self) -> usize {
+        self.fill_pointer.unwrap_or(self.data.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn elements(&self) -> &[Expr] {
+        &self.data[..self.len()]
+    }
+
+    fn row_major_index(&self, subscripts: &[usize]) -> Result<usize, RispError> {
+        if subscripts.len() != self.rank() {
+            return Err(RispError::Expr(format!(
+                "Wrong number of subscripts {} for array of rank {}",
+                subscripts.len(),
+                self.rank()
+            )));
+        }
+        let mut index = 0;
+        for (subscript, dimension) in subscripts.iter().zip(&self.dimensions) {
+            if subscript >= dimension {
+                return Err(RispError::Expr(format!(
+                    "Subscript {} is out of bounds for dimension {}",
+                    subscript, dimension
+                )));
+            }
+            index = index * dimension + subscript;
+        }
+        Ok(index)
+    }
+
+    pub fn get(&self, subscripts: &[usize]) -> Result<Expr, RispError> {
+        Ok(self.data[self.row_major_index(subscripts)?].clone())
+    }
+
+    pub fn set(&mut self, subscripts: &[usize], value: Expr) -> Result<(), RispError> {
+        let index = self.row_major_index(subscripts)?;
+        self.data[index] = value;
+        Ok(())
+    }
+
+    // Stores the value at the fill pointer and advances it, growing the
+    // vector when it is full. Returns the index the value was stored at.
+    pub fn push_extend(&mut self, value: Expr) -> Result<usize, RispError> {
+        let index = self
+            .fill_pointer
+            .ok_or_else(|| RispError::Expr("Vector has no fill pointer".to_string()))?;
+        if index == self.data.len() {
+            if !self.adjustable {
+                return Err(RispError::Expr("Vector is not adjustable".to_string()));
+            }
+            self.data.push(value);
+            self.dimensions[0] = self.data.len();
+        } else {
+            self.data[index] = value;
+        }
+        self.fill_pointer = Some(index + 1);
+        Ok(index)
+    }
+
+    // Decrements the fill pointer and returns the element it now points at.
+    pub fn pop(&mut self) -> Result<Expr, RispError> {
+        match self.fill_pointer {
+            Some(0) => Err(RispError::Expr("Vector is empty".to_string())),
+            Some(index) => {
+                self.fill_pointer = Some(index - 1);
+                Ok(self.data[index - 1].clone())
+            }
+            None => Err(RispError::Expr("Vector has no fill pointer".to_string())),
+        }
+    }
+
+    // Wraps the array as a vector when it has rank one.
+    pub fn into_expr(self) -> Expr {
+        if self.rank() == 1 {
+            Expr::Vector(alloc(self))
+        } else {
+            Expr::Array(alloc(self))
+        }
+    }
+}
+
+// Number of elements in an array with the given dimensions.
+fn total_size(dimensions: &[usize]) -> Result<usize, RispError> {
+    dimensions
+        .iter()
+        .try_fold(1usize, |size, dimension| size.checked_mul(*dimension))
+        .filter(|size| *size <= ARRAY_TOTAL_SIZE_LIMIT)
+        .ok_or_else(|| {
+            RispError::Expr(format!(
+                "Array dimensions ({}) exceed the total size limit of {}",
+                dimensions
+                    .iter()
+                    .map(|dimension| dimension.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                ARRAY_TOTAL_SIZE_LIMIT
+            ))
+        })
+}
+
+// Elements past the fill pointer are referenced too.
+impl Trace for Array {
+    fn trace(&self, visit: &mut dyn FnMut(&Expr)) {
+        self.data.iter().for_each(visit);
+    }
+
+    fn clear_references(&mut self) {
+        self.data.fill(Expr::Nil);
+    }
+
+    fn kind(&self) -> &'static str {
+        "arrays"
+    }
+}
+
+fn collect_contents(
+    dimensions: &[usize],
+    contents: &Expr,
+    data: &mut Vec<Expr>,
+) -> Result<(), RispError> {
+    let (dimension, rest) = match dimensions.split_first() {
+        Some(split) => split,
+        None => {
+            data.push(contents.clone());
+            return Ok(());
+        }
+    };
+    let elements = match contents {
+        Expr::List(exprs) => exprs.to_vec(),
+        Expr::Nil => vec![],
+        Expr::Vector(vector) => vector.borrow().elements().to_vec(),
+        Expr::String(s) => s.chars().map(Expr::Char).collect(),
+        _ => return Err(RispError::Expr(format!("{} is not sequence", contents))),
+    };
+    if elements.len() != *dimension {
+        return Err(RispError::Expr(format!(
+            "Initial contents {} do not match dimension {}",
+            contents, dimension
+        )));
+    }
+    for element in &elements {
+        collect_contents(rest, element, data)?;
+    }
+    Ok(())
+}
+
+pub fn expect_array(x: &Expr) -> Result<Rc<RefCell<Array>>, RispError> {
+    match x {
+        Expr::Vector(array) | Expr::Array(array) => Ok(array.clone()),
+        _ => Err(RispError::Expr(format!("{} is not array", x))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::number::Number;
+
+    fn num(n: i64) -> Expr {
+        Expr::Number(Number::Fixnum(n))
+    }
+
+    #[test]
+    fn array_subscripts() {
+        let contents = Expr::List(
+            vec![
+                Expr::List(vec![num(1), num(2), num(3)].into()),
+                Expr::List(vec![num(4), num(5), num(6)].into()),
+            ]
+            .into(),
+        );
+        let mut array = Array::from_contents(vec![2, 3], &contents).unwrap();
+        assert!(array.get(&[1, 0]).unwrap() == num(4));
+        array.set(&[0, 2], num(0)).unwrap();
+        assert!(array.get(&[0, 2]).unwrap() == num(0));
+        assert!(array.get(&[2, 0]).is_err());
+        assert!(array.get(&[1]).is_err());
+        assert!(Array::from_contents(vec![3, 2], &contents).is_err());
+    }
+
+    #[test]
+    fn vector_fill_pointer() {
+        let mut vector = Array::new(vec![1], Expr::Nil).unwrap();
+        vector.fill_pointer = Some(0);
+        assert!(vector.push_extend(num(1)).is_ok());
+        assert!(vector.push_extend(num(2)).is_err());
+        vector.adjustable = true;
+        assert_eq!(vector.push_extend(num(2)).unwrap(), 1);
+        assert_eq!(vector.dimensions, vec![2]);
+        assert!(vector.pop().unwrap() == num(2));
+        assert_eq!(vector.elements().len(), 1);
+    }
+}
@@ -1,5 +1,20 @@
+use super::array::Array;
+use super::builtin::check_arity;
+use super::clos::Instance;
+use super::compiler::Chunk;
+use super::equality;
 use super::error::RispError;
+use super::eval::{Evaluator, ExprEnv};
+use super::gc;
+use super::hash_table::HashTable;
+use super::number::Number;
+use super::package::Package;
+use super::printer;
+use super::stream::Stream;
+use super::structure::Structure;
+use std::cell::RefCell;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
 // #[derive(Debug)]
@@ -9,51 +24,128 @@ use std::rc::Rc;
 
 #[derive(Clone)]
 pub struct Lambda {
+    pub name: Option<String>,
     pub args: Vec<String>,
+    // Declared types of the arguments, checked when the function is called.
+    pub arg_types: Vec<Option<Expr>>,
     pub body: Rc<Expr>,
+    // The lexical environment the function was created in.
+    pub env: ExprEnv,
+    // The compiled body, run instead of `body` when present.
+    pub code: Option<Rc<Chunk>>,
+}
+
+pub type NativeFn = dyn Fn(&mut Evaluator, &[Expr], &mut ExprEnv) -> Result<Expr, RispError>;
+
+// A function implemented in Rust. It receives its evaluated arguments along
+// with the evaluator and the caller's environment, so that it can call back
+// into Lisp functions. The arity is checked before it is called.
+pub struct Native {
+    pub name: String,
+    pub min_args: usize,
+    pub max_args: usize,
+    pub func: Box<NativeFn>,
+}
+
+impl Native {
+    pub fn new(
+        name: &str,
+        min_args: usize,
+        max_args: usize,
+        func: impl Fn(&mut Evaluator, &[Expr], &mut ExprEnv) -> Result<Expr, RispError> + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            min_args,
+            max_args,
+            func: Box::new(func),
+        }
+    }
+
+    pub fn call(
+        &self,
+        evaluator: &mut Evaluator,
+        args: &[Expr],
+        env: &mut ExprEnv,
+    ) -> Result<Expr, RispError> {
+        check_arity(&self.name, args, self.min_args, self.max_args)?;
+        (self.func)(evaluator, args, env)
+    }
 }
 
 #[derive(Clone)]
 pub enum Expr {
-    Number(f64),
-    String(String),
-    Symbol(String),
-    List(Vec<Expr>),
+    Number(Number),
+    String(Rc<str>),
+    Char(char),
+    Symbol(Rc<str>),
+    List(Rc<[Expr]>),
     True,
     Nil,
-    Func(fn(&[Expr]) -> Result<Expr, RispError>),
-    Lambda(Lambda),
+    Func(Rc<Native>),
+    Lambda(Rc<Lambda>),
+    Stream(Stream),
+    HashTable(Rc<RefCell<HashTable>>),
+    Vector(Rc<RefCell<Array>>),
+    Array(Rc<RefCell<Array>>),
+    Struct(Rc<RefCell<Structure>>),
+    Instance(Rc<RefCell<Instance>>),
+    Package(Rc<RefCell<Package>>),
+}
+
+// Lists, strings and closures are allocated through these, which count them
+// for TIME along with the objects the collector tracks.
+impl Expr {
+    pub fn list(exprs: impl Into<Rc<[Expr]>>) -> Self {
+        gc::count_allocation();
+        Expr::List(exprs.into())
+    }
+
+    pub fn string(s: impl Into<Rc<str>>) -> Self {
+        gc::count_allocation();
+        Expr::String(s.into())
+    }
+
+    pub fn lambda(lambda: Lambda) -> Self {
+        gc::count_allocation();
+        Expr::Lambda(Rc::new(lambda))
+    }
 }
 
+// Rust-side equality follows EQUAL, see `equality` for the other predicates.
 impl PartialEq for Expr {
     fn eq(&self, other: &Self) -> bool {
-        use Expr::{List, Nil, Number, String, Symbol};
-        match (self, other) {
-            (Symbol(a), Symbol(b)) => a == b,
-            (Number(a), Number(b)) => a == b,
-            (String(a), String(b)) => a == b,
-            (List(a), List(b)) => a == b,
-            (Nil, Nil) => true,
-            _ => false,
+        equality::equal(self, other)
+    }
+}
+
+impl Eq for Expr {}
+
+impl Hash for Expr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Expr::Number(num) => num.hash(state),
+            Expr::String(s) => s.hash(state),
+            Expr::Char(ch) => ch.hash(state),
+            Expr::Symbol(sym) => sym.hash(state),
+            Expr::List(exprs) => exprs.hash(state),
+            Expr::True | Expr::Nil => {}
+            Expr::Func(native) => Rc::as_ptr(native).hash(state),
+            Expr::Lambda(lambda) => Rc::as_ptr(lambda).hash(state),
+            Expr::Stream(stream) => stream.identity().hash(state),
+            Expr::HashTable(table) => Rc::as_ptr(table).hash(state),
+            Expr::Vector(array) | Expr::Array(array) => Rc::as_ptr(array).hash(state),
+            Expr::Struct(structure) => Rc::as_ptr(structure).hash(state),
+            Expr::Instance(instance) => Rc::as_ptr(instance).hash(state),
+            Expr::Package(package) => Rc::as_ptr(package).hash(state),
         }
     }
 }
 
+// Display writes the PRINC representation; see `printer` for the readable one.
 impl fmt::Display for Expr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let s = match self {
-            Expr::List(exprs) => {
-                let xs = exprs.iter().map(|x| x.to_string()).collect::<Vec<String>>();
-                format!("({})", xs.join(" "))
-            }
-            Expr::Number(num) => num.to_string(),
-            Expr::String(s) => s.to_string(),
-            Expr::Symbol(sym) => sym.to_string(),
-            Expr::Nil => "NIL".to_string(),
-            Expr::Func(_) => "LAMBDA".to_string(),
-            Expr::Lambda(_) => "LAMBDA".to_string(),
-            Expr::True => "T".to_string(),
-        };
-        write!(f, "{}", s)
+        write!(f, "{}", printer::princ_to_string(self))
     }
 }
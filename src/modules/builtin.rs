@@ -0,0 +1,188 @@
+use super::array::Array;
+use super::ast::{Expr, Native};
+use super::error::RispError;
+use super::eval::{Evaluator, ExprEnv};
+use super::number::Number;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub mod array;
+pub mod character;
+pub mod clos;
+pub mod equality;
+pub mod format;
+pub mod function;
+pub mod gc;
+pub mod hash_table;
+pub mod load;
+pub mod number;
+pub mod package;
+pub mod printer;
+pub mod sequence;
+pub mod string;
+pub mod structure;
+pub mod types;
+pub mod values;
+
+pub fn register(env: &mut ExprEnv) {
+    array::register(env);
+    character::register(env);
+    clos::register(env);
+    equality::register(env);
+    format::register(env);
+    function::register(env);
+    gc::register(env);
+    hash_table::register(env);
+    load::register(env);
+    number::register(env);
+    package::register(env);
+    printer::register(env);
+    sequence::register(env);
+    string::register(env);
+    types::register(env);
+    values::register(env);
+}
+
+// Registers a builtin which only needs its evaluated arguments. Use
+// `usize::MAX` as `max_args` for functions taking any number of arguments.
+pub fn define(
+    env: &mut ExprEnv,
+    name: &str,
+    min_args: usize,
+    max_args: usize,
+    func: fn(&[Expr]) -> Result<Expr, RispError>,
+) {
+    define_native(env, name, min_args, max_args, move |_, args, _| func(args));
+}
+
+// Registers a builtin which calls back into the evaluator.
+pub fn define_native(
+    env: &mut ExprEnv,
+    name: &str,
+    min_args: usize,
+    max_args: usize,
+    func: impl Fn(&mut Evaluator, &[Expr], &mut ExprEnv) -> Result<Expr, RispError> + 'static,
+) {
+    env.define_function(
+        name,
+        Expr::Func(Rc::new(Native::new(name, min_args, max_args, func))),
+    );
+}
+
+pub fn check_arity(name: &str, args: &[Expr], min: usize, max: usize) -> Result<(), RispError> {
+    if args.len() < min || args.len() > max {
+        return Err(RispError::Expr(format!(
+            "{}: invalid number of arguments: {}",
+            name,
+            args.len()
+        )));
+    }
+    Ok(())
+}
+
+pub fn boolean(b: bool) -> Expr {
+    if b {
+        Expr::True
+    } else {
+        Expr::Nil
+    }
+}
+
+pub fn expect_char(x: &Expr) -> Result<char, RispError> {
+    match x {
+        Expr::Char(ch) => Ok(*ch),
+        _ => Err(RispError::Expr(format!("{} is not character", x))),
+    }
+}
+
+pub fn expect_string(x: &Expr) -> Result<&str, RispError> {
+    match x {
+        Expr::String(s) => Ok(s),
+        _ => Err(RispError::Expr(format!("{} is not string", x))),
+    }
+}
+
+pub fn expect_index(x: &Expr) -> Result<usize, RispError> {
+    match x {
+        Expr::Number(Number::Fixnum(n)) if *n >= 0 => Ok(*n as usize),
+        _ => Err(RispError::Expr(format!("{} is not valid index", x))),
+    }
+}
+
+pub fn index_expr(index: usize) -> Expr {
+    Expr::Number(Number::Fixnum(index as i64))
+}
+
+pub fn list(exprs: Vec<Expr>) -> Expr {
+    if exprs.is_empty() {
+        Expr::Nil
+    } else {
+        Expr::list(exprs)
+    }
+}
+
+// Elements of a list, a string or the active elements of a vector.
+pub fn expect_sequence(x: &Expr) -> Result<Vec<Expr>, RispError> {
+    match x {
+        Expr::List(exprs) => Ok(exprs.to_vec()),
+        Expr::Nil => Ok(vec![]),
+        Expr::String(s) => Ok(s.chars().map(Expr::Char).collect()),
+        Expr::Vector(vector) => Ok(vector.borrow().elements().to_vec()),
+        _ => Err(RispError::Expr(format!("{} is not sequence", x))),
+    }
+}
+
+// A sequence of the same kind as `x` holding the given elements.
+pub fn sequence_like(x: &Expr, elements: Vec<Expr>) -> Result<Expr, RispError> {
+    match x {
+        Expr::String(_) => Ok(Expr::string(
+            elements
+                .iter()
+                .map(expect_char)
+                .collect::<Result<String, _>>()?,
+        )),
+        Expr::Vector(_) => Ok(Array::from_vec(elements).into_expr()),
+        _ => Ok(list(elements)),
+    }
+}
+
+// Strings, characters and symbols can all be used where a string is expected.
+pub fn expect_string_designator(x: &Expr) -> Result<String, RispError> {
+    match x {
+        Expr::String(s) => Ok(s.to_string()),
+        Expr::Char(ch) => Ok(ch.to_string()),
+        Expr::Symbol(_) | Expr::Nil | Expr::True => Ok(x.to_string()),
+        _ => Err(RispError::Expr(format!("{} is not string designator", x))),
+    }
+}
+
+// Collects trailing `:key value` pairs, rejecting keys not listed in `allowed`.
+pub fn keyword_args(
+    name: &str,
+    args: &[Expr],
+    allowed: &[&str],
+) -> Result<HashMap<String, Expr>, RispError> {
+    if !args.len().is_multiple_of(2) {
+        return Err(RispError::Expr(format!(
+            "{}: odd number of keyword arguments",
+            name
+        )));
+    }
+    let mut keywords = HashMap::new();
+    for pair in args.chunks(2) {
+        match &pair[0] {
+            Expr::Symbol(key) if allowed.contains(&&**key) => {
+                keywords
+                    .entry(key.to_string())
+                    .or_insert_with(|| pair[1].clone());
+            }
+            key => {
+                return Err(RispError::Expr(format!(
+                    "{}: unknown keyword argument {}",
+                    name, key
+                )))
+            }
+        }
+    }
+    Ok(keywords)
+}
@@ -0,0 +1,113 @@
+use super::{define, expect_index, index_expr, keyword_args, list};
+use crate::modules::array::{expect_array, Array};
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::eval::ExprEnv;
+
+pub fn register(env: &mut ExprEnv) {
+    define(env, "MAKE-ARRAY", 1, 11, make_array);
+    define(env, "VECTOR", 0, usize::MAX, vector);
+    define(env, "AREF", 1, usize::MAX, aref);
+    define(env, "ARRAY-DIMENSIONS", 1, 1, array_dimensions);
+    define(env, "ARRAY-RANK", 1, 1, array_rank);
+    define(env, "FILL-POINTER", 1, 1, fill_pointer);
+    define(env, "VECTOR-PUSH-EXTEND", 2, 2, vector_push_extend);
+    define(env, "VECTOR-POP", 1, 1, vector_pop);
+}
+
+pub fn expect_subscripts(args: &[Expr]) -> Result<Vec<usize>, RispError> {
+    args.iter().map(expect_index).collect()
+}
+
+// (make-array dimensions &key initial-element initial-contents adjustable
+// fill-pointer element-type) where dimensions is an integer or a list of them.
+fn make_array(args: &[Expr]) -> Result<Expr, RispError> {
+    let dimensions = match &args[0] {
+        Expr::List(exprs) => expect_subscripts(exprs)?,
+        Expr::Nil => vec![],
+        dimension => vec![expect_index(dimension)?],
+    };
+    let keywords = keyword_args(
+        "MAKE-ARRAY",
+        &args[1..],
+        &[
+            ":INITIAL-ELEMENT",
+            ":INITIAL-CONTENTS",
+            ":ADJUSTABLE",
+            ":FILL-POINTER",
+            ":ELEMENT-TYPE",
+        ],
+    )?;
+    let mut array = match keywords.get(":INITIAL-CONTENTS") {
+        Some(contents) => Array::from_contents(dimensions, contents)?,
+        None => Array::new(
+            dimensions,
+            keywords
+                .get(":INITIAL-ELEMENT")
+                .cloned()
+                .unwrap_or(Expr::Nil),
+        )?,
+    };
+    array.adjustable = keywords.get(":ADJUSTABLE").is_some_and(|x| *x != Expr::Nil);
+    array.fill_pointer = match keywords.get(":FILL-POINTER") {
+        None | Some(Expr::Nil) => None,
+        Some(_) if array.rank() != 1 => {
+            return Err(RispError::Expr(
+                "MAKE-ARRAY: only vectors can have a fill pointer".to_string(),
+            ))
+        }
+        Some(Expr::True) => Some(array.dimensions[0]),
+        Some(index) => match expect_index(index)? {
+            index if index <= array.dimensions[0] => Some(index),
+            index => {
+                return Err(RispError::Expr(format!(
+                    "MAKE-ARRAY: fill pointer {} is out of bounds",
+                    index
+                )))
+            }
+        },
+    };
+    Ok(array.into_expr())
+}
+
+fn vector(args: &[Expr]) -> Result<Expr, RispError> {
+    Ok(Array::from_vec(args.to_vec()).into_expr())
+}
+
+// (aref array &rest subscripts)
+fn aref(args: &[Expr]) -> Result<Expr, RispError> {
+    let array = expect_array(&args[0])?;
+    let subscripts = expect_subscripts(&args[1..])?;
+    let value = array.borrow().get(&subscripts)?;
+    Ok(value)
+}
+
+fn array_dimensions(args: &[Expr]) -> Result<Expr, RispError> {
+    let array = expect_array(&args[0])?;
+    let dimensions = array.borrow().dimensions.clone();
+    Ok(list(dimensions.into_iter().map(index_expr).collect()))
+}
+
+fn array_rank(args: &[Expr]) -> Result<Expr, RispError> {
+    let rank = expect_array(&args[0])?.borrow().rank();
+    Ok(index_expr(rank))
+}
+
+fn fill_pointer(args: &[Expr]) -> Result<Expr, RispError> {
+    let fill_pointer = expect_array(&args[0])?.borrow().fill_pointer;
+    fill_pointer
+        .map(index_expr)
+        .ok_or_else(|| RispError::Expr(format!("{} has no fill pointer", args[0])))
+}
+
+// (vector-push-extend new-element vector) returns the index of the new element.
+fn vector_push_extend(args: &[Expr]) -> Result<Expr, RispError> {
+    let array = expect_array(&args[1])?;
+    let index = array.borrow_mut().push_extend(args[0].clone())?;
+    Ok(index_expr(index))
+}
+
+fn vector_pop(args: &[Expr]) -> Result<Expr, RispError> {
+    let value = expect_array(&args[0])?.borrow_mut().pop()?;
+    Ok(value)
+}
@@ -0,0 +1,96 @@
+use super::{boolean, define, expect_char, expect_index, expect_string};
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::eval::ExprEnv;
+use crate::modules::number::Number;
+
+pub fn register(env: &mut ExprEnv) {
+    define(env, "CHAR", 2, 2, char);
+    define(env, "CHAR-CODE", 1, 1, char_code);
+    define(env, "CODE-CHAR", 1, 1, code_char);
+    define(env, "CHAR-UPCASE", 1, 1, char_upcase);
+    define(env, "CHAR-DOWNCASE", 1, 1, char_downcase);
+    define(env, "CHAR=", 1, usize::MAX, char_eq);
+    define(env, "CHAR<", 1, usize::MAX, char_lt);
+    define(env, "ALPHA-CHAR-P", 1, 1, alpha_char_p);
+    define(env, "DIGIT-CHAR-P", 1, 2, digit_char_p);
+}
+
+fn parse_list_of_chars(args: &[Expr]) -> Result<Vec<char>, RispError> {
+    args.iter().map(expect_char).collect()
+}
+
+// (char string index)
+fn char(args: &[Expr]) -> Result<Expr, RispError> {
+    let s = expect_string(&args[0])?;
+    let index = expect_index(&args[1])?;
+    s.chars()
+        .nth(index)
+        .map(Expr::Char)
+        .ok_or_else(|| RispError::Expr(format!("CHAR: index {} out of bounds", index)))
+}
+
+fn char_code(args: &[Expr]) -> Result<Expr, RispError> {
+    let ch = expect_char(&args[0])?;
+    Ok(Expr::Number(Number::Fixnum(ch as i64)))
+}
+
+fn code_char(args: &[Expr]) -> Result<Expr, RispError> {
+    let code = expect_index(&args[0])?;
+    Ok(u32::try_from(code)
+        .ok()
+        .and_then(char::from_u32)
+        .map_or(Expr::Nil, Expr::Char))
+}
+
+fn char_upcase(args: &[Expr]) -> Result<Expr, RispError> {
+    let ch = expect_char(&args[0])?;
+    Ok(Expr::Char(convert_case(ch, ch.to_uppercase())))
+}
+
+fn char_downcase(args: &[Expr]) -> Result<Expr, RispError> {
+    let ch = expect_char(&args[0])?;
+    Ok(Expr::Char(convert_case(ch, ch.to_lowercase())))
+}
+
+// Case conversions which do not map to a single character leave it unchanged.
+fn convert_case(ch: char, mut converted: impl ExactSizeIterator<Item = char>) -> char {
+    if converted.len() == 1 {
+        converted.next().unwrap_or(ch)
+    } else {
+        ch
+    }
+}
+
+fn char_eq(args: &[Expr]) -> Result<Expr, RispError> {
+    let chars = parse_list_of_chars(args)?;
+    Ok(boolean(chars.windows(2).all(|w| w[0] == w[1])))
+}
+
+fn char_lt(args: &[Expr]) -> Result<Expr, RispError> {
+    let chars = parse_list_of_chars(args)?;
+    Ok(boolean(chars.windows(2).all(|w| w[0] < w[1])))
+}
+
+fn alpha_char_p(args: &[Expr]) -> Result<Expr, RispError> {
+    let ch = expect_char(&args[0])?;
+    Ok(boolean(ch.is_alphabetic()))
+}
+
+// (digit-char-p char &optional (radix 10)) returns the weight of the digit.
+fn digit_char_p(args: &[Expr]) -> Result<Expr, RispError> {
+    let ch = expect_char(&args[0])?;
+    let radix = match args.get(1) {
+        Some(radix) => expect_index(radix)?,
+        None => 10,
+    };
+    if !(2..=36).contains(&radix) {
+        return Err(RispError::Expr(format!(
+            "DIGIT-CHAR-P: invalid radix {}",
+            radix
+        )));
+    }
+    Ok(ch
+        .to_digit(radix as u32)
+        .map_or(Expr::Nil, |d| Expr::Number(Number::Fixnum(d as i64))))
+}
@@ -0,0 +1,64 @@
+use super::define_native;
+use crate::modules::ast::Expr;
+use crate::modules::clos::{expect_instance, Instance};
+use crate::modules::error::RispError;
+use crate::modules::eval::{Evaluator, ExprEnv};
+use crate::modules::gc::alloc;
+
+pub fn register(env: &mut ExprEnv) {
+    define_native(env, "MAKE-INSTANCE", 1, usize::MAX, make_instance);
+    define_native(env, "SLOT-VALUE", 2, 2, slot_value);
+}
+
+// (make-instance class-name &rest initargs) where a slot without an initarg
+// takes the value of its initform, or is left unbound.
+fn make_instance(
+    evaluator: &mut Evaluator,
+    args: &[Expr],
+    env: &mut ExprEnv,
+) -> Result<Expr, RispError> {
+    let class = match &args[0] {
+        Expr::Symbol(name) => evaluator
+            .find_class(name)
+            .ok_or_else(|| RispError::Expr(format!("MAKE-INSTANCE: undefined class {}", name)))?,
+        class => return Err(RispError::Expr(format!("{} is not class name", class))),
+    };
+    let initargs = &args[1..];
+    if !initargs.len().is_multiple_of(2) {
+        return Err(RispError::Expr(
+            "MAKE-INSTANCE: odd number of initialization arguments".to_string(),
+        ));
+    }
+    for pair in initargs.chunks(2) {
+        let known = matches!(&pair[0], Expr::Symbol(key)
+            if class.slots.iter().any(|slot| slot.initargs.iter().any(|initarg| initarg == &**key)));
+        if !known {
+            return Err(RispError::Expr(format!(
+                "MAKE-INSTANCE: invalid initarg {} for {}",
+                pair[0], class.name
+            )));
+        }
+    }
+    let mut values = vec![];
+    for slot in &class.slots {
+        let initarg = initargs.chunks(2).find(|pair| match &pair[0] {
+            Expr::Symbol(key) => slot.initargs.iter().any(|initarg| initarg == &**key),
+            _ => false,
+        });
+        values.push(match (initarg, &slot.initform) {
+            (Some(pair), _) => Some(pair[1].clone()),
+            (None, Some(initform)) => Some(evaluator.eval(initform, env)?),
+            (None, None) => None,
+        });
+    }
+    Ok(Expr::Instance(alloc(Instance { class, values })))
+}
+
+// (slot-value instance slot-name)
+fn slot_value(_: &mut Evaluator, args: &[Expr], _: &mut ExprEnv) -> Result<Expr, RispError> {
+    let instance = expect_instance(&args[0])?;
+    match &args[1] {
+        Expr::Symbol(slot) => instance.borrow().slot_value(slot),
+        slot => Err(RispError::Expr(format!("{} is not symbol", slot))),
+    }
+}
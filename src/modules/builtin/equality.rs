@@ -0,0 +1,28 @@
+use super::{boolean, define};
+use crate::modules::ast::Expr;
+use crate::modules::equality;
+use crate::modules::error::RispError;
+use crate::modules::eval::ExprEnv;
+
+pub fn register(env: &mut ExprEnv) {
+    define(env, "EQ", 2, 2, eq);
+    define(env, "EQL", 2, 2, eql);
+    define(env, "EQUAL", 2, 2, equal);
+    define(env, "EQUALP", 2, 2, equalp);
+}
+
+fn eq(args: &[Expr]) -> Result<Expr, RispError> {
+    Ok(boolean(equality::eq(&args[0], &args[1])))
+}
+
+fn eql(args: &[Expr]) -> Result<Expr, RispError> {
+    Ok(boolean(equality::eql(&args[0], &args[1])))
+}
+
+fn equal(args: &[Expr]) -> Result<Expr, RispError> {
+    Ok(boolean(equality::equal(&args[0], &args[1])))
+}
+
+fn equalp(args: &[Expr]) -> Result<Expr, RispError> {
+    Ok(boolean(equality::equalp(&args[0], &args[1])))
+}
@@ -0,0 +1,755 @@
+use super::printer::output_stream;
+use super::{define_native, expect_sequence, expect_string};
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::eval::{Evaluator, ExprEnv};
+use crate::modules::number::Number;
+use crate::modules::printer::{prin1_to_string, princ_to_string};
+
+pub fn register(env: &mut ExprEnv) {
+    define_native(env, "FORMAT", 2, usize::MAX, format);
+}
+
+// (format destination control-string &rest args)
+// A NIL destination returns the output as a string, T writes it to the
+// standard output and a stream writes it to that stream.
+fn format(_: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let control = expect_string(&args[1])?;
+    let output = format_to_string(control, &args[2..])?;
+    match &args[0] {
+        Expr::Nil => Ok(Expr::string(output)),
+        destination => {
+            output_stream(Some(destination), env)?.write_str(&output)?;
+            Ok(Expr::Nil)
+        }
+    }
+}
+
+pub fn format_to_string(control: &str, args: &[Expr]) -> Result<String, RispError> {
+    let mut parser = DirectiveParser {
+        chars: control.chars().collect(),
+        position: 0,
+    };
+    let (items, _) = parser.parse_items(&[])?;
+    let mut args = Args { args, position: 0 };
+    let mut out = String::new();
+    format_items(&items, &mut args, &mut out)?;
+    Ok(out)
+}
+
+enum Param {
+    Number(i64),
+    Char(char),
+    // `v`: the parameter is taken from the next argument.
+    Arg,
+    // `#`: the number of remaining arguments.
+    Remaining,
+    Default,
+}
+
+struct Directive {
+    params: Vec<Param>,
+    colon: bool,
+    at: bool,
+    kind: char,
+    // Clauses of ~[ ~] and the body of ~{ ~}.
+    clauses: Vec<Vec<Item>>,
+    // Whether the last clause of ~[ ~] was introduced by ~:;
+    has_default: bool,
+}
+
+enum Item {
+    Text(String),
+    Directive(Directive),
+}
+
+// The directive which ended a nested block, with its colon modifier.
+type Terminator = Option<(char, bool)>;
+
+struct DirectiveParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl DirectiveParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Result<char, RispError> {
+        let ch = self
+            .peek()
+            .ok_or_else(|| RispError::Expr("FORMAT: unexpected end of control string".into()))?;
+        self.position += 1;
+        Ok(ch)
+    }
+
+    // Parses items until one of the `terminators` directives (e.g. `}` or `;`)
+    // and returns it together with its colon modifier.
+    fn parse_items(&mut self, terminators: &[char]) -> Result<(Vec<Item>, Terminator), RispError> {
+        let mut items = Vec::<Item>::new();
+        let mut text = String::new();
+        while let Some(ch) = self.peek() {
+            self.position += 1;
+            if ch != '~' {
+                text.push(ch);
+                continue;
+            }
+            if !text.is_empty() {
+                items.push(Item::Text(std::mem::take(&mut text)));
+            }
+            let mut directive = self.parse_directive()?;
+            match directive.kind {
+                kind if terminators.contains(&kind) => {
+                    return Ok((items, Some((kind, directive.colon))));
+                }
+                '}' | ']' | ';' => {
+                    return Err(RispError::Expr(format!(
+                        "FORMAT: unexpected directive ~{}",
+                        directive.kind
+                    )))
+                }
+                '{' => {
+                    let (body, _) = self.parse_items(&['}'])?;
+                    directive.clauses.push(body);
+                }
+                '[' => loop {
+                    let (clause, terminator) = self.parse_items(&[';', ']'])?;
+                    directive.clauses.push(clause);
+                    match terminator {
+                        Some((';', colon)) => directive.has_default = colon,
+                        _ => break,
+                    }
+                },
+                '\n' => {
+                    // A tilde followed by a newline ignores the newline and
+                    // any whitespace at the beginning of the next line.
+                    while self
+                        .peek()
+                        .is_some_and(|ch| ch.is_whitespace() && ch != '\n')
+                    {
+                        self.position += 1;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            items.push(Item::Directive(directive));
+        }
+        if !text.is_empty() {
+            items.push(Item::Text(text));
+        }
+        match terminators.first() {
+            Some(terminator) => Err(RispError::Expr(format!(
+                "FORMAT: missing ~{} in control string",
+                terminator
+            ))),
+            None => Ok((items, None)),
+        }
+    }
+
+    fn parse_directive(&mut self) -> Result<Directive, RispError> {
+        let mut params = Vec::<Param>::new();
+        loop {
+            let param = match self.peek() {
+                Some('0'..='9' | '+' | '-') => {
+                    let mut digits = String::from(self.next()?);
+                    while self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+                        digits.push(self.next()?);
+                    }
+                    let num = digits.parse::<i64>().map_err(|_| {
+                        RispError::Expr(format!("FORMAT: invalid parameter {}", digits))
+                    })?;
+                    Param::Number(num)
+                }
+                Some('\'') => {
+                    self.position += 1;
+                    Param::Char(self.next()?)
+                }
+                Some('v' | 'V') => {
+                    self.position += 1;
+                    Param::Arg
+                }
+                Some('#') => {
+                    self.position += 1;
+                    Param::Remaining
+                }
+                Some(',') => Param::Default,
+                _ => break,
+            };
+            params.push(param);
+            if self.peek() == Some(',') {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+        let (mut colon, mut at) = (false, false);
+        loop {
+            match self.peek() {
+                Some(':') => colon = true,
+                Some('@') => at = true,
+                _ => break,
+            }
+            self.position += 1;
+        }
+        Ok(Directive {
+            params,
+            colon,
+            at,
+            kind: self.next()?.to_ascii_lowercase(),
+            clauses: vec![],
+            has_default: false,
+        })
+    }
+}
+
+struct Args<'a> {
+    args: &'a [Expr],
+    position: usize,
+}
+
+impl Args<'_> {
+    fn next(&mut self) -> Result<Expr, RispError> {
+        let arg = self
+            .args
+            .get(self.position)
+            .ok_or_else(|| RispError::Expr("FORMAT: not enough arguments".to_string()))?;
+        self.position += 1;
+        Ok(arg.clone())
+    }
+
+    fn remaining(&self) -> usize {
+        self.args.len().saturating_sub(self.position)
+    }
+
+    fn goto(&mut self, position: i64) -> Result<(), RispError> {
+        if position < 0 || position as usize > self.args.len() {
+            return Err(RispError::Expr(format!(
+                "FORMAT: cannot move to argument {}",
+                position
+            )));
+        }
+        self.position = position as usize;
+        Ok(())
+    }
+}
+
+// Resolved parameters of a single directive.
+struct Params(Vec<Option<Param>>);
+
+impl Params {
+    fn resolve(params: &[Param], args: &mut Args) -> Result<Self, RispError> {
+        let mut resolved = Vec::new();
+        for param in params {
+            resolved.push(match param {
+                Param::Number(n) => Some(Param::Number(*n)),
+                Param::Char(ch) => Some(Param::Char(*ch)),
+                Param::Remaining => Some(Param::Number(args.remaining() as i64)),
+                Param::Arg => match args.next()? {
+                    Expr::Nil => None,
+                    Expr::Number(Number::Fixnum(n)) => Some(Param::Number(n)),
+                    Expr::Char(ch) => Some(Param::Char(ch)),
+                    x => return Err(RispError::Expr(format!("FORMAT: invalid parameter {}", x))),
+                },
+                Param::Default => None,
+            });
+        }
+        Ok(Params(resolved))
+    }
+
+    fn number(&self, index: usize, default: i64) -> i64 {
+        match self.0.get(index) {
+            Some(Some(Param::Number(n))) => *n,
+            _ => default,
+        }
+    }
+
+    fn optional_number(&self, index: usize) -> Option<i64> {
+        match self.0.get(index) {
+            Some(Some(Param::Number(n))) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn char(&self, index: usize, default: char) -> char {
+        match self.0.get(index) {
+            Some(Some(Param::Char(ch))) => *ch,
+            _ => default,
+        }
+    }
+}
+
+// Returns false when a ~^ directive terminated the processing.
+fn format_items(items: &[Item], args: &mut Args, out: &mut String) -> Result<bool, RispError> {
+    for item in items {
+        let directive = match item {
+            Item::Text(text) => {
+                out.push_str(text);
+                continue;
+            }
+            Item::Directive(directive) => directive,
+        };
+        let params = Params::resolve(&directive.params, args)?;
+        match directive.kind {
+            'a' | 's' => {
+                let arg = args.next()?;
+                let s = if directive.kind == 'a' {
+                    princ_to_string(&arg)
+                } else {
+                    prin1_to_string(&arg)
+                };
+                let mincol = params.number(0, 0);
+                let minpad = params.number(2, 0);
+                let padchar = params.char(3, ' ');
+                out.push_str(&pad(&s, mincol, minpad, padchar, directive.at));
+            }
+            'd' => format_radix(&params, directive, args.next()?, 10, out),
+            'b' => format_radix(&params, directive, args.next()?, 2, out),
+            'o' => format_radix(&params, directive, args.next()?, 8, out),
+            'x' => format_radix(&params, directive, args.next()?, 16, out),
+            'r' => {
+                let arg = args.next()?;
+                match params.optional_number(0) {
+                    Some(radix) if (2..=36).contains(&radix) => {
+                        let params = Params(params.0.into_iter().skip(1).collect());
+                        format_radix(&params, directive, arg, radix as u32, out)
+                    }
+                    Some(radix) => {
+                        return Err(RispError::Expr(format!("FORMAT: invalid radix {}", radix)))
+                    }
+                    None => out.push_str(&english(&arg, directive.colon, directive.at)?),
+                }
+            }
+            'f' => {
+                let arg = args.next()?;
+                let s = match &arg {
+                    Expr::Number(num) => match params.optional_number(1) {
+                        Some(digits) => format!("{:.*}", digits.max(0) as usize, num.to_f64()),
+                        None => Number::Float(num.to_f64()).to_string(),
+                    },
+                    _ => princ_to_string(&arg),
+                };
+                out.push_str(&pad(&s, params.number(0, 0), 0, ' ', true));
+            }
+            'c' => {
+                let arg = args.next()?;
+                out.push_str(&if directive.at {
+                    prin1_to_string(&arg)
+                } else {
+                    princ_to_string(&arg)
+                });
+            }
+            '%' => out.push_str(&"\n".repeat(params.number(0, 1).max(0) as usize)),
+            '&' => {
+                let count = params.number(0, 1).max(0) as usize;
+                if count > 0 {
+                    let fresh = if out.is_empty() || out.ends_with('\n') {
+                        0
+                    } else {
+                        1
+                    };
+                    out.push_str(&"\n".repeat(count - 1 + fresh));
+                }
+            }
+            '~' => out.push_str(&"~".repeat(params.number(0, 1).max(0) as usize)),
+            'p' => {
+                if directive.colon {
+                    args.goto(args.position as i64 - 1)?;
+                }
+                let singular = matches!(args.next()?, Expr::Number(Number::Fixnum(1)));
+                out.push_str(match (directive.at, singular) {
+                    (false, true) => "",
+                    (false, false) => "s",
+                    (true, true) => "y",
+                    (true, false) => "ies",
+                });
+            }
+            '*' => {
+                if directive.at {
+                    args.goto(params.number(0, 0))?;
+                } else if directive.colon {
+                    args.goto(args.position as i64 - params.number(0, 1))?;
+                } else {
+                    args.goto(args.position as i64 + params.number(0, 1))?;
+                }
+            }
+            '^' => {
+                if args.remaining() == 0 {
+                    return Ok(false);
+                }
+            }
+            '[' => {
+                if let Some(clause) = select_clause(directive, &params, args)? {
+                    if !format_items(clause, args, out)? {
+                        return Ok(false);
+                    }
+                }
+            }
+            '{' => format_iteration(directive, &params, args, out)?,
+            kind => {
+                return Err(RispError::Expr(format!(
+                    "FORMAT: unknown directive ~{}",
+                    kind
+                )))
+            }
+        }
+    }
+    Ok(true)
+}
+
+fn select_clause<'a>(
+    directive: &'a Directive,
+    params: &Params,
+    args: &mut Args,
+) -> Result<Option<&'a Vec<Item>>, RispError> {
+    let clauses = &directive.clauses;
+    if directive.colon {
+        let index = if args.next()? == Expr::Nil { 0 } else { 1 };
+        return Ok(clauses.get(index));
+    }
+    if directive.at {
+        // ~@[ processes its clause with the argument still available when it
+        // is true, and consumes it otherwise.
+        let position = args.position;
+        if args.next()? == Expr::Nil {
+            return Ok(None);
+        }
+        args.goto(position as i64)?;
+        return Ok(clauses.first());
+    }
+    let index = match params.optional_number(0) {
+        Some(index) => index,
+        None => match args.next()? {
+            Expr::Number(Number::Fixnum(index)) => index,
+            x => return Err(RispError::Expr(format!("FORMAT: {} is not integer", x))),
+        },
+    };
+    let choices = clauses.len() - usize::from(directive.has_default);
+    if index >= 0 && (index as usize) < choices {
+        Ok(clauses.get(index as usize))
+    } else if directive.has_default {
+        Ok(clauses.last())
+    } else {
+        Ok(None)
+    }
+}
+
+// ~{ ~} iterates over a list argument, ~:{ ~} over a list of sublists and
+// ~@{ ~} over the remaining arguments.
+fn format_iteration(
+    directive: &Directive,
+    params: &Params,
+    args: &mut Args,
+    out: &mut String,
+) -> Result<(), RispError> {
+    let body = &directive.clauses[0];
+    let max_iterations = params
+        .optional_number(0)
+        .map_or(usize::MAX, |n| n.max(0) as usize);
+    let elements = if directive.at {
+        let rest = args.args[args.position..].to_vec();
+        args.position = args.args.len();
+        rest
+    } else {
+        expect_sequence(&args.next()?)?
+    };
+
+    if directive.colon {
+        for element in elements.iter().take(max_iterations) {
+            let sublist = expect_sequence(element)?;
+            let mut sub_args = Args {
+                args: &sublist,
+                position: 0,
+            };
+            format_items(body, &mut sub_args, out)?;
+        }
+        return Ok(());
+    }
+
+    let mut sub_args = Args {
+        args: &elements,
+        position: 0,
+    };
+    let mut iterations = 0;
+    while sub_args.remaining() > 0 && iterations < max_iterations {
+        let position = sub_args.position;
+        if !format_items(body, &mut sub_args, out)? || sub_args.position == position {
+            break;
+        }
+        iterations += 1;
+    }
+    Ok(())
+}
+
+// Pads `s` to at least `mincol` columns on the right, or on the left when
+// `left` is set, after adding `minpad` padding characters.
+fn pad(s: &str, mincol: i64, minpad: i64, padchar: char, left: bool) -> String {
+    let len = s.chars().count() + minpad.max(0) as usize;
+    let padding = padchar
+        .to_string()
+        .repeat(minpad.max(0) as usize + (mincol.max(0) as usize).saturating_sub(len));
+    if left {
+        format!("{}{}", padding, s)
+    } else {
+        format!("{}{}", s, padding)
+    }
+}
+
+// ~mincol,padchar,commachar,comma-intervalD and the other radix directives.
+fn format_radix(params: &Params, directive: &Directive, arg: Expr, radix: u32, out: &mut String) {
+    let integer = match &arg {
+        Expr::Number(num) => num.to_bigint(),
+        _ => None,
+    };
+    let s = match integer {
+        Some(integer) => {
+            // Digits above 9 are written in uppercase, as CL prints them.
+            let digits = integer.magnitude().to_str_radix(radix).to_uppercase();
+            let digits = if directive.colon {
+                group_digits(
+                    &digits,
+                    params.char(2, ','),
+                    params.number(3, 3).max(1) as usize,
+                )
+            } else {
+                digits
+            };
+            let sign = if integer.sign() == num_bigint::Sign::Minus {
+                "-"
+            } else if directive.at {
+                "+"
+            } else {
+                ""
+            };
+            format!("{}{}", sign, digits)
+        }
+        None => princ_to_string(&arg),
+    };
+    out.push_str(&pad(&s, params.number(0, 0), 0, params.char(1, ' '), true));
+}
+
+fn group_digits(digits: &str, separator: char, interval: usize) -> String {
+    let chars = digits.chars().collect::<Vec<char>>();
+    let mut grouped = String::new();
+    for (i, ch) in chars.iter().enumerate() {
+        if i > 0 && (chars.len() - i) % interval == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(*ch);
+    }
+    grouped
+}
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const SCALES: [&str; 7] = [
+    "",
+    "thousand",
+    "million",
+    "billion",
+    "trillion",
+    "quadrillion",
+    "quintillion",
+];
+
+// ~R prints the number as an English cardinal, ~:R as an ordinal and ~@R in
+// Roman numerals.
+fn english(arg: &Expr, ordinal: bool, roman: bool) -> Result<String, RispError> {
+    let n = match arg {
+        Expr::Number(Number::Fixnum(n)) => *n,
+        _ => return Err(RispError::Expr(format!("FORMAT: {} is not fixnum", arg))),
+    };
+    if roman {
+        return roman_numeral(n);
+    }
+    let cardinal = cardinal(n);
+    if !ordinal {
+        return Ok(cardinal);
+    }
+    let (head, last) = match cardinal.rfind([' ', '-']) {
+        Some(i) => cardinal.split_at(i + 1),
+        None => ("", cardinal.as_str()),
+    };
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        word if word.ends_with('y') => format!("{}ieth", &word[..word.len() - 1]),
+        word => format!("{}th", word),
+    };
+    Ok(format!("{}{}", head, last))
+}
+
+fn cardinal(n: i64) -> String {
+    if n == 0 {
+        return ONES[0].to_string();
+    }
+    let mut magnitude = n.unsigned_abs();
+    let mut groups = Vec::<String>::new();
+    let mut scale = 0;
+    while magnitude > 0 {
+        let group = (magnitude % 1000) as usize;
+        if group > 0 {
+            let words = below_thousand(group);
+            groups.push(match SCALES[scale] {
+                "" => words,
+                name => format!("{} {}", words, name),
+            });
+        }
+        magnitude /= 1000;
+        scale += 1;
+    }
+    groups.reverse();
+    let words = groups.join(" ");
+    if n < 0 {
+        format!("negative {}", words)
+    } else {
+        words
+    }
+}
+
+fn below_thousand(n: usize) -> String {
+    let mut words = Vec::<String>::new();
+    if n >= 100 {
+        words.push(format!("{} hundred", ONES[n / 100]));
+    }
+    match n % 100 {
+        0 => {}
+        rest if rest < 20 => words.push(ONES[rest].to_string()),
+        rest if rest % 10 == 0 => words.push(TENS[rest / 10].to_string()),
+        rest => words.push(format!("{}-{}", TENS[rest / 10], ONES[rest % 10])),
+    }
+    words.join(" ")
+}
+
+fn roman_numeral(n: i64) -> Result<String, RispError> {
+    if !(1..=3999).contains(&n) {
+        return Err(RispError::Expr(format!(
+            "FORMAT: {} cannot be printed in Roman numerals",
+            n
+        )));
+    }
+    let numerals = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut rest = n;
+    let mut s = String::new();
+    for (value, numeral) in numerals {
+        while rest >= value {
+            s.push_str(numeral);
+            rest -= value;
+        }
+    }
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn num(n: i64) -> Expr {
+        Expr::Number(Number::Fixnum(n))
+    }
+
+    #[test]
+    fn format_directives() {
+        let list = Expr::List(vec![num(1), num(2), num(3)].into());
+        let tests = vec![
+            (
+                "~a and ~s",
+                vec![Expr::String("a".into()), Expr::String("s".into())],
+                r#"a and "s""#,
+            ),
+            ("~5a|~5@a|", vec![num(1), num(2)], "1    |    2|"),
+            (
+                "~d ~:d ~@d",
+                vec![num(42), num(1234567), num(5)],
+                "42 1,234,567 +5",
+            ),
+            ("~5,'0d", vec![num(42)], "00042"),
+            ("~x ~b ~8r", vec![num(255), num(5), num(8)], "FF 101 10"),
+            ("~36r ~:x", vec![num(35), num(-65535)], "Z -F,FFF"),
+            (
+                "~,2f ~f",
+                vec![Expr::Number(Number::Float(1.23456)), num(2)],
+                "1.23 2.0",
+            ),
+            ("a~%b~&~&c~~", vec![], "a\nb\nc~"),
+            ("~{~a~^, ~}", vec![list.clone()], "1, 2, 3"),
+            ("~@{<~a>~}", vec![num(1), num(2)], "<1><2>"),
+            ("~[zero~;one~:;many~]", vec![num(5)], "many"),
+            ("~[zero~;one~]", vec![num(1)], "one"),
+            ("~:[no~;yes~]", vec![Expr::True], "yes"),
+            ("~@[x=~a~]", vec![num(3)], "x=3"),
+            ("~a ~:*~a", vec![num(7)], "7 7"),
+            (
+                "~d item~:p, ~d pon~:@p",
+                vec![num(1), num(2)],
+                "1 item, 2 ponies",
+            ),
+            (
+                "~r",
+                vec![num(1234)],
+                "one thousand two hundred thirty-four",
+            ),
+            ("~:r ~@r", vec![num(42), num(1999)], "forty-second MCMXCIX"),
+        ];
+        for (control, args, want) in tests {
+            assert_eq!(
+                format_to_string(control, &args).unwrap(),
+                want,
+                "{}",
+                control
+            );
+        }
+    }
+
+    #[test]
+    fn format_errors() {
+        assert!(format_to_string("~a", &[]).is_err());
+        assert!(format_to_string("~{~a", &[Expr::Nil]).is_err());
+        assert!(format_to_string("~q", &[num(1)]).is_err());
+    }
+}
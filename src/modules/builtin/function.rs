@@ -0,0 +1,82 @@
+use super::{boolean, define_native};
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::eval::{resolve_function, Evaluator, ExprEnv};
+
+pub fn register(env: &mut ExprEnv) {
+    define_native(env, "FUNCALL", 1, usize::MAX, funcall);
+    define_native(env, "APPLY", 2, usize::MAX, apply);
+    define_native(env, "FBOUNDP", 1, 1, fboundp);
+    define_native(env, "FMAKUNBOUND", 1, 1, fmakunbound);
+    define_native(env, "SYMBOL-FUNCTION", 1, 1, symbol_function);
+    define_native(env, "BOUNDP", 1, 1, boundp);
+    define_native(env, "SYMBOL-VALUE", 1, 1, symbol_value);
+}
+
+fn expect_symbol(x: &Expr) -> Result<&str, RispError> {
+    match x {
+        Expr::Symbol(sym) => Ok(sym),
+        _ => Err(RispError::Expr(format!("{} is not symbol", x))),
+    }
+}
+
+// (funcall function args...)
+fn funcall(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let func = resolve_function(&args[0], env)?;
+    ev.call_function(&func, &args[1..], env)
+}
+
+// (apply function args... list) calling the function with the elements of
+// the last argument after the other arguments.
+fn apply(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let func = resolve_function(&args[0], env)?;
+    let (last, spread) = args[1..].split_last().unwrap();
+    let mut spread = spread.to_vec();
+    match last {
+        Expr::List(list) => spread.extend(list.iter().cloned()),
+        Expr::Nil => {}
+        _ => return Err(RispError::Expr(format!("APPLY: {} is not list", last))),
+    }
+    ev.call_function(&func, &spread, env)
+}
+
+fn fboundp(_ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    Ok(boolean(env.is_fbound(expect_symbol(&args[0])?)))
+}
+
+fn fmakunbound(_ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    env.remove_function(expect_symbol(&args[0])?);
+    Ok(args[0].clone())
+}
+
+fn symbol_function(
+    _ev: &mut Evaluator,
+    args: &[Expr],
+    env: &mut ExprEnv,
+) -> Result<Expr, RispError> {
+    let name = expect_symbol(&args[0])?;
+    env.get_global_function(name)
+        .ok_or_else(|| RispError::Expr(format!("undefined function: {}", name)))
+}
+
+// T, NIL and keywords are constants whose value is themselves. Lexical
+// variables are not seen by BOUNDP and SYMBOL-VALUE.
+fn boundp(_ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    match &args[0] {
+        Expr::True | Expr::Nil => Ok(Expr::True),
+        Expr::Symbol(sym) if sym.starts_with(':') => Ok(Expr::True),
+        x => Ok(boolean(env.is_defined(expect_symbol(x)?))),
+    }
+}
+
+fn symbol_value(_ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    match &args[0] {
+        Expr::True | Expr::Nil => Ok(args[0].clone()),
+        Expr::Symbol(sym) if sym.starts_with(':') => Ok(args[0].clone()),
+        x => {
+            let name = expect_symbol(x)?;
+            env.get_global(name)
+                .ok_or_else(|| RispError::Expr(format!("unbound variable: {}", name)))
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use super::printer::output_stream;
+use super::{define_native, index_expr};
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::eval::{Evaluator, ExprEnv};
+use crate::modules::gc;
+
+pub fn register(env: &mut ExprEnv) {
+    define_native(env, "GC", 0, 0, collect);
+    define_native(env, "ROOM", 0, 0, room);
+}
+
+// (gc) frees the unreachable cycles, returning the number of objects still
+// alive and the number freed.
+fn collect(ev: &mut Evaluator, _args: &[Expr], _env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let collection = gc::collect();
+    Ok(ev.values(vec![
+        index_expr(collection.live),
+        index_expr(collection.freed),
+    ]))
+}
+
+// (room) prints the number of objects of each kind on the heap, including
+// garbage not collected yet, and returns their total.
+fn room(_ev: &mut Evaluator, _args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let counts = gc::room();
+    let total = counts.iter().map(|(_, count)| count).sum::<usize>();
+    let mut report = String::new();
+    for (kind, count) in counts {
+        report.push_str(&format!("{:>8} {}\n", count, kind));
+    }
+    report.push_str(&format!("{:>8} objects\n", total));
+    output_stream(None, env)?.write_str(&report)?;
+    Ok(index_expr(total))
+}
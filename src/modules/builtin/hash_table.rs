@@ -0,0 +1,70 @@
+use super::{boolean, define, define_native, index_expr, keyword_args};
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::eval::{Evaluator, ExprEnv};
+use crate::modules::gc::alloc;
+use crate::modules::hash_table::{expect_hash_table, HashTable, HashTest};
+
+pub fn register(env: &mut ExprEnv) {
+    define(env, "MAKE-HASH-TABLE", 0, usize::MAX, make_hash_table);
+    define_native(env, "GETHASH", 2, 3, gethash);
+    define(env, "REMHASH", 2, 2, remhash);
+    define(env, "CLRHASH", 1, 1, clrhash);
+    define(env, "HASH-TABLE-COUNT", 1, 1, hash_table_count);
+    define_native(env, "MAPHASH", 2, 2, maphash);
+}
+
+// (make-hash-table &key (test 'eql)) where the test is one of EQ, EQL, EQUAL
+// and EQUALP, named or given as the function.
+fn make_hash_table(args: &[Expr]) -> Result<Expr, RispError> {
+    let keywords = keyword_args("MAKE-HASH-TABLE", args, &[":TEST"])?;
+    let test = match keywords.get(":TEST") {
+        None => Some(HashTest::Eql),
+        Some(Expr::Symbol(name)) => HashTest::from_name(name),
+        Some(Expr::Func(native)) => HashTest::from_name(&native.name),
+        Some(_) => None,
+    };
+    let test = test.ok_or_else(|| {
+        RispError::Expr(format!(
+            "MAKE-HASH-TABLE: unsupported test {}",
+            keywords[":TEST"]
+        ))
+    })?;
+    Ok(Expr::HashTable(alloc(HashTable::new(test))))
+}
+
+// (gethash key hash-table &optional default)
+// (gethash key hash-table &optional default) returning whether the key was
+// found as the second value.
+fn gethash(ev: &mut Evaluator, args: &[Expr], _env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let table = expect_hash_table(&args[1])?;
+    let value = table.borrow().get(&args[0]).cloned();
+    let found = boolean(value.is_some());
+    let value = value.unwrap_or_else(|| args.get(2).cloned().unwrap_or(Expr::Nil));
+    Ok(ev.values(vec![value, found]))
+}
+
+fn remhash(args: &[Expr]) -> Result<Expr, RispError> {
+    let table = expect_hash_table(&args[1])?;
+    let removed = table.borrow_mut().remove(&args[0]);
+    Ok(boolean(removed))
+}
+
+fn clrhash(args: &[Expr]) -> Result<Expr, RispError> {
+    expect_hash_table(&args[0])?.borrow_mut().clear();
+    Ok(args[0].clone())
+}
+
+fn hash_table_count(args: &[Expr]) -> Result<Expr, RispError> {
+    let count = expect_hash_table(&args[0])?.borrow().len();
+    Ok(index_expr(count))
+}
+
+// (maphash function hash-table)
+fn maphash(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let entries = expect_hash_table(&args[1])?.borrow().entries();
+    for (key, value) in entries {
+        ev.funcall(&args[0], &[key, value], env)?;
+    }
+    Ok(Expr::Nil)
+}
@@ -0,0 +1,40 @@
+use super::{boolean, define_native, expect_string};
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::eval::{provide, Evaluator, ExprEnv};
+use crate::modules::package::designated_name;
+use std::path::{Path, PathBuf};
+
+pub fn register(env: &mut ExprEnv) {
+    env.define("*MODULES*", Expr::Nil);
+    env.proclaim_special("*MODULES*");
+    define_native(env, "LOAD", 1, 1, load);
+    define_native(env, "REQUIRE", 1, 2, require);
+    define_native(env, "PROVIDE", 1, 1, |_, args, env| {
+        provide(&designated_name(&args[0])?, env)?;
+        Ok(Expr::True)
+    });
+}
+
+// (load path)
+fn load(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    ev.load(Path::new(expect_string(&args[0])?), env)?;
+    Ok(Expr::True)
+}
+
+// (require name &optional path-or-paths) returning whether the module was
+// loaded.
+fn require(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let name = designated_name(&args[0])?;
+    let files = match args.get(1) {
+        None | Some(Expr::Nil) => None,
+        Some(Expr::List(paths)) => Some(
+            paths
+                .iter()
+                .map(|path| Ok(PathBuf::from(expect_string(path)?)))
+                .collect::<Result<Vec<_>, RispError>>()?,
+        ),
+        Some(path) => Some(vec![PathBuf::from(expect_string(path)?)]),
+    };
+    Ok(boolean(ev.require(&name, files, env)?))
+}
@@ -0,0 +1,43 @@
+use super::define_native;
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::eval::ExprEnv;
+use crate::modules::number::{Number, Rounding};
+
+pub fn register(env: &mut ExprEnv) {
+    let divisions = [
+        ("FLOOR", Rounding::Floor),
+        ("CEILING", Rounding::Ceiling),
+        ("TRUNCATE", Rounding::Truncate),
+        ("ROUND", Rounding::Round),
+    ];
+    for (name, rounding) in divisions {
+        define_native(env, name, 1, 2, move |evaluator, args, _| {
+            let (quotient, remainder) = divide(args, rounding)?;
+            Ok(evaluator.values(vec![Expr::Number(quotient), Expr::Number(remainder)]))
+        });
+    }
+    define_native(env, "MOD", 2, 2, |_, args, _| {
+        Ok(Expr::Number(divide(args, Rounding::Floor)?.1))
+    });
+    define_native(env, "REM", 2, 2, |_, args, _| {
+        Ok(Expr::Number(divide(args, Rounding::Truncate)?.1))
+    });
+}
+
+// (floor number &optional (divisor 1)) and the like.
+fn divide(args: &[Expr], rounding: Rounding) -> Result<(Number, Number), RispError> {
+    let number = expect_number(&args[0])?;
+    let divisor = match args.get(1) {
+        Some(divisor) => expect_number(divisor)?,
+        None => Number::Fixnum(1),
+    };
+    number.divide(&divisor, rounding)
+}
+
+fn expect_number(x: &Expr) -> Result<Number, RispError> {
+    match x {
+        Expr::Number(num) => Ok(num.clone()),
+        _ => Err(RispError::Expr(format!("{} is not number", x))),
+    }
+}
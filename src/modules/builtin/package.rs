@@ -0,0 +1,25 @@
+use super::{define, define_native};
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::eval::{Evaluator, ExprEnv};
+use crate::modules::package::{designated_name, expect_package};
+
+pub fn register(env: &mut ExprEnv) {
+    define_native(env, "FIND-PACKAGE", 1, 1, find_package);
+    define(env, "PACKAGE-NAME", 1, 1, package_name);
+}
+
+// (find-package name) returning NIL when there is no such package.
+fn find_package(_ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let name = designated_name(&args[0])?;
+    match env.packages().borrow().find(&name) {
+        Some(package) => Ok(Expr::Package(package)),
+        None => Ok(Expr::Nil),
+    }
+}
+
+fn package_name(args: &[Expr]) -> Result<Expr, RispError> {
+    let package = expect_package(&args[0])?;
+    let name = package.borrow().name.clone();
+    Ok(Expr::string(name))
+}
@@ -0,0 +1,83 @@
+use super::{define, define_native, expect_string};
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::eval::{Evaluator, ExprEnv};
+use crate::modules::printer::{prin1_to_string, princ_to_string};
+use crate::modules::stream::Stream;
+
+pub fn register(env: &mut ExprEnv) {
+    env.define("*STANDARD-OUTPUT*", Expr::Stream(Stream::Stdout));
+    env.proclaim_special("*STANDARD-OUTPUT*");
+    define_native(env, "PRINT", 1, 2, print);
+    define_native(env, "PRIN1", 1, 2, prin1);
+    define_native(env, "PRINC", 1, 2, princ);
+    define_native(env, "TERPRI", 0, 1, terpri);
+    define_native(env, "WRITE-STRING", 1, 2, write_string);
+    define(
+        env,
+        "MAKE-STRING-OUTPUT-STREAM",
+        0,
+        0,
+        make_string_output_stream,
+    );
+    define(
+        env,
+        "GET-OUTPUT-STREAM-STRING",
+        1,
+        1,
+        get_output_stream_string,
+    );
+}
+
+// The optional stream argument of the printing functions, where NIL and T
+// both designate the current value of *STANDARD-OUTPUT*.
+pub fn output_stream(arg: Option<&Expr>, env: &ExprEnv) -> Result<Stream, RispError> {
+    match arg {
+        None | Some(Expr::Nil) | Some(Expr::True) => match env.get("*STANDARD-OUTPUT*") {
+            Some(Expr::Stream(stream)) => Ok(stream),
+            _ => Ok(Stream::Stdout),
+        },
+        Some(Expr::Stream(stream)) => Ok(stream.clone()),
+        Some(x) => Err(RispError::Expr(format!("{} is not stream", x))),
+    }
+}
+
+// Writes a newline, the readable representation and a space.
+fn print(_: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    output_stream(args.get(1), env)?.write_str(&format!("\n{} ", prin1_to_string(&args[0])))?;
+    Ok(args[0].clone())
+}
+
+fn prin1(_: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    output_stream(args.get(1), env)?.write_str(&prin1_to_string(&args[0]))?;
+    Ok(args[0].clone())
+}
+
+fn princ(_: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    output_stream(args.get(1), env)?.write_str(&princ_to_string(&args[0]))?;
+    Ok(args[0].clone())
+}
+
+fn terpri(_: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    output_stream(args.first(), env)?.write_str("\n")?;
+    Ok(Expr::Nil)
+}
+
+fn write_string(_: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    output_stream(args.get(1), env)?.write_str(expect_string(&args[0])?)?;
+    Ok(args[0].clone())
+}
+
+fn make_string_output_stream(_args: &[Expr]) -> Result<Expr, RispError> {
+    Ok(Expr::Stream(Stream::string_output()))
+}
+
+fn get_output_stream_string(args: &[Expr]) -> Result<Expr, RispError> {
+    match &args[0] {
+        Expr::Stream(stream) => stream
+            .take_string()
+            .map(Expr::string)
+            .ok_or_else(|| RispError::Expr(format!("{} is not string output stream", args[0]))),
+        x => Err(RispError::Expr(format!("{} is not stream", x))),
+    }
+}
@@ -0,0 +1,353 @@
+use super::{define_native, expect_sequence, index_expr, keyword_args, list, sequence_like};
+use crate::modules::array::expect_array;
+use crate::modules::ast::Expr;
+use crate::modules::equality::eql;
+use crate::modules::error::RispError;
+use crate::modules::eval::{Evaluator, ExprEnv};
+use std::collections::HashMap;
+
+pub fn register(env: &mut ExprEnv) {
+    define_native(env, "MAPCAR", 2, usize::MAX, mapcar);
+    define_native(env, "MAPC", 2, usize::MAX, mapc);
+    define_native(env, "MAPCAN", 2, usize::MAX, mapcan);
+    define_native(env, "REDUCE", 2, 8, reduce);
+    define_native(env, "REMOVE", 2, 6, remove);
+    define_native(env, "REMOVE-IF", 2, 4, remove_if);
+    define_native(env, "REMOVE-IF-NOT", 2, 4, remove_if_not);
+    define_native(env, "FIND", 2, 6, find);
+    define_native(env, "FIND-IF", 2, 4, find_if);
+    define_native(env, "COUNT-IF", 2, 4, count_if);
+    define_native(env, "EVERY", 2, usize::MAX, every);
+    define_native(env, "SOME", 2, usize::MAX, some);
+    define_native(env, "SORT", 2, 4, sort);
+    define_native(env, "ASSOC", 2, 6, assoc);
+    define_native(env, "MEMBER", 2, 6, member);
+}
+
+// The `:test` and `:key` keyword arguments shared by the item searching
+// functions.
+struct ItemTest {
+    test: Option<Expr>,
+    key: Option<Expr>,
+}
+
+impl ItemTest {
+    fn new(keywords: &HashMap<String, Expr>) -> Self {
+        Self {
+            test: keywords.get(":TEST").cloned(),
+            key: keywords.get(":KEY").cloned(),
+        }
+    }
+}
+
+fn call_key(
+    ev: &mut Evaluator,
+    key: &Option<Expr>,
+    x: &Expr,
+    env: &mut ExprEnv,
+) -> Result<Expr, RispError> {
+    match key {
+        Some(key) => ev.funcall(key, std::slice::from_ref(x), env),
+        None => Ok(x.clone()),
+    }
+}
+
+fn satisfies(
+    ev: &mut Evaluator,
+    predicate: &Expr,
+    key: &Option<Expr>,
+    x: &Expr,
+    env: &mut ExprEnv,
+) -> Result<bool, RispError> {
+    let x = call_key(ev, key, x, env)?;
+    Ok(ev.funcall(predicate, &[x], env)? != Expr::Nil)
+}
+
+fn matches(
+    ev: &mut Evaluator,
+    item: &Expr,
+    item_test: &ItemTest,
+    x: &Expr,
+    env: &mut ExprEnv,
+) -> Result<bool, RispError> {
+    let x = call_key(ev, &item_test.key, x, env)?;
+    match &item_test.test {
+        Some(test) => Ok(ev.funcall(test, &[item.clone(), x], env)? != Expr::Nil),
+        None => Ok(eql(item, &x)),
+    }
+}
+
+// Calls the function with the n-th elements of each sequence, stopping at
+// the end of the shortest one.
+fn map_sequences(
+    ev: &mut Evaluator,
+    args: &[Expr],
+    env: &mut ExprEnv,
+) -> Result<Vec<Expr>, RispError> {
+    let sequences = args[1..]
+        .iter()
+        .map(expect_sequence)
+        .collect::<Result<Vec<_>, _>>()?;
+    let length = sequences.iter().map(Vec::len).min().unwrap_or(0);
+    (0..length)
+        .map(|i| {
+            let xs = sequences
+                .iter()
+                .map(|seq| seq[i].clone())
+                .collect::<Vec<_>>();
+            ev.funcall(&args[0], &xs, env)
+        })
+        .collect()
+}
+
+// (mapcar function sequence &rest sequences)
+fn mapcar(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    Ok(list(map_sequences(ev, args, env)?))
+}
+
+// Like MAPCAR but only for side effects, returning the first sequence.
+fn mapc(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    map_sequences(ev, args, env)?;
+    Ok(args[1].clone())
+}
+
+// Like MAPCAR but appends the lists returned by the function.
+fn mapcan(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let mut elements = vec![];
+    for result in map_sequences(ev, args, env)? {
+        elements.extend(expect_sequence(&result)?);
+    }
+    Ok(list(elements))
+}
+
+// (reduce function sequence &key initial-value from-end key)
+fn reduce(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let keywords = keyword_args(
+        "REDUCE",
+        &args[2..],
+        &[":INITIAL-VALUE", ":FROM-END", ":KEY"],
+    )?;
+    let key = keywords.get(":KEY").cloned();
+    let from_end = keywords.get(":FROM-END").is_some_and(|x| *x != Expr::Nil);
+    let mut elements = expect_sequence(&args[1])?
+        .iter()
+        .map(|x| call_key(ev, &key, x, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    if from_end {
+        elements.reverse();
+    }
+    let mut elements = elements.into_iter();
+    let mut acc = match keywords.get(":INITIAL-VALUE").cloned() {
+        Some(initial) => initial,
+        None => match elements.next() {
+            Some(first) => first,
+            None => return ev.funcall(&args[0], &[], env),
+        },
+    };
+    for x in elements {
+        let pair = if from_end { [x, acc] } else { [acc, x] };
+        acc = ev.funcall(&args[0], &pair, env)?;
+    }
+    Ok(acc)
+}
+
+// Keeps the elements for which `keep` returns true, in a sequence of the
+// same kind.
+fn filter(
+    ev: &mut Evaluator,
+    sequence: &Expr,
+    env: &mut ExprEnv,
+    mut keep: impl FnMut(&mut Evaluator, &Expr, &mut ExprEnv) -> Result<bool, RispError>,
+) -> Result<Expr, RispError> {
+    let mut elements = vec![];
+    for x in expect_sequence(sequence)? {
+        if keep(ev, &x, env)? {
+            elements.push(x);
+        }
+    }
+    sequence_like(sequence, elements)
+}
+
+// (remove item sequence &key test key)
+fn remove(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let item_test = ItemTest::new(&keyword_args("REMOVE", &args[2..], &[":TEST", ":KEY"])?);
+    filter(ev, &args[1], env, |this, x, env| {
+        Ok(!matches(this, &args[0], &item_test, x, env)?)
+    })
+}
+
+// (remove-if predicate sequence &key key)
+fn remove_if(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let key = keyword_args("REMOVE-IF", &args[2..], &[":KEY"])?.remove(":KEY");
+    filter(ev, &args[1], env, |this, x, env| {
+        Ok(!satisfies(this, &args[0], &key, x, env)?)
+    })
+}
+
+fn remove_if_not(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let key = keyword_args("REMOVE-IF-NOT", &args[2..], &[":KEY"])?.remove(":KEY");
+    filter(ev, &args[1], env, |this, x, env| {
+        satisfies(this, &args[0], &key, x, env)
+    })
+}
+
+// (find item sequence &key test key)
+fn find(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let item_test = ItemTest::new(&keyword_args("FIND", &args[2..], &[":TEST", ":KEY"])?);
+    for x in expect_sequence(&args[1])? {
+        if matches(ev, &args[0], &item_test, &x, env)? {
+            return Ok(x);
+        }
+    }
+    Ok(Expr::Nil)
+}
+
+// (find-if predicate sequence &key key)
+fn find_if(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let key = keyword_args("FIND-IF", &args[2..], &[":KEY"])?.remove(":KEY");
+    for x in expect_sequence(&args[1])? {
+        if satisfies(ev, &args[0], &key, &x, env)? {
+            return Ok(x);
+        }
+    }
+    Ok(Expr::Nil)
+}
+
+// (count-if predicate sequence &key key)
+fn count_if(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let key = keyword_args("COUNT-IF", &args[2..], &[":KEY"])?.remove(":KEY");
+    let mut count = 0;
+    for x in expect_sequence(&args[1])? {
+        if satisfies(ev, &args[0], &key, &x, env)? {
+            count += 1;
+        }
+    }
+    Ok(index_expr(count))
+}
+
+// (every predicate sequence &rest sequences)
+fn every(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let sequences = args[1..]
+        .iter()
+        .map(expect_sequence)
+        .collect::<Result<Vec<_>, _>>()?;
+    let length = sequences.iter().map(Vec::len).min().unwrap_or(0);
+    for i in 0..length {
+        let xs = sequences
+            .iter()
+            .map(|seq| seq[i].clone())
+            .collect::<Vec<_>>();
+        if ev.funcall(&args[0], &xs, env)? == Expr::Nil {
+            return Ok(Expr::Nil);
+        }
+    }
+    Ok(Expr::True)
+}
+
+// (some predicate sequence &rest sequences) returns the first true value
+// of the predicate.
+fn some(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let sequences = args[1..]
+        .iter()
+        .map(expect_sequence)
+        .collect::<Result<Vec<_>, _>>()?;
+    let length = sequences.iter().map(Vec::len).min().unwrap_or(0);
+    for i in 0..length {
+        let xs = sequences
+            .iter()
+            .map(|seq| seq[i].clone())
+            .collect::<Vec<_>>();
+        let result = ev.funcall(&args[0], &xs, env)?;
+        if result != Expr::Nil {
+            return Ok(result);
+        }
+    }
+    Ok(Expr::Nil)
+}
+
+// (sort sequence predicate &key key) is a stable merge sort. Vectors are
+// sorted in place, lists and strings are returned as new sequences.
+fn sort(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let key = keyword_args("SORT", &args[2..], &[":KEY"])?.remove(":KEY");
+    let elements = expect_sequence(&args[0])?;
+    let keyed = elements
+        .into_iter()
+        .map(|x| Ok((call_key(ev, &key, &x, env)?, x)))
+        .collect::<Result<Vec<_>, RispError>>()?;
+    let sorted = merge_sort(ev, keyed, &args[1], env)?
+        .into_iter()
+        .map(|(_, x)| x)
+        .collect::<Vec<_>>();
+    match &args[0] {
+        Expr::Vector(_) => {
+            let vector = expect_array(&args[0])?;
+            let mut vector = vector.borrow_mut();
+            for (i, x) in sorted.into_iter().enumerate() {
+                vector.set(&[i], x)?;
+            }
+            Ok(args[0].clone())
+        }
+        sequence => sequence_like(sequence, sorted),
+    }
+}
+
+fn merge_sort(
+    ev: &mut Evaluator,
+    mut elements: Vec<(Expr, Expr)>,
+    predicate: &Expr,
+    env: &mut ExprEnv,
+) -> Result<Vec<(Expr, Expr)>, RispError> {
+    if elements.len() <= 1 {
+        return Ok(elements);
+    }
+    let right = elements.split_off(elements.len() / 2);
+    let left = merge_sort(ev, elements, predicate, env)?;
+    let right = merge_sort(ev, right, predicate, env)?;
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(x), Some(y)) = (left.peek(), right.peek()) {
+        // Take from the right only when strictly less, to keep equal
+        // elements in their original order.
+        let right_first = ev.funcall(predicate, &[y.0.clone(), x.0.clone()], env)? != Expr::Nil;
+        merged.extend(if right_first {
+            right.next()
+        } else {
+            left.next()
+        });
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+// (assoc item alist &key test key) returns the first pair whose car
+// matches the item.
+fn assoc(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let item_test = ItemTest::new(&keyword_args("ASSOC", &args[2..], &[":TEST", ":KEY"])?);
+    for pair in expect_sequence(&args[1])? {
+        match &pair {
+            Expr::Nil => continue,
+            Expr::List(exprs) => {
+                if matches(ev, &args[0], &item_test, &exprs[0], env)? {
+                    return Ok(pair);
+                }
+            }
+            _ => return Err(RispError::Expr(format!("{} is not list", pair))),
+        }
+    }
+    Ok(Expr::Nil)
+}
+
+// (member item list &key test key) returns the tail of the list starting
+// with the first matching element.
+fn member(ev: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let item_test = ItemTest::new(&keyword_args("MEMBER", &args[2..], &[":TEST", ":KEY"])?);
+    let elements = expect_sequence(&args[1])?;
+    for (i, x) in elements.iter().enumerate() {
+        if matches(ev, &args[0], &item_test, x, env)? {
+            return Ok(list(elements[i..].to_vec()));
+        }
+    }
+    Ok(Expr::Nil)
+}
@@ -0,0 +1,204 @@
+use super::{
+    boolean, define, define_native, expect_char, expect_index, expect_sequence, expect_string,
+    expect_string_designator, index_expr, keyword_args, list, sequence_like,
+};
+use crate::modules::array::Array;
+use crate::modules::ast::Expr;
+use crate::modules::equality::eql;
+use crate::modules::error::RispError;
+use crate::modules::eval::{Evaluator, ExprEnv};
+use crate::modules::number::Number;
+use crate::modules::printer;
+use num_bigint::BigInt;
+
+pub fn register(env: &mut ExprEnv) {
+    define(env, "CONCATENATE", 1, usize::MAX, concatenate);
+    define(env, "SUBSEQ", 2, 3, subseq);
+    define(env, "STRING-UPCASE", 1, 1, string_upcase);
+    define(env, "STRING-DOWNCASE", 1, 1, string_downcase);
+    define(env, "STRING-TRIM", 2, 2, string_trim);
+    define(env, "STRING=", 2, 2, string_eq);
+    define(env, "STRING<", 2, 2, string_lt);
+    define(env, "SEARCH", 2, 2, search);
+    define(env, "POSITION", 2, 2, position);
+    define_native(env, "PARSE-INTEGER", 1, 5, parse_integer);
+    define(env, "PRIN1-TO-STRING", 1, 1, prin1_to_string);
+    define(env, "STRING", 1, 1, string);
+    define(env, "SPLIT-STRING", 1, 2, split_string);
+}
+
+fn chars_to_string(exprs: &[Expr]) -> Result<String, RispError> {
+    exprs.iter().map(expect_char).collect()
+}
+
+// (concatenate 'string "a" "b"), (concatenate 'list '(1) "ab") or
+// (concatenate 'vector #(1) '(2))
+fn concatenate(args: &[Expr]) -> Result<Expr, RispError> {
+    let mut elements = Vec::<Expr>::new();
+    for arg in &args[1..] {
+        elements.extend(expect_sequence(arg)?);
+    }
+    match &args[0] {
+        Expr::Symbol(sym) if &**sym == "STRING" => Ok(Expr::string(chars_to_string(&elements)?)),
+        Expr::Symbol(sym) if &**sym == "LIST" => Ok(list(elements)),
+        Expr::Symbol(sym) if &**sym == "VECTOR" => Ok(Array::from_vec(elements).into_expr()),
+        result_type => Err(RispError::Expr(format!(
+            "CONCATENATE: unsupported result type {}",
+            result_type
+        ))),
+    }
+}
+
+// (subseq sequence start &optional end)
+fn subseq(args: &[Expr]) -> Result<Expr, RispError> {
+    let elements = expect_sequence(&args[0])?;
+    let start = expect_index(&args[1])?;
+    let end = match args.get(2) {
+        Some(Expr::Nil) | None => elements.len(),
+        Some(end) => expect_index(end)?,
+    };
+    if start > end || end > elements.len() {
+        return Err(RispError::Expr(format!(
+            "SUBSEQ: bounding indices {} and {} are bad for length {}",
+            start,
+            end,
+            elements.len()
+        )));
+    }
+    sequence_like(&args[0], elements[start..end].to_vec())
+}
+
+fn string_upcase(args: &[Expr]) -> Result<Expr, RispError> {
+    Ok(Expr::string(
+        expect_string_designator(&args[0])?.to_uppercase(),
+    ))
+}
+
+fn string_downcase(args: &[Expr]) -> Result<Expr, RispError> {
+    Ok(Expr::string(
+        expect_string_designator(&args[0])?.to_lowercase(),
+    ))
+}
+
+// (string-trim char-bag string)
+fn string_trim(args: &[Expr]) -> Result<Expr, RispError> {
+    let bag = chars_to_string(&expect_sequence(&args[0])?)?;
+    let s = expect_string_designator(&args[1])?;
+    Ok(Expr::string(s.trim_matches(|ch| bag.contains(ch))))
+}
+
+fn string_eq(args: &[Expr]) -> Result<Expr, RispError> {
+    let a = expect_string_designator(&args[0])?;
+    let b = expect_string_designator(&args[1])?;
+    Ok(boolean(a == b))
+}
+
+// Returns the index of the first mismatching character when the first string
+// is less than the second, otherwise NIL.
+fn string_lt(args: &[Expr]) -> Result<Expr, RispError> {
+    let a = expect_string_designator(&args[0])?;
+    let b = expect_string_designator(&args[1])?;
+    if a >= b {
+        return Ok(Expr::Nil);
+    }
+    let mismatch = a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count();
+    Ok(index_expr(mismatch))
+}
+
+// (search sequence1 sequence2) returns the index of the first occurrence of
+// sequence1 within sequence2.
+fn search(args: &[Expr]) -> Result<Expr, RispError> {
+    let needle = expect_sequence(&args[0])?;
+    let haystack = expect_sequence(&args[1])?;
+    if needle.is_empty() {
+        return Ok(index_expr(0));
+    }
+    Ok(haystack
+        .windows(needle.len())
+        .position(|window| window.iter().zip(&needle).all(|(x, y)| eql(x, y)))
+        .map_or(Expr::Nil, index_expr))
+}
+
+// (position item sequence)
+fn position(args: &[Expr]) -> Result<Expr, RispError> {
+    let elements = expect_sequence(&args[1])?;
+    Ok(elements
+        .iter()
+        .position(|x| eql(x, &args[0]))
+        .map_or(Expr::Nil, index_expr))
+}
+
+// (parse-integer string &key radix junk-allowed)
+fn parse_integer(ev: &mut Evaluator, args: &[Expr], _env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let s = expect_string(&args[0])?;
+    let keywords = keyword_args("PARSE-INTEGER", &args[1..], &[":RADIX", ":JUNK-ALLOWED"])?;
+    let radix = match keywords.get(":RADIX") {
+        Some(radix) => expect_index(radix)? as u32,
+        None => 10,
+    };
+    if !(2..=36).contains(&radix) {
+        return Err(RispError::Expr(format!(
+            "PARSE-INTEGER: invalid radix {}",
+            radix
+        )));
+    }
+    let junk_allowed = keywords
+        .get(":JUNK-ALLOWED")
+        .is_some_and(|x| *x != Expr::Nil);
+
+    let trimmed = s.trim();
+    let (negative, unsigned) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let digits = unsigned
+        .chars()
+        .take_while(|ch| ch.is_digit(radix))
+        .collect::<String>();
+    // The second value is the index where parsing stopped.
+    let end = if digits.len() == unsigned.len() {
+        s.chars().count()
+    } else {
+        let leading = s.chars().count() - s.trim_start().chars().count();
+        leading + (trimmed.len() - unsigned.len()) + digits.len()
+    };
+    if digits.is_empty() && junk_allowed {
+        return Ok(ev.values(vec![Expr::Nil, index_expr(end)]));
+    }
+    if digits.is_empty() || (digits.len() != unsigned.len() && !junk_allowed) {
+        return Err(RispError::Expr(format!(
+            "PARSE-INTEGER: junk in string {}",
+            printer::prin1_to_string(&args[0])
+        )));
+    }
+    let num = BigInt::parse_bytes(digits.as_bytes(), radix)
+        .ok_or_else(|| RispError::Expr(format!("PARSE-INTEGER: cannot parse {}", digits)))?;
+    let num = Number::from_bigint(if negative { -num } else { num });
+    Ok(ev.values(vec![Expr::Number(num), index_expr(end)]))
+}
+
+fn prin1_to_string(args: &[Expr]) -> Result<Expr, RispError> {
+    Ok(Expr::string(printer::prin1_to_string(&args[0])))
+}
+
+fn string(args: &[Expr]) -> Result<Expr, RispError> {
+    Ok(Expr::string(expect_string_designator(&args[0])?))
+}
+
+// (split-string string &optional (separator #\Space)) where the separator is
+// either a character or a string.
+fn split_string(args: &[Expr]) -> Result<Expr, RispError> {
+    let s = expect_string(&args[0])?;
+    let separator = match args.get(1) {
+        Some(separator) => expect_string_designator(separator)?,
+        None => " ".to_string(),
+    };
+    if separator.is_empty() {
+        return Err(RispError::Expr(
+            "SPLIT-STRING: separator must not be empty".to_string(),
+        ));
+    }
+    Ok(list(
+        s.split(separator.as_str()).map(Expr::string).collect(),
+    ))
+}
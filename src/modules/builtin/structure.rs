@@ -0,0 +1,66 @@
+use super::{boolean, define_native, keyword_args};
+use crate::modules::ast::Expr;
+use crate::modules::eval::ExprEnv;
+use crate::modules::gc::alloc;
+use crate::modules::structure::{expect_structure, StructType, Structure};
+use std::rc::Rc;
+
+// Defines the constructor, predicate, copier and slot accessors of a
+// structure type.
+pub fn define_struct(env: &mut ExprEnv, ty: Rc<StructType>) {
+    env.define_struct_type(ty.clone());
+    if let Some(name) = &ty.constructor {
+        let constructor = ty.clone();
+        let function = name.clone();
+        define_native(env, name, 0, usize::MAX, move |evaluator, args, env| {
+            let keys = constructor
+                .slots
+                .iter()
+                .map(|slot| format!(":{}", slot.name))
+                .collect::<Vec<_>>();
+            let allowed = keys.iter().map(String::as_str).collect::<Vec<_>>();
+            let keywords = keyword_args(&function, args, &allowed)?;
+            let values = constructor
+                .slots
+                .iter()
+                .zip(&keys)
+                .map(|(slot, key)| match keywords.get(key) {
+                    Some(value) => Ok(value.clone()),
+                    None => evaluator.eval(&slot.default, env),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Expr::Struct(alloc(Structure {
+                ty: constructor.clone(),
+                values,
+            })))
+        });
+    }
+
+    if let Some(name) = &ty.predicate {
+        let predicate = ty.clone();
+        define_native(env, name, 1, 1, move |_, args, _| {
+            Ok(boolean(expect_structure(&args[0], &predicate).is_ok()))
+        });
+    }
+
+    if let Some(name) = &ty.copier {
+        let copier = ty.clone();
+        define_native(env, name, 1, 1, move |_, args, _| {
+            let structure = expect_structure(&args[0], &copier)?;
+            let values = structure.borrow().values.clone();
+            Ok(Expr::Struct(alloc(Structure {
+                ty: copier.clone(),
+                values,
+            })))
+        });
+    }
+
+    for (i, slot) in ty.slots.iter().enumerate() {
+        let accessor = ty.clone();
+        define_native(env, &ty.accessor_name(slot), 1, 1, move |_, args, _| {
+            let structure = expect_structure(&args[0], &accessor)?;
+            let value = structure.borrow().values[i].clone();
+            Ok(value)
+        });
+    }
+}
@@ -0,0 +1,122 @@
+use super::{boolean, define, define_native, expect_sequence, list, sequence_like};
+use crate::modules::array::Array;
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::eval::{Evaluator, ExprEnv};
+use crate::modules::number::Number;
+use crate::modules::printer::prin1_to_string;
+use crate::modules::types::{is_a, is_builtin_type, type_of, TypeSpec};
+
+pub fn register(env: &mut ExprEnv) {
+    define(env, "TYPE-OF", 1, 1, |args| {
+        Ok(Expr::Symbol(type_of(&args[0]).into()))
+    });
+    define_native(env, "TYPEP", 2, 2, |evaluator, args, env| {
+        Ok(boolean(typep(evaluator, &args[0], &args[1], env)?))
+    });
+    define_native(env, "COERCE", 2, 2, coerce);
+
+    let predicates = [
+        ("NUMBERP", "NUMBER"),
+        ("INTEGERP", "INTEGER"),
+        ("RATIONALP", "RATIONAL"),
+        ("FLOATP", "FLOAT"),
+        ("REALP", "REAL"),
+        ("CHARACTERP", "CHARACTER"),
+        ("STRINGP", "STRING"),
+        ("SYMBOLP", "SYMBOL"),
+        ("KEYWORDP", "KEYWORD"),
+        ("FUNCTIONP", "FUNCTION"),
+        ("CONSP", "CONS"),
+        ("LISTP", "LIST"),
+        ("ATOM", "ATOM"),
+        ("NULL", "NULL"),
+        ("VECTORP", "VECTOR"),
+        ("ARRAYP", "ARRAY"),
+        ("HASH-TABLE-P", "HASH-TABLE"),
+    ];
+    for (name, ty) in predicates {
+        define_native(env, name, 1, 1, move |_, args, _| {
+            Ok(boolean(is_a(&args[0], ty)))
+        });
+    }
+}
+
+// Whether the value belongs to the type given by an unevaluated type
+// specifier, calling the predicates of SATISFIES types. The type names must
+// be builtin types, structures or classes.
+pub fn typep(
+    evaluator: &mut Evaluator,
+    x: &Expr,
+    spec: &Expr,
+    env: &mut ExprEnv,
+) -> Result<bool, RispError> {
+    let spec = TypeSpec::parse(spec)?;
+    if let Some(name) = spec.names().into_iter().find(|name| {
+        !is_builtin_type(name)
+            && env.struct_type(name).is_none()
+            && evaluator.find_class(name).is_none()
+    }) {
+        return Err(RispError::Expr(format!("unknown type specifier {}", name)));
+    }
+    spec.matches(x, &mut |predicate, x| {
+        let result = evaluator.funcall(
+            &Expr::Symbol(predicate.into()),
+            std::slice::from_ref(x),
+            env,
+        )?;
+        Ok(result != Expr::Nil)
+    })
+}
+
+pub fn type_error(x: &Expr, spec: &Expr) -> RispError {
+    RispError::Type {
+        datum: prin1_to_string(x),
+        expected: prin1_to_string(spec),
+    }
+}
+
+// (coerce object result-type) converting between sequences, to floats and
+// to characters. Objects already of the result type are returned as is.
+fn coerce(evaluator: &mut Evaluator, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+    let (x, spec) = (&args[0], &args[1]);
+    if typep(evaluator, x, spec, env)? {
+        return Ok(x.clone());
+    }
+    let name = match spec {
+        Expr::Symbol(name) => &**name,
+        Expr::List(spec) => match spec.first() {
+            Some(Expr::Symbol(name)) => &**name,
+            _ => return Err(type_error(x, spec.first().unwrap_or(&Expr::Nil))),
+        },
+        _ => return Err(type_error(x, spec)),
+    };
+    let coerced = match (name, x) {
+        ("LIST", _) => list(expect_sequence(x).map_err(|_| type_error(x, &args[1]))?),
+        ("VECTOR" | "SIMPLE-VECTOR", _) => {
+            Array::from_vec(expect_sequence(x).map_err(|_| type_error(x, &args[1]))?).into_expr()
+        }
+        ("STRING", _) => {
+            let elements = expect_sequence(x).map_err(|_| type_error(x, &args[1]))?;
+            sequence_like(&Expr::String("".into()), elements)
+                .map_err(|_| type_error(x, &args[1]))?
+        }
+        ("FLOAT" | "DOUBLE-FLOAT" | "SINGLE-FLOAT", Expr::Number(num)) => {
+            Expr::Number(Number::Float(num.to_f64()))
+        }
+        ("CHARACTER", Expr::String(_) | Expr::Symbol(_)) => {
+            let name = x.to_string();
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => Expr::Char(ch),
+                _ => return Err(type_error(x, &args[1])),
+            }
+        }
+        _ => return Err(type_error(x, &args[1])),
+    };
+    if typep(evaluator, &coerced, spec, env)? {
+        Ok(coerced)
+    } else {
+        Err(type_error(x, spec))
+    }
+}
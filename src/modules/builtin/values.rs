@@ -0,0 +1,11 @@
+use super::{define_native, expect_sequence};
+use crate::modules::eval::ExprEnv;
+
+pub fn register(env: &mut ExprEnv) {
+    define_native(env, "VALUES", 0, usize::MAX, |evaluator, args, _| {
+        Ok(evaluator.values(args.to_vec()))
+    });
+    define_native(env, "VALUES-LIST", 1, 1, |evaluator, args, _| {
+        Ok(evaluator.values(expect_sequence(&args[0])?))
+    });
+}
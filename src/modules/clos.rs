@@ -0,0 +1,223 @@
+use super::ast::Expr;
+use super::env::ExprEnv;
+use super::error::RispError;
+use super::gc::Trace;
+use super::types::class_precedence_list;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Clone)]
+pub struct SlotDefinition {
+    pub name: String,
+    pub initargs: Vec<String>,
+    // Unevaluated form giving the initial value when no initarg is passed.
+    pub initform: Option<Expr>,
+}
+
+// A class defined by DEFCLASS. `slots` holds the effective slots, including
+// the ones inherited from the superclass.
+pub struct Class {
+    pub name: String,
+    pub superclass: Option<Rc<Class>>,
+    pub slots: Vec<SlotDefinition>,
+}
+
+impl Class {
+    pub fn slot_index(&self, name: &str) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.name == name)
+    }
+
+    pub fn precedence_list(&self) -> Vec<String> {
+        let mut names = vec![self.name.clone()];
+        let mut class = self.superclass.clone();
+        while let Some(superclass) = class {
+            names.push(superclass.name.clone());
+            class = superclass.superclass.clone();
+        }
+        names.push("STANDARD-OBJECT".to_string());
+        names.push("T".to_string());
+        names
+    }
+}
+
+pub struct Instance {
+    pub class: Rc<Class>,
+    // Slot values in the order of the class' effective slots. `None` is an
+    // unbound slot.
+    pub values: Vec<Option<Expr>>,
+}
+
+impl Trace for Instance {
+    fn trace(&self, visit: &mut dyn FnMut(&Expr)) {
+        self.values.iter().flatten().for_each(visit);
+    }
+
+    fn clear_references(&mut self) {
+        self.values.fill(None);
+    }
+
+    fn kind(&self) -> &'static str {
+        "instances"
+    }
+}
+
+impl Instance {
+    pub fn slot_value(&self, name: &str) -> Result<Expr, RispError> {
+        let index = self
+            .class
+            .slot_index(name)
+            .ok_or_else(|| RispError::Expr(format!("{} has no slot {}", self.class.name, name)))?;
+        self.values[index]
+            .clone()
+            .ok_or_else(|| RispError::Expr(format!("The slot {} is unbound", name)))
+    }
+
+    pub fn set_slot_value(&mut self, name: &str, value: Expr) -> Result<(), RispError> {
+        let index = self
+            .class
+            .slot_index(name)
+            .ok_or_else(|| RispError::Expr(format!("{} has no slot {}", self.class.name, name)))?;
+        self.values[index] = Some(value);
+        Ok(())
+    }
+}
+
+pub fn expect_instance(x: &Expr) -> Result<Rc<RefCell<Instance>>, RispError> {
+    match x {
+        Expr::Instance(instance) => Ok(instance.clone()),
+        _ => Err(RispError::Expr(format!("{} is not instance", x))),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Qualifier {
+    Primary,
+    Before,
+    After,
+    Around,
+}
+
+impl Qualifier {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            ":BEFORE" => Some(Qualifier::Before),
+            ":AFTER" => Some(Qualifier::After),
+            ":AROUND" => Some(Qualifier::Around),
+            _ => None,
+        }
+    }
+}
+
+pub struct Method {
+    pub qualifier: Qualifier,
+    // Class names of the required parameters, T when unspecialized.
+    pub specializers: Vec<String>,
+    pub params: Vec<String>,
+    pub body: Rc<[Expr]>,
+    // The lexical environment of the DEFMETHOD.
+    pub env: ExprEnv,
+}
+
+impl Method {
+    // Positions of the specializers in the class precedence lists of the
+    // arguments, or None when the method is not applicable.
+    fn ranks(&self, precedence_lists: &[Vec<String>]) -> Option<Vec<usize>> {
+        self.specializers
+            .iter()
+            .zip(precedence_lists)
+            .map(|(specializer, names)| names.iter().position(|name| name == specializer))
+            .collect()
+    }
+}
+
+pub struct Generic {
+    pub name: String,
+    pub required: usize,
+    pub methods: Vec<Rc<Method>>,
+}
+
+impl Generic {
+    // Adds a method, replacing the one with the same qualifier and
+    // specializers if any.
+    pub fn add_method(&mut self, method: Method) -> Result<(), RispError> {
+        if method.params.len() != self.required {
+            return Err(RispError::Expr(format!(
+                "DEFMETHOD: {} takes {} required arguments",
+                self.name, self.required
+            )));
+        }
+        self.methods.retain(|other| {
+            other.qualifier != method.qualifier || other.specializers != method.specializers
+        });
+        self.methods.push(Rc::new(method));
+        Ok(())
+    }
+
+    // Applicable methods sorted from the most to the least specific, comparing
+    // the specializers of the arguments from left to right.
+    pub fn applicable_methods(&self, args: &[Expr]) -> Vec<Rc<Method>> {
+        let precedence_lists = args.iter().map(class_precedence_list).collect::<Vec<_>>();
+        let mut methods = self
+            .methods
+            .iter()
+            .filter_map(|method| {
+                method
+                    .ranks(&precedence_lists)
+                    .map(|ranks| (ranks, method.clone()))
+            })
+            .collect::<Vec<_>>();
+        methods.sort_by(|(x, _), (y, _)| x.cmp(y));
+        methods.into_iter().map(|(_, method)| method).collect()
+    }
+}
+
+// Orders methods by qualifier, keeping the most specific first except for the
+// after methods, which run from the least specific.
+pub fn combine(methods: &[Rc<Method>]) -> EffectiveMethod {
+    let select = |qualifier| {
+        methods
+            .iter()
+            .filter(|method| method.qualifier == qualifier)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let mut afters = select(Qualifier::After);
+    afters.reverse();
+    EffectiveMethod {
+        arounds: select(Qualifier::Around),
+        befores: select(Qualifier::Before),
+        primaries: select(Qualifier::Primary),
+        afters,
+    }
+}
+
+// The methods left to run, which is also what CALL-NEXT-METHOD continues with.
+#[derive(Clone, Default)]
+pub struct EffectiveMethod {
+    pub arounds: Vec<Rc<Method>>,
+    pub befores: Vec<Rc<Method>>,
+    pub primaries: Vec<Rc<Method>>,
+    pub afters: Vec<Rc<Method>>,
+}
+
+impl EffectiveMethod {
+    pub fn has_next(&self) -> bool {
+        !self.arounds.is_empty() || !self.primaries.is_empty()
+    }
+
+    // The rest of the method chain after running the first around method.
+    pub fn after_around(&self) -> Self {
+        Self {
+            arounds: self.arounds[1..].to_vec(),
+            ..self.clone()
+        }
+    }
+
+    // The rest of the method chain after running the first primary method.
+    pub fn after_primary(&self) -> Self {
+        Self {
+            primaries: self.primaries[1..].to_vec(),
+            ..Self::default()
+        }
+    }
+}
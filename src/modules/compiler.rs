@@ -0,0 +1,397 @@
+use super::ast::Expr;
+use super::env::ExprEnv;
+use super::eval::is_special_form;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+// An instruction of the stack machine run by `Evaluator::run`. Operands index
+// the pools of the chunk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    // Pushes a constant.
+    Const(usize),
+    // Pushes or assigns a local slot: an argument or a LET variable.
+    Local(usize),
+    SetLocal(usize),
+    // Pushes or assigns a lexical variable captured from the environment the
+    // function was created in.
+    Cell(usize),
+    SetCell(usize),
+    // Pushes or assigns the global value of a variable.
+    Global(usize),
+    SetGlobal(usize),
+    // Pushes the function of a name, as #'name.
+    Function(usize),
+    // Applies an arithmetic function to the two numbers on top of the stack,
+    // or calls the function of the name when it is no longer the builtin.
+    Arith {
+        op: Arith,
+        name: usize,
+    },
+    // Calls the function of a name with the arguments on top of the stack.
+    // `all_values` is set for a call in tail position, which passes on all
+    // the values it returns rather than its primary value only.
+    Call {
+        name: usize,
+        argc: usize,
+        all_values: bool,
+    },
+    Jump(usize),
+    // Pops the top of the stack and jumps when it is NIL.
+    JumpIfNil(usize),
+    Pop,
+    Return,
+}
+
+// The arithmetic functions of COMMON-LISP which are open-coded when called
+// with two arguments. Programs do not redefine them, but a call is compiled as
+// such only while the name is bound to the builtin, and made after all when
+// it has been redefined since.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arith {
+    Add,
+    Sub,
+    Mul,
+    Eq,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+impl Arith {
+    fn of(name: &str) -> Option<Self> {
+        match name {
+            "+" => Some(Self::Add),
+            "-" => Some(Self::Sub),
+            "*" => Some(Self::Mul),
+            "=" => Some(Self::Eq),
+            "<" => Some(Self::Lt),
+            ">" => Some(Self::Gt),
+            "<=" => Some(Self::Le),
+            ">=" => Some(Self::Ge),
+            _ => None,
+        }
+    }
+}
+
+// The bytecode of a function body.
+#[derive(Default)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    pub constants: Vec<Expr>,
+    pub names: Vec<String>,
+    pub cells: Vec<Rc<RefCell<Expr>>>,
+    // Number of local slots, starting with the arguments.
+    pub slots: usize,
+    // The functions of the names as last looked up, while the global
+    // functions are at `generation`.
+    pub functions: RefCell<Vec<Option<Expr>>>,
+    pub generation: Cell<u64>,
+    // Whether the names of the open-coded arithmetic were all bound to the
+    // builtins when last checked, at the generation given with it.
+    pub arith_checked: Cell<Option<(u64, bool)>>,
+}
+
+struct Compiler<'a> {
+    chunk: Chunk,
+    // Local variables in scope and their slots, the innermost last.
+    scope: Vec<(String, usize)>,
+    env: &'a ExprEnv,
+}
+
+// Compiles the body of a function taking `params`, created in `env`. Returns
+// `None` when the body uses forms the compiler does not handle or binds
+// special variables, so that the function is interpreted.
+pub fn compile(params: &[String], body: &Expr, env: &ExprEnv) -> Option<Chunk> {
+    if params.iter().any(|param| env.is_special(param)) {
+        return None;
+    }
+    let mut compiler = Compiler {
+        chunk: Chunk {
+            slots: params.len(),
+            ..Chunk::default()
+        },
+        scope: params.iter().cloned().zip(0..).collect(),
+        env,
+    };
+    compiler.expr(body, true)?;
+    compiler.emit(Op::Return);
+    Some(compiler.chunk)
+}
+
+impl Compiler<'_> {
+    fn emit(&mut self, op: Op) -> usize {
+        self.chunk.code.push(op);
+        self.chunk.code.len() - 1
+    }
+
+    fn constant(&mut self, x: Expr) -> usize {
+        self.chunk.constants.push(x);
+        self.chunk.constants.len() - 1
+    }
+
+    fn name(&mut self, name: &str) -> usize {
+        match self.chunk.names.iter().position(|x| x == name) {
+            Some(i) => i,
+            None => {
+                self.chunk.names.push(name.to_string());
+                self.chunk.names.len() - 1
+            }
+        }
+    }
+
+    fn cell(&mut self, cell: Rc<RefCell<Expr>>) -> usize {
+        match self.chunk.cells.iter().position(|x| Rc::ptr_eq(x, &cell)) {
+            Some(i) => i,
+            None => {
+                self.chunk.cells.push(cell);
+                self.chunk.cells.len() - 1
+            }
+        }
+    }
+
+    fn local(&self, name: &str) -> Option<usize> {
+        self.scope
+            .iter()
+            .rev()
+            .find(|(var, _)| var == name)
+            .map(|(_, slot)| *slot)
+    }
+
+    // Compiles an expression leaving its value on the stack. `tail` tells
+    // whether the value is returned from the function.
+    fn expr(&mut self, x: &Expr, tail: bool) -> Option<()> {
+        match x {
+            Expr::Symbol(sym) if sym.starts_with(':') => {
+                let i = self.constant(x.clone());
+                self.emit(Op::Const(i));
+            }
+            Expr::Symbol(sym) => self.variable(sym),
+            Expr::List(list) => self.form(list, tail)?,
+            _ => {
+                let i = self.constant(x.clone());
+                self.emit(Op::Const(i));
+            }
+        }
+        Some(())
+    }
+
+    fn variable(&mut self, name: &str) {
+        if let Some(slot) = self.local(name) {
+            self.emit(Op::Local(slot));
+        } else if let Some(cell) = self.env.local_cell(name) {
+            let i = self.cell(cell);
+            self.emit(Op::Cell(i));
+        } else {
+            let i = self.name(name);
+            self.emit(Op::Global(i));
+        }
+    }
+
+    fn form(&mut self, list: &[Expr], tail: bool) -> Option<()> {
+        let (head, args) = match list.split_first()? {
+            (Expr::Symbol(head), args) => (&**head, args),
+            _ => return None,
+        };
+        match (head, args) {
+            ("QUOTE", [x]) => {
+                let i = self.constant(x.clone());
+                self.emit(Op::Const(i));
+            }
+            ("IF", [test, then]) => self.conditional(test, then, &Expr::Nil, tail)?,
+            ("IF", [test, then, otherwise]) => self.conditional(test, then, otherwise, tail)?,
+            ("SETQ", [Expr::Symbol(var), value]) => {
+                self.expr(value, false)?;
+                self.assign(var);
+            }
+            ("LET", [bindings, body]) => self.let_form(bindings, body, false, tail)?,
+            ("LET*", [bindings, body]) => self.let_form(bindings, body, true, tail)?,
+            ("FUNCTION", [Expr::Symbol(name)]) => {
+                let i = self.name(name);
+                self.emit(Op::Function(i));
+            }
+            // A body using any other special form, such as LAMBDA which needs
+            // an environment to capture, is left to the tree-walking evaluator.
+            _ if is_special_form(head) => return None,
+            (_, [a, b]) if self.arith(head).is_some() => {
+                self.expr(a, false)?;
+                self.expr(b, false)?;
+                let op = self.arith(head)?;
+                let name = self.name(head);
+                self.emit(Op::Arith { op, name });
+            }
+            _ => {
+                for arg in args {
+                    self.expr(arg, false)?;
+                }
+                let name = self.name(head);
+                self.emit(Op::Call {
+                    name,
+                    argc: args.len(),
+                    all_values: tail,
+                });
+            }
+        }
+        Some(())
+    }
+
+    fn arith(&self, name: &str) -> Option<Arith> {
+        match self.env.get_function(name)? {
+            Expr::Func(native) if native.name == name => Arith::of(name),
+            _ => None,
+        }
+    }
+
+    fn assign(&mut self, name: &str) {
+        if let Some(slot) = self.local(name) {
+            self.emit(Op::SetLocal(slot));
+        } else if let Some(cell) = self.env.local_cell(name) {
+            let i = self.cell(cell);
+            self.emit(Op::SetCell(i));
+        } else {
+            let i = self.name(name);
+            self.emit(Op::SetGlobal(i));
+        }
+    }
+
+    fn conditional(
+        &mut self,
+        test: &Expr,
+        then: &Expr,
+        otherwise: &Expr,
+        tail: bool,
+    ) -> Option<()> {
+        self.expr(test, false)?;
+        let jump_to_else = self.emit(Op::JumpIfNil(0));
+        self.expr(then, tail)?;
+        let jump_to_end = self.emit(Op::Jump(0));
+        self.chunk.code[jump_to_else] = Op::JumpIfNil(self.chunk.code.len());
+        self.expr(otherwise, tail)?;
+        self.chunk.code[jump_to_end] = Op::Jump(self.chunk.code.len());
+        Some(())
+    }
+
+    // (let ((var value)...) body) with a single body form, binding lexical
+    // variables to new slots. LET* makes each variable visible to the values
+    // after it.
+    fn let_form(
+        &mut self,
+        bindings: &Expr,
+        body: &Expr,
+        sequential: bool,
+        tail: bool,
+    ) -> Option<()> {
+        let bindings = match bindings {
+            Expr::List(bindings) => bindings.to_vec(),
+            Expr::Nil => vec![],
+            _ => return None,
+        };
+        let scope = self.scope.len();
+        let mut vars = vec![];
+        for binding in &bindings {
+            let (var, value) = match binding {
+                Expr::Symbol(var) => (var, &Expr::Nil),
+                Expr::List(binding) => match binding.as_ref() {
+                    [Expr::Symbol(var)] => (var, &Expr::Nil),
+                    [Expr::Symbol(var), value] => (var, value),
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            if self.env.is_special(var) || self.env.is_constant(var) {
+                return None;
+            }
+            self.expr(value, false)?;
+            let slot = self.chunk.slots;
+            self.chunk.slots += 1;
+            if sequential {
+                self.emit(Op::SetLocal(slot));
+                self.emit(Op::Pop);
+                self.scope.push((var.to_string(), slot));
+            } else {
+                vars.push((var.to_string(), slot));
+            }
+        }
+        // The values of a LET are all computed before any variable is bound.
+        for (_, slot) in vars.iter().rev() {
+            self.emit(Op::SetLocal(*slot));
+            self.emit(Op::Pop);
+        }
+        self.scope.extend(vars);
+        self.expr(body, tail)?;
+        self.scope.truncate(scope);
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::eval::default_env;
+    use crate::modules::lexer::Lexer;
+    use crate::modules::parser::Parser;
+
+    fn read(s: &str) -> Expr {
+        Parser::new(Lexer::new(s.to_string()).unwrap())
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn compile_body() {
+        let env = ExprEnv::new();
+        let params = ["N".to_string()];
+        let chunk = compile(&params, &read("(if (< n 2) n (f (- n 1)))"), &env).unwrap();
+        assert_eq!(
+            chunk.code,
+            vec![
+                Op::Local(0),
+                Op::Const(0),
+                Op::Call {
+                    name: 0,
+                    argc: 2,
+                    all_values: false
+                },
+                Op::JumpIfNil(6),
+                Op::Local(0),
+                Op::Jump(10),
+                Op::Local(0),
+                Op::Const(1),
+                Op::Call {
+                    name: 1,
+                    argc: 2,
+                    all_values: false
+                },
+                Op::Call {
+                    name: 2,
+                    argc: 1,
+                    all_values: true
+                },
+                Op::Return,
+            ]
+        );
+        assert_eq!(chunk.names, vec!["<", "-", "F"]);
+
+        let chunk = compile(&params, &read("(let ((a n) (b 1)) (setq n b))"), &env).unwrap();
+        assert_eq!(chunk.slots, 3);
+        assert!(compile(&params, &read("(lambda () n)"), &env).is_none());
+        assert!(compile(&params, &read("((lambda (x) x) n)"), &env).is_none());
+
+        // Arithmetic on two arguments is open-coded while + is the builtin.
+        let mut env = default_env();
+        let chunk = compile(&params, &read("(+ n 1)"), &env).unwrap();
+        assert_eq!(
+            chunk.code[2],
+            Op::Arith {
+                op: Arith::Add,
+                name: 0
+            }
+        );
+        let chunk = compile(&params, &read("(+ n 1 2)"), &env).unwrap();
+        assert!(matches!(chunk.code[3], Op::Call { argc: 3, .. }));
+        env.define_function("+", Expr::Nil);
+        let chunk = compile(&params, &read("(+ n 1)"), &env).unwrap();
+        assert!(matches!(chunk.code[2], Op::Call { argc: 2, .. }));
+    }
+}
@@ -0,0 +1,358 @@
+use super::ast::Expr;
+use super::builtin::{boolean, expect_sequence, list};
+use super::error::RispError;
+use super::gc::alloc;
+use super::hash_table::{expect_hash_table, HashTable, HashTest};
+use super::number::Number;
+use super::printer::prin1_to_string;
+use num_traits::ToPrimitive;
+use std::collections::HashMap;
+
+/// Converts a Rust value to the risp value it stands for.
+///
+/// ```
+/// use risp::IntoExpr;
+///
+/// assert_eq!(vec![(1, "one"), (2, "two")].into_expr().to_string(), "((1 one) (2 two))");
+/// assert_eq!(None::<i64>.into_expr().to_string(), "NIL");
+/// ```
+pub trait IntoExpr {
+    fn into_expr(self) -> Expr;
+}
+
+/// Converts a risp value to a Rust value, failing with a type error when it
+/// is not of the expected type.
+///
+/// ```
+/// use risp::{FromExpr, Interpreter};
+///
+/// let mut interp = Interpreter::new();
+/// let xs = interp.eval_str("(mapcar (lambda (x) (* x x)) '(1 2 3))").unwrap();
+/// assert_eq!(Vec::<i64>::from_expr(&xs).unwrap(), vec![1, 4, 9]);
+/// assert!(String::from_expr(&xs).is_err());
+/// ```
+pub trait FromExpr: Sized {
+    fn from_expr(x: &Expr) -> Result<Self, RispError>;
+}
+
+fn type_error(x: &Expr, expected: &str) -> RispError {
+    RispError::Type {
+        datum: prin1_to_string(x),
+        expected: expected.to_string(),
+    }
+}
+
+impl IntoExpr for Expr {
+    fn into_expr(self) -> Expr {
+        self
+    }
+}
+
+impl FromExpr for Expr {
+    fn from_expr(x: &Expr) -> Result<Self, RispError> {
+        Ok(x.clone())
+    }
+}
+
+impl IntoExpr for () {
+    fn into_expr(self) -> Expr {
+        Expr::Nil
+    }
+}
+
+impl IntoExpr for i64 {
+    fn into_expr(self) -> Expr {
+        Expr::Number(Number::Fixnum(self))
+    }
+}
+
+impl FromExpr for i64 {
+    fn from_expr(x: &Expr) -> Result<Self, RispError> {
+        match x {
+            Expr::Number(Number::Fixnum(n)) => Ok(*n),
+            Expr::Number(Number::Bignum(n)) => n.to_i64().ok_or_else(|| type_error(x, "FIXNUM")),
+            _ => Err(type_error(x, "INTEGER")),
+        }
+    }
+}
+
+impl IntoExpr for f64 {
+    fn into_expr(self) -> Expr {
+        Expr::Number(Number::Float(self))
+    }
+}
+
+// Any real number converts to a float, as with COERCE.
+impl FromExpr for f64 {
+    fn from_expr(x: &Expr) -> Result<Self, RispError> {
+        match x {
+            Expr::Number(n) => Ok(n.to_f64()),
+            _ => Err(type_error(x, "REAL")),
+        }
+    }
+}
+
+impl IntoExpr for bool {
+    fn into_expr(self) -> Expr {
+        boolean(self)
+    }
+}
+
+// Any value but NIL is true.
+impl FromExpr for bool {
+    fn from_expr(x: &Expr) -> Result<Self, RispError> {
+        Ok(!matches!(x, Expr::Nil))
+    }
+}
+
+impl IntoExpr for char {
+    fn into_expr(self) -> Expr {
+        Expr::Char(self)
+    }
+}
+
+impl FromExpr for char {
+    fn from_expr(x: &Expr) -> Result<Self, RispError> {
+        match x {
+            Expr::Char(ch) => Ok(*ch),
+            _ => Err(type_error(x, "CHARACTER")),
+        }
+    }
+}
+
+impl IntoExpr for String {
+    fn into_expr(self) -> Expr {
+        Expr::string(self)
+    }
+}
+
+impl IntoExpr for &str {
+    fn into_expr(self) -> Expr {
+        Expr::string(self)
+    }
+}
+
+impl FromExpr for String {
+    fn from_expr(x: &Expr) -> Result<Self, RispError> {
+        match x {
+            Expr::String(s) => Ok(s.to_string()),
+            _ => Err(type_error(x, "STRING")),
+        }
+    }
+}
+
+// Vectors convert to lists, and back from any sequence.
+impl<T: IntoExpr> IntoExpr for Vec<T> {
+    fn into_expr(self) -> Expr {
+        list(self.into_iter().map(IntoExpr::into_expr).collect())
+    }
+}
+
+impl<T: FromExpr> FromExpr for Vec<T> {
+    fn from_expr(x: &Expr) -> Result<Self, RispError> {
+        let elements = expect_sequence(x).map_err(|_| type_error(x, "SEQUENCE"))?;
+        elements.iter().map(T::from_expr).collect()
+    }
+}
+
+// `None` is NIL, which `Some(false)` converts to as well.
+impl<T: IntoExpr> IntoExpr for Option<T> {
+    fn into_expr(self) -> Expr {
+        self.map_or(Expr::Nil, IntoExpr::into_expr)
+    }
+}
+
+impl<T: FromExpr> FromExpr for Option<T> {
+    fn from_expr(x: &Expr) -> Result<Self, RispError> {
+        match x {
+            Expr::Nil => Ok(None),
+            _ => T::from_expr(x).map(Some),
+        }
+    }
+}
+
+// Maps convert to EQUAL hash tables keyed by strings.
+impl<T: IntoExpr> IntoExpr for HashMap<String, T> {
+    fn into_expr(self) -> Expr {
+        let mut table = HashTable::new(HashTest::Equal);
+        for (key, value) in self {
+            table.insert(key.into_expr(), value.into_expr());
+        }
+        Expr::HashTable(alloc(table))
+    }
+}
+
+impl<T: FromExpr> FromExpr for HashMap<String, T> {
+    fn from_expr(x: &Expr) -> Result<Self, RispError> {
+        let table = expect_hash_table(x).map_err(|_| type_error(x, "HASH-TABLE"))?;
+        let entries = table.borrow().entries();
+        entries
+            .iter()
+            .map(|(key, value)| Ok((String::from_expr(key)?, T::from_expr(value)?)))
+            .collect()
+    }
+}
+
+// Tuples convert to lists of their elements, and back from lists of as many.
+macro_rules! tuple_conversions {
+    ($len:expr; $($name:ident),+) => {
+        impl<$($name: IntoExpr),+> IntoExpr for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn into_expr(self) -> Expr {
+                let ($($name,)+) = self;
+                list(vec![$($name.into_expr()),+])
+            }
+        }
+
+        impl<$($name: FromExpr),+> FromExpr for ($($name,)+) {
+            fn from_expr(x: &Expr) -> Result<Self, RispError> {
+                let elements = match x {
+                    Expr::List(elements) => elements,
+                    Expr::Nil => &[][..],
+                    _ => return Err(type_error(x, "LIST")),
+                };
+                if elements.len() != $len {
+                    return Err(RispError::Expr(format!(
+                        "The value {} is not a list of {} elements: it has {}",
+                        prin1_to_string(x),
+                        $len,
+                        elements.len()
+                    )));
+                }
+                let mut elements = elements.iter();
+                Ok(($($name::from_expr(elements.next().unwrap())?,)+))
+            }
+        }
+    };
+}
+
+tuple_conversions!(1; A);
+tuple_conversions!(2; A, B);
+tuple_conversions!(3; A, B, C);
+tuple_conversions!(4; A, B, C, D);
+
+/// The result of a Rust function called from risp: a value, or an error
+/// signaled in risp.
+pub trait IntoResult {
+    fn into_result(self) -> Result<Expr, RispError>;
+}
+
+impl<T: IntoExpr> IntoResult for T {
+    fn into_result(self) -> Result<Expr, RispError> {
+        Ok(self.into_expr())
+    }
+}
+
+impl<T: IntoExpr> IntoResult for Result<T, RispError> {
+    fn into_result(self) -> Result<Expr, RispError> {
+        self.map(IntoExpr::into_expr)
+    }
+}
+
+/// A Rust closure taking arguments converted from risp values, which
+/// `Interpreter::register` defines as a function. `Args` is the tuple of
+/// its argument types.
+pub trait HostFn<Args>: 'static {
+    const ARITY: usize;
+
+    fn call(&self, args: &[Expr]) -> Result<Expr, RispError>;
+}
+
+macro_rules! host_fn {
+    ($len:expr; $($name:ident),*) => {
+        impl<Func, Ret, $($name),*> HostFn<($($name,)*)> for Func
+        where
+            Func: Fn($($name),*) -> Ret + 'static,
+            Ret: IntoResult,
+            $($name: FromExpr),*
+        {
+            const ARITY: usize = $len;
+
+            #[allow(unused_variables, unused_mut)]
+            fn call(&self, args: &[Expr]) -> Result<Expr, RispError> {
+                let mut args = args.iter();
+                self($($name::from_expr(args.next().unwrap())?),*).into_result()
+            }
+        }
+    };
+}
+
+host_fn!(0;);
+host_fn!(1; A);
+host_fn!(2; A, B);
+host_fn!(3; A, B, C);
+host_fn!(4; A, B, C, D);
+host_fn!(5; A, B, C, D, E);
+host_fn!(6; A, B, C, D, E, F);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: IntoExpr + FromExpr>(value: T) -> T {
+        T::from_expr(&value.into_expr()).unwrap()
+    }
+
+    #[test]
+    fn convert_values() {
+        assert_eq!(round_trip(42i64), 42);
+        assert_eq!(round_trip(1.5f64), 1.5);
+        assert!(round_trip(true));
+        assert!(!round_trip(false));
+        assert_eq!(round_trip('a'), 'a');
+        assert_eq!(round_trip("text".to_string()), "text");
+        assert_eq!(round_trip(vec![1i64, 2, 3]), vec![1, 2, 3]);
+        assert_eq!(round_trip(Vec::<i64>::new()), vec![]);
+        assert_eq!(round_trip(Some(1i64)), Some(1));
+        assert_eq!(round_trip(None::<i64>), None);
+        assert_eq!(round_trip((1i64, "a".to_string())), (1, "a".to_string()));
+        let map = HashMap::from([("a".to_string(), 1i64), ("b".to_string(), 2)]);
+        assert_eq!(round_trip(map.clone()), map);
+
+        assert_eq!(prin1_to_string(&"a".into_expr()), "\"a\"");
+        assert_eq!(prin1_to_string(&(1i64, (2.5f64,)).into_expr()), "(1 (2.5))");
+        assert_eq!(f64::from_expr(&3i64.into_expr()).unwrap(), 3.0);
+        assert_eq!(
+            Vec::<char>::from_expr(&"ab".into_expr()).unwrap(),
+            ['a', 'b']
+        );
+        assert!(bool::from_expr(&0i64.into_expr()).unwrap());
+    }
+
+    #[test]
+    fn convert_errors() {
+        let error = |result: Result<_, RispError>| result.err().unwrap().to_string();
+        assert_eq!(
+            error(i64::from_expr(&1.5.into_expr()).map(|_| ())),
+            "The value 1.5 is not of type INTEGER"
+        );
+        let big = Expr::Number(Number::parse("100000000000000000000").unwrap());
+        assert_eq!(
+            error(i64::from_expr(&big).map(|_| ())),
+            "The value 100000000000000000000 is not of type FIXNUM"
+        );
+        assert_eq!(
+            error(String::from_expr(&1i64.into_expr()).map(|_| ())),
+            "The value 1 is not of type STRING"
+        );
+        assert_eq!(
+            error(Vec::<i64>::from_expr(&vec!["a"].into_expr()).map(|_| ())),
+            "The value \"a\" is not of type INTEGER"
+        );
+        assert_eq!(
+            error(<(i64, i64)>::from_expr(&vec![1i64].into_expr()).map(|_| ())),
+            "The value (1) is not a list of 2 elements: it has 1"
+        );
+        assert_eq!(
+            error(<(i64,)>::from_expr(&Expr::Nil).map(|_| ())),
+            "The value NIL is not a list of 1 elements: it has 0"
+        );
+        assert_eq!(
+            error(<(i64, i64)>::from_expr(&1i64.into_expr()).map(|_| ())),
+            "The value 1 is not of type LIST"
+        );
+        let table = HashMap::from([("a".to_string(), "b")]).into_expr();
+        assert!(HashMap::<String, i64>::from_expr(&table).is_err());
+        assert!(HashMap::<String, i64>::from_expr(&Expr::Nil).is_err());
+    }
+}
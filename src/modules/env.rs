@@ -0,0 +1,414 @@
+use super::ast::Expr;
+use super::error::RispError;
+use super::gc::{alloc, Trace};
+use super::package::Packages;
+use super::structure::StructType;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+#[derive(Default)]
+struct Globals {
+    values: HashMap<String, Expr>,
+    // Functions live apart from the values so that a variable does not
+    // shadow the function of the same name.
+    functions: HashMap<String, Expr>,
+    // Counts the changes to the global functions, so that the functions a
+    // compiled body has looked up can be told stale.
+    generation: u64,
+    // Variables proclaimed special by DEFVAR and DEFPARAMETER, which are
+    // rebound dynamically.
+    specials: HashSet<String>,
+    constants: HashSet<String>,
+    // Structure types by name, as #S reads them.
+    structs: HashMap<String, Rc<StructType>>,
+}
+
+// Closures defined globally refer back to the environment they are defined
+// in, so that a dropped environment is only freed by the collector.
+impl Trace for Globals {
+    fn trace(&self, visit: &mut dyn FnMut(&Expr)) {
+        self.values
+            .values()
+            .chain(self.functions.values())
+            .for_each(visit);
+    }
+
+    fn clear_references(&mut self) {
+        self.values.clear();
+        self.functions.clear();
+    }
+
+    fn kind(&self) -> &'static str {
+        "environments"
+    }
+}
+
+// The variables and functions visible to a form: the global bindings, shared
+// by every environment, and the lexical bindings of the enclosing LETs and
+// functions. Lexical variables live in cells so that closures capturing them
+// see later assignments.
+#[derive(Clone)]
+pub struct ExprEnv {
+    globals: Rc<RefCell<Globals>>,
+    locals: Frames<Rc<RefCell<Expr>>>,
+    local_functions: Frames<Expr>,
+    packages: Rc<RefCell<Packages>>,
+}
+
+// Lexical bindings as a list of frames, the innermost first. A frame is
+// shared by the environments extended from the one it was bound in, so
+// that an environment is cloned, as closures and calls do, in constant time.
+struct Frames<T>(Option<Rc<Frame<T>>>);
+
+pub struct Frame<T> {
+    name: Rc<str>,
+    value: T,
+    outer: Frames<T>,
+}
+
+impl<T> Frame<T> {
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn outer(&self) -> Option<&Rc<Frame<T>>> {
+        self.outer.0.as_ref()
+    }
+}
+
+impl<T> Clone for Frames<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Frames<T> {
+    fn new() -> Self {
+        Self(None)
+    }
+
+    // Binds a name in a new frame shadowing any other binding of it.
+    fn insert(&mut self, name: &str, value: T) {
+        let outer = Self(self.0.take());
+        self.0 = Some(Rc::new(Frame {
+            name: name.into(),
+            value,
+            outer,
+        }));
+    }
+
+    fn get(&self, name: &str) -> Option<&T> {
+        self.iter()
+            .find(|(var, _)| *var == name)
+            .map(|(_, value)| value)
+    }
+
+    // The bindings, the innermost first, including the ones shadowed.
+    fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        std::iter::successors(self.0.as_deref(), |frame| frame.outer.0.as_deref())
+            .map(|frame| (&*frame.name, &frame.value))
+    }
+}
+
+// The previous global values of the special variables rebound by a LET or a
+// function call, restored by `ExprEnv::unbind`.
+#[derive(Default)]
+pub struct SpecialBindings {
+    saved: Vec<(String, Option<Expr>)>,
+}
+
+impl Default for ExprEnv {
+    fn default() -> Self {
+        Self {
+            globals: alloc(Globals::default()),
+            locals: Frames::new(),
+            local_functions: Frames::new(),
+            packages: Rc::default(),
+        }
+    }
+}
+
+impl ExprEnv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Expr> {
+        match self.locals.get(name) {
+            Some(cell) => Some(cell.borrow().clone()),
+            None => self.globals.borrow().values.get(name).cloned(),
+        }
+    }
+
+    // An environment with the same global bindings and none of the lexical
+    // ones, in which the forms of a loaded file are evaluated.
+    pub fn toplevel(&self) -> Self {
+        Self {
+            globals: self.globals.clone(),
+            locals: Frames::new(),
+            local_functions: Frames::new(),
+            packages: self.packages.clone(),
+        }
+    }
+
+    // The global value of a variable, ignoring lexical bindings.
+    pub fn get_global(&self, name: &str) -> Option<Expr> {
+        self.globals.borrow().values.get(name).cloned()
+    }
+
+    // Sets the global value of a variable.
+    pub fn define(&mut self, name: &str, value: Expr) {
+        self.globals
+            .borrow_mut()
+            .values
+            .insert(name.to_string(), value);
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.globals.borrow().values.contains_key(name)
+    }
+
+    // Assigns the innermost binding of a variable as SETQ does, creating a
+    // global one when there is none.
+    pub fn set(&mut self, name: &str, value: Expr) -> Result<(), RispError> {
+        if let Some(cell) = self.locals.get(name) {
+            *cell.borrow_mut() = value;
+            return Ok(());
+        }
+        self.set_global(name, value)
+    }
+
+    // Assigns the global value of a variable as SET does.
+    pub fn set_global(&mut self, name: &str, value: Expr) -> Result<(), RispError> {
+        if self.is_constant(name) {
+            return Err(RispError::Expr(format!("{} is a constant", name)));
+        }
+        self.define(name, value);
+        Ok(())
+    }
+
+    // The cell of a lexical variable, shared with the closures capturing it.
+    pub fn local_cell(&self, name: &str) -> Option<Rc<RefCell<Expr>>> {
+        self.locals.get(name).cloned()
+    }
+
+    // Binds a new lexical variable shadowing any other binding of the name.
+    pub fn bind_lexical(&mut self, name: &str, value: Expr) {
+        self.locals.insert(name, alloc(value));
+    }
+
+    pub fn globals(&self) -> Rc<RefCell<dyn Trace>> {
+        self.globals.clone()
+    }
+
+    // The innermost frames of the lexical variables and functions, which
+    // the collector follows to find what a closure captures.
+    pub fn variable_frame(&self) -> Option<&Rc<Frame<Rc<RefCell<Expr>>>>> {
+        self.locals.0.as_ref()
+    }
+
+    pub fn function_frame(&self) -> Option<&Rc<Frame<Expr>>> {
+        self.local_functions.0.as_ref()
+    }
+
+    // Binds a variable for a LET or a function call. Special variables are
+    // rebound globally, saving their values in `specials` until `unbind`.
+    pub fn bind(
+        &mut self,
+        name: &str,
+        value: Expr,
+        specials: &mut SpecialBindings,
+    ) -> Result<(), RispError> {
+        if self.is_constant(name) {
+            return Err(RispError::Expr(format!(
+                "{} is a constant and cannot be bound",
+                name
+            )));
+        }
+        if self.is_special(name) {
+            let previous = self.globals.borrow().values.get(name).cloned();
+            specials.saved.push((name.to_string(), previous));
+            self.define(name, value);
+        } else {
+            self.bind_lexical(name, value);
+        }
+        Ok(())
+    }
+
+    // Restores the special variables rebound with `bind`, the most recent
+    // binding last.
+    pub fn unbind(&mut self, specials: SpecialBindings) {
+        let mut globals = self.globals.borrow_mut();
+        for (name, previous) in specials.saved.into_iter().rev() {
+            match previous {
+                Some(value) => globals.values.insert(name, value),
+                None => globals.values.remove(&name),
+            };
+        }
+    }
+
+    pub fn get_function(&self, name: &str) -> Option<Expr> {
+        match self.local_functions.get(name) {
+            Some(func) => Some(func.clone()),
+            None => self.globals.borrow().functions.get(name).cloned(),
+        }
+    }
+
+    // The global function of a name, as SYMBOL-FUNCTION returns it.
+    pub fn get_global_function(&self, name: &str) -> Option<Expr> {
+        self.globals.borrow().functions.get(name).cloned()
+    }
+
+    pub fn define_function(&mut self, name: &str, func: Expr) {
+        let mut globals = self.globals.borrow_mut();
+        globals.generation += 1;
+        globals.functions.insert(name.to_string(), func);
+    }
+
+    // Removes the global function of a name, returning whether there was one.
+    pub fn remove_function(&mut self, name: &str) -> bool {
+        let mut globals = self.globals.borrow_mut();
+        globals.generation += 1;
+        globals.functions.remove(name).is_some()
+    }
+
+    pub fn function_generation(&self) -> u64 {
+        self.globals.borrow().generation
+    }
+
+    pub fn is_fbound(&self, name: &str) -> bool {
+        self.globals.borrow().functions.contains_key(name)
+    }
+
+    // Binds a local function visible only through this environment.
+    pub fn bind_function(&mut self, name: &str, func: Expr) {
+        self.local_functions.insert(name, func);
+    }
+
+    pub fn define_struct_type(&mut self, ty: Rc<StructType>) {
+        self.globals
+            .borrow_mut()
+            .structs
+            .insert(ty.name.clone(), ty);
+    }
+
+    pub fn struct_type(&self, name: &str) -> Option<Rc<StructType>> {
+        self.globals.borrow().structs.get(name).cloned()
+    }
+
+    pub fn packages(&self) -> Rc<RefCell<Packages>> {
+        self.packages.clone()
+    }
+
+    // Makes every global function and variable defined so far an external
+    // symbol of the COMMON-LISP package. Called once the builtins are
+    // registered.
+    pub fn export_builtins(&mut self) {
+        let globals = self.globals.borrow();
+        self.packages
+            .borrow_mut()
+            .export_common_lisp(globals.functions.keys().chain(globals.values.keys()));
+    }
+
+    pub fn proclaim_special(&mut self, name: &str) {
+        self.globals.borrow_mut().specials.insert(name.to_string());
+    }
+
+    pub fn is_special(&self, name: &str) -> bool {
+        self.globals.borrow().specials.contains(name)
+    }
+
+    pub fn define_constant(&mut self, name: &str, value: Expr) {
+        self.define(name, value);
+        self.globals.borrow_mut().constants.insert(name.to_string());
+    }
+
+    pub fn is_constant(&self, name: &str) -> bool {
+        self.globals.borrow().constants.contains(name)
+    }
+
+    // All visible bindings, the lexical ones first.
+    pub fn bindings(&self) -> Vec<(String, Expr)> {
+        let mut seen = HashSet::new();
+        let mut bindings = self
+            .locals
+            .iter()
+            .filter(|(name, _)| seen.insert(*name))
+            .map(|(name, cell)| (name.to_string(), cell.borrow().clone()))
+            .collect::<Vec<_>>();
+        bindings.extend(
+            self.globals
+                .borrow()
+                .values
+                .iter()
+                .filter(|(name, _)| !seen.contains(name.as_str()))
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        bindings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::printer::prin1_to_string;
+
+    fn value(env: &ExprEnv, name: &str) -> String {
+        prin1_to_string(&env.get(name).unwrap())
+    }
+
+    #[test]
+    fn lexical_and_special_bindings() {
+        let mut env = ExprEnv::new();
+        env.define("X", Expr::Symbol("GLOBAL".into()));
+        env.define("*Y*", Expr::Symbol("GLOBAL".into()));
+        env.proclaim_special("*Y*");
+
+        let mut local = env.clone();
+        let mut specials = SpecialBindings::default();
+        local.bind("X", Expr::Nil, &mut specials).unwrap();
+        local.bind("*Y*", Expr::Nil, &mut specials).unwrap();
+        // The lexical binding is only seen through the new environment while
+        // the special one is seen everywhere.
+        assert_eq!(value(&env, "X"), "GLOBAL");
+        assert_eq!(value(&env, "*Y*"), "NIL");
+
+        let mut closure = local.clone();
+        closure.set("X", Expr::True).unwrap();
+        assert_eq!(value(&local, "X"), "T");
+
+        local.unbind(specials);
+        assert_eq!(value(&env, "*Y*"), "GLOBAL");
+    }
+
+    #[test]
+    fn constants() {
+        let mut env = ExprEnv::new();
+        env.define_constant("+C+", Expr::True);
+        assert!(env.set("+C+", Expr::Nil).is_err());
+        assert!(env
+            .bind("+C+", Expr::Nil, &mut SpecialBindings::default())
+            .is_err());
+        assert_eq!(value(&env, "+C+"), "T");
+    }
+
+    #[test]
+    fn function_namespace() {
+        let mut env = ExprEnv::new();
+        env.define("F", Expr::Nil);
+        env.define_function("F", Expr::True);
+        assert_eq!(value(&env, "F"), "NIL");
+        assert!(env.is_fbound("F"));
+
+        let mut local = env.clone();
+        local.bind_function("G", Expr::True);
+        assert!(local.get_function("G").is_some());
+        assert!(env.get_function("G").is_none());
+        assert!(!local.is_fbound("G"));
+
+        assert!(env.remove_function("F"));
+        assert!(!env.is_fbound("F"));
+        assert!(env.is_defined("F"));
+    }
+}
@@ -0,0 +1,133 @@
+use super::ast::Expr;
+use super::number::Number;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+// EQ: the same object. Objects are compared by identity, symbols by name,
+// and fixnums and characters by value. Other numbers are boxed and never EQ.
+pub fn eq(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (Expr::Number(Number::Fixnum(x)), Expr::Number(Number::Fixnum(y))) => x == y,
+        (Expr::Char(x), Expr::Char(y)) => x == y,
+        (Expr::Symbol(x), Expr::Symbol(y)) => x == y,
+        (Expr::String(x), Expr::String(y)) => Rc::ptr_eq(x, y),
+        (Expr::List(x), Expr::List(y)) => Rc::ptr_eq(x, y),
+        (Expr::True, Expr::True) | (Expr::Nil, Expr::Nil) => true,
+        (Expr::Func(x), Expr::Func(y)) => Rc::ptr_eq(x, y),
+        (Expr::Lambda(x), Expr::Lambda(y)) => Rc::ptr_eq(x, y),
+        (Expr::Stream(x), Expr::Stream(y)) => x.identity() == y.identity(),
+        (Expr::HashTable(x), Expr::HashTable(y)) => Rc::ptr_eq(x, y),
+        (Expr::Vector(x), Expr::Vector(y)) | (Expr::Array(x), Expr::Array(y)) => Rc::ptr_eq(x, y),
+        (Expr::Struct(x), Expr::Struct(y)) => Rc::ptr_eq(x, y),
+        (Expr::Instance(x), Expr::Instance(y)) => Rc::ptr_eq(x, y),
+        (Expr::Package(x), Expr::Package(y)) => Rc::ptr_eq(x, y),
+        _ => false,
+    }
+}
+
+// EQL: EQ, or numbers of the same type and value.
+pub fn eql(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (Expr::Number(x), Expr::Number(y)) => x == y,
+        _ => eq(a, b),
+    }
+}
+
+// EQUAL: EQL, or strings with the same characters and lists whose elements
+// are EQUAL.
+pub fn equal(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (Expr::String(x), Expr::String(y)) => x == y,
+        (Expr::List(x), Expr::List(y)) => {
+            x.len() == y.len() && x.iter().zip(y.iter()).all(|(x, y)| equal(x, y))
+        }
+        _ => eql(a, b),
+    }
+}
+
+// EQUALP: EQUAL, ignoring the case of characters and strings, comparing
+// numbers with `=`, and arrays, structures and hash tables by their contents.
+pub fn equalp(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (Expr::Number(x), Expr::Number(y)) => x.num_cmp(y) == Some(Ordering::Equal),
+        (Expr::Char(x), Expr::Char(y)) => chars_equalp(*x, *y),
+        (Expr::String(x), Expr::String(y)) => {
+            x.chars().count() == y.chars().count()
+                && x.chars().zip(y.chars()).all(|(x, y)| chars_equalp(x, y))
+        }
+        (Expr::List(x), Expr::List(y)) => {
+            x.len() == y.len() && x.iter().zip(y.iter()).all(|(x, y)| equalp(x, y))
+        }
+        (Expr::Vector(x), Expr::Vector(y)) | (Expr::Array(x), Expr::Array(y)) => {
+            let (x, y) = (x.borrow(), y.borrow());
+            x.rank() == y.rank()
+                && (x.rank() == 1 || x.dimensions == y.dimensions)
+                && x.len() == y.len()
+                && x.elements()
+                    .iter()
+                    .zip(y.elements())
+                    .all(|(x, y)| equalp(x, y))
+        }
+        (Expr::Struct(x), Expr::Struct(y)) => {
+            let (x, y) = (x.borrow(), y.borrow());
+            Rc::ptr_eq(&x.ty, &y.ty) && x.values.iter().zip(&y.values).all(|(x, y)| equalp(x, y))
+        }
+        (Expr::HashTable(x), Expr::HashTable(y)) => {
+            let (x, y) = (x.borrow(), y.borrow());
+            x.test == y.test
+                && x.len() == y.len()
+                && x.entries()
+                    .iter()
+                    .all(|(key, value)| y.get(key).is_some_and(|other| equalp(value, other)))
+        }
+        _ => eq(a, b),
+    }
+}
+
+fn chars_equalp(x: char, y: char) -> bool {
+    x == y || x.to_lowercase().eq(y.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn num(s: &str) -> Expr {
+        Expr::Number(Number::parse(s).unwrap())
+    }
+
+    fn string(s: &str) -> Expr {
+        Expr::String(s.into())
+    }
+
+    #[test]
+    fn equality_predicates() {
+        let shared = Expr::List(vec![num("1")].into());
+        let tests = vec![
+            // (a, b, eq, eql, equal, equalp)
+            (num("1"), num("1"), true, true, true, true),
+            (num("1"), num("1.0"), false, false, false, true),
+            (num("1.5"), num("1.5"), false, true, true, true),
+            (num("1/2"), num("0.5"), false, false, false, true),
+            (Expr::True, Expr::True, true, true, true, true),
+            (Expr::Char('a'), Expr::Char('A'), false, false, false, true),
+            (string("abc"), string("abc"), false, false, true, true),
+            (string("abc"), string("ABC"), false, false, false, true),
+            (shared.clone(), shared.clone(), true, true, true, true),
+            (
+                Expr::List(vec![num("1")].into()),
+                shared.clone(),
+                false,
+                false,
+                true,
+                true,
+            ),
+        ];
+        for (a, b, want_eq, want_eql, want_equal, want_equalp) in tests {
+            assert_eq!(eq(&a, &b), want_eq, "EQ {} {}", a, b);
+            assert_eq!(eql(&a, &b), want_eql, "EQL {} {}", a, b);
+            assert_eq!(equal(&a, &b), want_equal, "EQUAL {} {}", a, b);
+            assert_eq!(equalp(&a, &b), want_equalp, "EQUALP {} {}", a, b);
+        }
+    }
+}
@@ -1,15 +1,21 @@
 use std::convert::From;
 use std::error;
 use std::fmt;
+use std::io;
 use std::num;
 
+use num_bigint::ParseBigIntError;
+
 #[derive(Debug)]
 pub enum RispError {
     LexerInitialize(String),
     Expr(String),
+    Io(io::Error),
     Parse(num::ParseFloatError),
+    ParseInteger(ParseBigIntError),
     Peek(String),
     Read(String),
+    Type { datum: String, expected: String },
 }
 
 // Start: From implementations
@@ -18,17 +24,34 @@ impl From<num::ParseFloatError> for RispError {
         RispError::Parse(err)
     }
 }
+
+impl From<io::Error> for RispError {
+    fn from(err: io::Error) -> RispError {
+        RispError::Io(err)
+    }
+}
+
+impl From<ParseBigIntError> for RispError {
+    fn from(err: ParseBigIntError) -> RispError {
+        RispError::ParseInteger(err)
+    }
+}
 // End: From implementations
 
 impl fmt::Display for RispError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use RispError::{Expr, LexerInitialize, Parse, Peek, Read};
+        use RispError::{Expr, Io, LexerInitialize, Parse, ParseInteger, Peek, Read, Type};
         match self {
             LexerInitialize(str) => write!(f, "{}", str),
             Expr(str) => write!(f, "{}", str),
+            Io(err) => write!(f, "IO error: {}", err),
             Parse(err) => write!(f, "Parse error: {}", err),
+            ParseInteger(err) => write!(f, "Parse error: {}", err),
             Peek(str) => write!(f, "{}", str),
             Read(str) => write!(f, "{}", str),
+            Type { datum, expected } => {
+                write!(f, "The value {} is not of type {}", datum, expected)
+            }
         }
     }
 }
@@ -1,27 +1,131 @@
+use crate::modules::array::expect_array;
 use crate::modules::ast::{Expr, Lambda};
+use crate::modules::builtin::array::expect_subscripts;
+use crate::modules::builtin::structure::define_struct;
+use crate::modules::builtin::types::{type_error, typep};
+use crate::modules::builtin::{self, define};
+use crate::modules::clos::{expect_instance, Class, Generic};
+use crate::modules::compiler::{compile, Chunk};
 use crate::modules::error::RispError;
-use std::{collections::HashMap, rc::Rc};
+use crate::modules::hash_table::expect_hash_table;
+use crate::modules::lint::{parse_declarations, FunctionType, Linter};
+use crate::modules::number::Number;
+use crate::modules::package::symbol_name;
+use crate::modules::structure::{expect_structure, Slot, StructType};
+use std::path::PathBuf;
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap, rc::Rc};
 
-pub type ExprEnv = HashMap<String, Expr>;
+mod binding;
+mod clos;
+mod load;
+mod package;
+mod time;
+mod values;
+mod vm;
 
-pub struct Evaluator {}
+pub use crate::modules::env::ExprEnv;
+pub use load::provide;
 
+// How function bodies are executed: walking their forms, or compiling them
+// to bytecode when they are defined and running it on a stack machine.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Engine {
+    Interpreter,
+    Bytecode,
+}
+
+// The default limit on nested function calls. Calls grow the stack as they
+// need, so that the limit does not depend on the stack size of the thread.
+pub const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+// Calls go on in a new stack segment of `STACK_SEGMENT` bytes when less than
+// `STACK_RED_ZONE` are left, which is more than the nested calls of the
+// evaluator use in between two function calls.
+const STACK_RED_ZONE: usize = 256 * 1024;
+const STACK_SEGMENT: usize = 8 * 1024 * 1024;
+
+pub struct Evaluator {
+    engine: Engine,
+    // Slot accessors defined by DEFSTRUCT, which can be used as SETF places.
+    accessors: HashMap<String, (Rc<StructType>, usize)>,
+    classes: HashMap<String, Rc<Class>>,
+    generics: HashMap<String, Rc<RefCell<Generic>>>,
+    // Slot names of the accessors defined by DEFCLASS.
+    slot_accessors: HashMap<String, String>,
+    // Function types declared by DECLAIM.
+    ftypes: HashMap<String, FunctionType>,
+    // Problems found by linting function definitions, left for the caller to
+    // report.
+    pub warnings: Vec<String>,
+    // All the values of the form just evaluated by `eval_form` when it
+    // returned multiple values. `None` means a single value, the one
+    // returned.
+    values: Option<Vec<Expr>>,
+    // Directories searched by REQUIRE, the ones added first, and then the
+    // ones listed in RISP_PATH.
+    load_path: Vec<PathBuf>,
+    risp_path: Vec<PathBuf>,
+    // The files being loaded, the innermost last.
+    loading: Vec<PathBuf>,
+    // The modules being loaded by REQUIRE, which requiring again does not
+    // load, so that modules can require each other.
+    requiring: Vec<String>,
+    // The number of function calls in progress, which is kept under
+    // `max_depth` so that runaway recursion signals an error rather than
+    // overflowing the stack.
+    depth: usize,
+    max_depth: usize,
+}
+
+// An arithmetic function folding its arguments from the left. With fewer than
+// two it starts from the identity, so that (+) is 0, (- x) negates x and
+// (/ x) is the reciprocal of x.
 macro_rules! basic_op {
+    ($identity: expr, $fn: expr) => {
+        |args: &[Expr]| -> Result<Expr, RispError> {
+            let op: fn(Number, &Number) -> Result<Number, RispError> = $fn;
+            let numbers = parse_list_of_numbers(args)?;
+            let result = match numbers.split_first() {
+                Some((first, rest)) if !rest.is_empty() => {
+                    rest.iter().try_fold(first.clone(), op)?
+                }
+                _ => numbers.iter().try_fold(Number::Fixnum($identity), op)?,
+            };
+            Ok(Expr::Number(result))
+        }
+    };
+}
+
+macro_rules! compare_op {
     ($fn: expr) => {
         |args: &[Expr]| -> Result<Expr, RispError> {
-            let floats = parse_list_of_floats(args)?;
-            let (first, rest) = floats
-                .split_first()
-                .ok_or_else(|| RispError::Expr("Expected at least one number".to_string()))?;
-            Ok(Expr::Number(rest.iter().fold(*first, $fn)))
+            let test: fn(Ordering) -> bool = $fn;
+            let numbers = parse_list_of_numbers(args)?;
+            Ok(builtin::boolean(
+                numbers
+                    .windows(2)
+                    .all(|pair| pair[0].num_cmp(&pair[1]).is_some_and(test)),
+            ))
         }
     };
 }
 
-fn parse_list_of_floats(args: &[Expr]) -> Result<Vec<f64>, RispError> {
+// (/= a b ...) is true when no two numbers are equal.
+fn not_equal(args: &[Expr]) -> Result<Expr, RispError> {
+    let numbers = parse_list_of_numbers(args)?;
+    Ok(builtin::boolean(numbers.iter().enumerate().all(
+        |(i, x)| {
+            numbers[i + 1..]
+                .iter()
+                .all(|y| x.num_cmp(y) != Some(Ordering::Equal))
+        },
+    )))
+}
+
+fn parse_list_of_numbers(args: &[Expr]) -> Result<Vec<Number>, RispError> {
     args.iter()
         .map(|x| match x {
-            Expr::Number(num) => Ok(*num),
+            Expr::Number(num) => Ok(num.clone()),
             _ => Err(RispError::Expr(format!("{} is not number", x))),
         })
         .collect()
@@ -30,39 +134,292 @@ fn parse_list_of_floats(args: &[Expr]) -> Result<Vec<f64>, RispError> {
 fn parse_list_of_symbols(args: &[Expr]) -> Result<Vec<String>, RispError> {
     args.iter()
         .map(|x| match x {
-            Expr::Symbol(symbol) => Ok(symbol.clone()),
+            Expr::Symbol(symbol) => Ok(symbol.to_string()),
             _ => Err(RispError::Expr(format!("{} is not symbol", x))),
         })
         .collect()
 }
 
+fn parse_symbol(x: &Expr) -> Result<String, RispError> {
+    match x {
+        Expr::Symbol(symbol) => Ok(symbol.to_string()),
+        _ => Err(RispError::Expr(format!("{} is not symbol", x))),
+    }
+}
+
+// The function named by a symbol, or the function itself.
+pub fn resolve_function(func: &Expr, env: &ExprEnv) -> Result<Expr, RispError> {
+    match func {
+        Expr::Symbol(sym) => env
+            .get_function(sym)
+            .ok_or_else(|| RispError::Expr(format!("undefined function: {}", sym))),
+        _ => Ok(func.clone()),
+    }
+}
+
+fn parse_lambda_list(args: &Expr) -> Result<Vec<String>, RispError> {
+    match args {
+        Expr::List(list) => parse_list_of_symbols(list),
+        Expr::Nil => Ok(vec![]),
+        _ => Err(RispError::Expr(format!("Invalid list: {}", args))),
+    }
+}
+
 pub fn default_env() -> ExprEnv {
-    let mut env: ExprEnv = HashMap::new();
-    env.insert("+".to_string(), Expr::Func(basic_op!(|sum, x| sum + x)));
-    env.insert("-".to_string(), Expr::Func(basic_op!(|sum, x| sum - x)));
-    env.insert("*".to_string(), Expr::Func(basic_op!(|sum, x| sum * x)));
-    env.insert("/".to_string(), Expr::Func(basic_op!(|sum, x| sum / x)));
+    let mut env = ExprEnv::new();
+    define(
+        &mut env,
+        "+",
+        0,
+        usize::MAX,
+        basic_op!(0, |sum, x| Ok(sum.add(x))),
+    );
+    define(
+        &mut env,
+        "-",
+        1,
+        usize::MAX,
+        basic_op!(0, |sum, x| Ok(sum.sub(x))),
+    );
+    define(
+        &mut env,
+        "*",
+        0,
+        usize::MAX,
+        basic_op!(1, |sum, x| Ok(sum.mul(x))),
+    );
+    define(
+        &mut env,
+        "/",
+        1,
+        usize::MAX,
+        basic_op!(1, |sum, x| sum.div(x)),
+    );
+    define(
+        &mut env,
+        "=",
+        1,
+        usize::MAX,
+        compare_op!(|ord| ord == Ordering::Equal),
+    );
+    define(&mut env, "/=", 1, usize::MAX, not_equal);
+    define(
+        &mut env,
+        "<",
+        1,
+        usize::MAX,
+        compare_op!(|ord| ord == Ordering::Less),
+    );
+    define(
+        &mut env,
+        ">",
+        1,
+        usize::MAX,
+        compare_op!(|ord| ord == Ordering::Greater),
+    );
+    define(
+        &mut env,
+        "<=",
+        1,
+        usize::MAX,
+        compare_op!(|ord| ord != Ordering::Greater),
+    );
+    define(
+        &mut env,
+        ">=",
+        1,
+        usize::MAX,
+        compare_op!(|ord| ord != Ordering::Less),
+    );
+    builtin::register(&mut env);
+    env.export_builtins();
     env
 }
 
+// Applies an option of DEFSTRUCT, given as :NAME or (:NAME value). Only the
+// options naming the functions DEFSTRUCT defines are supported.
+fn parse_struct_option(ty: &mut StructType, option: &Expr) -> Result<(), RispError> {
+    let (name, args) = match option {
+        Expr::Symbol(name) => (name, &[][..]),
+        Expr::List(option) => match option.split_first() {
+            Some((Expr::Symbol(name), args)) => (name, args),
+            _ => {
+                return Err(RispError::Expr(format!(
+                    "DEFSTRUCT: invalid option {}",
+                    option[0]
+                )))
+            }
+        },
+        _ => {
+            return Err(RispError::Expr(format!(
+                "DEFSTRUCT: invalid option {}",
+                option
+            )))
+        }
+    };
+    // The function named by the option: NIL for none, or the default.
+    let function = |default: &Option<String>| match args {
+        [] => Ok(default.clone()),
+        [Expr::Nil] => Ok(None),
+        [Expr::Symbol(function)] => Ok(Some(function.to_string())),
+        _ => Err(RispError::Expr(format!(
+            "DEFSTRUCT: invalid option {}",
+            option
+        ))),
+    };
+    match &**name {
+        ":CONC-NAME" => {
+            ty.conc_name = match args {
+                [] | [Expr::Nil] => String::new(),
+                [Expr::Symbol(prefix)] => symbol_name(prefix).to_string(),
+                [Expr::String(prefix)] => prefix.to_string(),
+                _ => {
+                    return Err(RispError::Expr(format!(
+                        "DEFSTRUCT: invalid option {}",
+                        option
+                    )))
+                }
+            }
+        }
+        ":CONSTRUCTOR" if args.len() > 1 => {
+            return Err(RispError::Expr(format!(
+                "DEFSTRUCT: constructors with a lambda list are not supported: {}",
+                option
+            )))
+        }
+        ":CONSTRUCTOR" => ty.constructor = function(&ty.constructor)?,
+        ":COPIER" => ty.copier = function(&ty.copier)?,
+        ":PREDICATE" => ty.predicate = function(&ty.predicate)?,
+        _ => {
+            return Err(RispError::Expr(format!(
+                "DEFSTRUCT: unsupported option {}",
+                name
+            )))
+        }
+    }
+    Ok(())
+}
+
+// Defines `Evaluator::eval_builtin`, which evaluates the special forms, and
+// `is_special_form` from the one list of them, which the compiler consults
+// to leave the forms it does not translate to the evaluator.
+macro_rules! special_forms {
+    ($evaluator:ident, $args:ident, $env:ident; $($name:literal => $eval:expr,)+) => {
+        pub fn is_special_form(name: &str) -> bool {
+            matches!(name, $($name)|+)
+        }
+
+        impl Evaluator {
+            pub fn eval_builtin(
+                &mut self,
+                first: &Expr,
+                args: &[Expr],
+                env: &mut ExprEnv,
+            ) -> Option<Result<Expr, RispError>> {
+                let ($evaluator, $args, $env) = (self, args, env);
+                match first {
+                    Expr::Symbol(symbol) => match &**symbol {
+                        $($name => Some($eval),)+
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+special_forms! {
+    evaluator, args, env;
+    "QUOTE" => evaluator.eval_quote(args),
+    "IF" => evaluator.eval_if(args, env),
+    "SETQ" => evaluator.eval_setq(args, env),
+    "SETF" => evaluator.eval_setf(args, env),
+    "DEFUN" => evaluator.eval_defun(args, env),
+    "LAMBDA" => evaluator.eval_lambda_expr(args, env),
+    "FUNCTION" => evaluator.eval_function(args, env),
+    "DECLAIM" => evaluator.eval_declaim(args, env),
+    "DECLARE" => Err(RispError::Expr("DECLARE: misplaced declaration".to_string())),
+    "DEFSTRUCT" => evaluator.eval_defstruct(args, env),
+    "DEFCLASS" => evaluator.eval_defclass(args, env),
+    "DEFGENERIC" => evaluator.eval_defgeneric(args, env),
+    "DEFMETHOD" => evaluator.eval_defmethod(args, env),
+    "CHECK-TYPE" => evaluator.eval_check_type(args, env),
+    "LET" => evaluator.eval_let(args, env),
+    "LET*" => evaluator.eval_let_star(args, env),
+    "DEFVAR" => evaluator.eval_defvar(args, env),
+    "DEFPARAMETER" => evaluator.eval_defparameter(args, env),
+    "DEFCONSTANT" => evaluator.eval_defconstant(args, env),
+    "MULTIPLE-VALUE-BIND" => evaluator.eval_multiple_value_bind(args, env),
+    "MULTIPLE-VALUE-LIST" => evaluator.eval_multiple_value_list(args, env),
+    "NTH-VALUE" => evaluator.eval_nth_value(args, env),
+    "DEFPACKAGE" => evaluator.eval_defpackage(args, env),
+    "IN-PACKAGE" => evaluator.eval_in_package(args, env),
+    "TIME" => evaluator.eval_time(args, env),
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Evaluator {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            engine: Engine::Interpreter,
+            accessors: HashMap::new(),
+            classes: HashMap::new(),
+            generics: HashMap::new(),
+            slot_accessors: HashMap::new(),
+            ftypes: HashMap::new(),
+            warnings: vec![],
+            values: None,
+            load_path: vec![],
+            risp_path: load::risp_path(),
+            loading: vec![],
+            requiring: vec![],
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
     }
 
+    pub fn with_engine(self, engine: Engine) -> Self {
+        Self { engine, ..self }
+    }
+
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    // Evaluates a form for its primary value, discarding any other values.
     pub fn eval(&mut self, expr: &Expr, env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let result = self.eval_form(expr, env);
+        if self.values.is_some() {
+            self.values = None;
+        }
+        result
+    }
+
+    // Evaluates a form in a position whose values are passed on to the
+    // caller, such as the body of a function, leaving the secondary values
+    // in `self.values`.
+    fn eval_form(&mut self, expr: &Expr, env: &mut ExprEnv) -> Result<Expr, RispError> {
         match expr {
             Expr::String(_) => Ok(expr.clone()),
+            Expr::Char(_) => Ok(expr.clone()),
+            Expr::Stream(_) => Ok(expr.clone()),
+            Expr::HashTable(_) => Ok(expr.clone()),
+            Expr::Vector(_) | Expr::Array(_) => Ok(expr.clone()),
+            Expr::Struct(_) | Expr::Instance(_) => Ok(expr.clone()),
+            Expr::Package(_) => Ok(expr.clone()),
             Expr::Number(_) => Ok(expr.clone()),
             Expr::Nil => Ok(expr.clone()),
             Expr::True => Ok(expr.clone()),
+            Expr::Symbol(sym) if sym.starts_with(':') => Ok(expr.clone()),
             Expr::Symbol(sym) => match env.get(sym) {
-                Some(expr) => Ok(expr.clone()),
-                None => Err(RispError::Expr(format!(
-                    "not found symbol: {}, env: {}",
-                    sym,
-                    self.print_env(env.clone())
-                ))),
+                Some(expr) => Ok(expr),
+                None => Err(self.unbound_variable(sym, env)),
             },
             Expr::List(list) => {
                 let (first, rest) = list
@@ -71,11 +428,20 @@ impl Evaluator {
                 match self.eval_builtin(first, rest, env) {
                     Some(expr) => expr,
                     None => {
-                        let expr = self.eval(first, env)?;
-                        match expr {
-                            Expr::Func(f) => f(self.eval_args(rest, env)?.as_slice()),
-                            Expr::Lambda(lambda) => self.eval_lambda(lambda, rest, env),
-                            _ => unreachable!(),
+                        // The head of a form names a function, looked up apart
+                        // from the variables, or is a lambda expression.
+                        let func = match first {
+                            Expr::Symbol(sym) => env.get_function(sym).ok_or_else(|| {
+                                RispError::Expr(format!("undefined function: {}", sym))
+                            })?,
+                            _ => self.eval(first, env)?,
+                        };
+                        match func {
+                            Expr::Lambda(lambda) => self.eval_lambda(&lambda, rest, env),
+                            func => {
+                                let args = self.eval_args(rest, env)?;
+                                self.call_function(&func, &args, env)
+                            }
                         }
                     }
                 }
@@ -88,53 +454,168 @@ impl Evaluator {
         args.iter().map(|x| self.eval(x, env)).collect()
     }
 
-    pub fn eval_builtin(
+    // Calls a function with already evaluated arguments for its primary
+    // value.
+    pub fn apply(
         &mut self,
-        first: &Expr,
+        func: &Expr,
         args: &[Expr],
         env: &mut ExprEnv,
-    ) -> Option<Result<Expr, RispError>> {
-        match first {
-            Expr::Symbol(symbol) => match symbol.as_str() {
-                "SETQ" => Some(self.eval_setq(args, env)),
-                "DEFUN" => Some(self.eval_defun(args, env)),
-                _ => None,
-            },
-            _ => None,
+    ) -> Result<Expr, RispError> {
+        let result = self.call_function(func, args, env);
+        if self.values.is_some() {
+            self.values = None;
         }
+        result
+    }
+
+    // Calls a function, passing on all the values it returns.
+    pub fn call_function(
+        &mut self,
+        func: &Expr,
+        args: &[Expr],
+        env: &mut ExprEnv,
+    ) -> Result<Expr, RispError> {
+        match func {
+            Expr::Func(native) => native.call(self, args, env),
+            Expr::Lambda(lambda) => self.apply_lambda(lambda, args, env),
+            _ => Err(RispError::Expr(format!("{} is not function", func))),
+        }
+    }
+
+    // Calls a function designator, which is either a function or a symbol
+    // naming one.
+    pub fn funcall(
+        &mut self,
+        func: &Expr,
+        args: &[Expr],
+        env: &mut ExprEnv,
+    ) -> Result<Expr, RispError> {
+        let func = resolve_function(func, env)?;
+        self.apply(&func, args, env)
     }
 
     pub fn eval_lambda(
         &mut self,
-        lambda: Lambda,
+        lambda: &Lambda,
         outer_args: &[Expr],
         env: &mut ExprEnv,
     ) -> Result<Expr, RispError> {
-        if lambda.args.len() != outer_args.len() {
+        let args = self.eval_args(outer_args, env)?;
+        self.apply_lambda(lambda, &args, env)
+    }
+
+    fn apply_lambda(
+        &mut self,
+        lambda: &Lambda,
+        args: &[Expr],
+        env: &mut ExprEnv,
+    ) -> Result<Expr, RispError> {
+        self.nested_call(|evaluator| evaluator.call_lambda(lambda, args, env))
+    }
+
+    // Runs the body of a function or a method, keeping the calls in
+    // progress under `max_depth` and growing the stack when it runs low.
+    fn nested_call(
+        &mut self,
+        call: impl FnOnce(&mut Self) -> Result<Expr, RispError>,
+    ) -> Result<Expr, RispError> {
+        if self.depth == self.max_depth {
+            return Err(RispError::Expr(format!(
+                "call depth limit exceeded: more than {} nested calls",
+                self.max_depth
+            )));
+        }
+        self.depth += 1;
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || call(self));
+        self.depth -= 1;
+        result
+    }
+
+    fn call_lambda(
+        &mut self,
+        lambda: &Lambda,
+        args: &[Expr],
+        env: &mut ExprEnv,
+    ) -> Result<Expr, RispError> {
+        if lambda.args.len() != args.len() {
             return Err(RispError::Expr(
                 "Number of args and lambda's arg is not same".to_string(),
             ));
         }
 
-        let mut args = Vec::<Expr>::new();
-        for arg in outer_args {
-            args.push(self.eval(arg, env)?);
+        for (arg, spec) in args.iter().zip(&lambda.arg_types) {
+            if let Some(spec) = spec {
+                if !typep(self, arg, spec, env)? {
+                    return Err(type_error(arg, spec));
+                }
+            }
         }
 
-        let mut local_env = env.clone();
-        for (i, k) in lambda.args.iter().enumerate() {
-            let value = args
-                .get(i)
-                .ok_or(RispError::Expr("Not found value from env".to_string()))?;
-            local_env.insert(k.clone(), value.clone());
+        if let Some(code) = &lambda.code {
+            return self.run(code, args, &lambda.env, env);
         }
+        let bindings = lambda.args.iter().cloned().zip(args.iter().cloned());
+        self.with_bindings(&lambda.env, bindings, |evaluator, env| {
+            evaluator.eval_form(&lambda.body, env)
+        })
+    }
 
-        let result = self.eval(&lambda.body, &mut local_env)?;
-        Ok(result)
+    // (lambda (args...) (declare ...)... body)
+    pub fn eval_lambda_expr(&mut self, args: &[Expr], env: &ExprEnv) -> Result<Expr, RispError> {
+        let (args_expr, body) = args
+            .split_first()
+            .ok_or_else(|| RispError::Expr("Unexpected lambda definition".to_string()))?;
+        let (types, body) = parse_declarations(body)?;
+        let body = match body {
+            [body] => body,
+            _ => return Err(RispError::Expr("Unexpected lambda definition".to_string())),
+        };
+        let args = parse_lambda_list(args_expr)?;
+        Ok(Expr::lambda(Lambda {
+            name: None,
+            arg_types: args.iter().map(|arg| types.get(arg).cloned()).collect(),
+            code: self.compile(&args, body, env),
+            args,
+            body: Rc::new(body.clone()),
+            env: env.clone(),
+        }))
     }
 
+    // Compiles the body of a function when the evaluator runs bytecode.
+    fn compile(&self, args: &[String], body: &Expr, env: &ExprEnv) -> Option<Rc<Chunk>> {
+        match self.engine {
+            Engine::Interpreter => None,
+            Engine::Bytecode => compile(args, body, env).map(Rc::new),
+        }
+    }
+
+    // (function name) or (function (lambda ...)), also read as #'x.
+    pub fn eval_function(&mut self, args: &[Expr], env: &ExprEnv) -> Result<Expr, RispError> {
+        match args {
+            [Expr::Symbol(name)] => env
+                .get_function(name)
+                .ok_or_else(|| RispError::Expr(format!("undefined function: {}", name))),
+            [Expr::List(form)] => match form.split_first() {
+                Some((Expr::Symbol(head), lambda)) if &**head == "LAMBDA" => {
+                    self.eval_lambda_expr(lambda, env)
+                }
+                _ => Err(RispError::Expr(format!(
+                    "FUNCTION: invalid function name {}",
+                    args[0]
+                ))),
+            },
+            _ => Err(RispError::Expr(
+                "FUNCTION expects exactly one arg".to_string(),
+            )),
+        }
+    }
+
+    // (defun name (args...) (declare ...)... body) where the argument types
+    // are taken from the declarations or a declaimed FTYPE. The definition is
+    // linted once it is bound, so that recursive calls are checked too.
     pub fn eval_defun(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
-        if args.len() != 3 {
+        if args.len() < 3 {
             return Err(RispError::Expr(
                 "Unexpected function definition".to_string(),
             ));
@@ -150,21 +631,185 @@ impl Evaluator {
         let args_expr = iter
             .next()
             .ok_or(RispError::Expr("Cannot get function args".to_string()))?;
-        let args = match args_expr {
-            Expr::List(list) => Ok(list),
-            _ => Err(RispError::Expr(format!("Invalid list: {}", args_expr))),
-        }?;
-
-        let args = parse_list_of_symbols(args)?;
-        let body = iter
-            .next()
-            .ok_or(RispError::Expr("Cannot get function body".to_string()))?;
-        let lambda = Expr::Lambda(Lambda {
+        let args = parse_lambda_list(args_expr)?;
+        let (types, body) = parse_declarations(iter.as_slice())?;
+        let body = match body {
+            [body] => body,
+            _ => {
+                return Err(RispError::Expr(
+                    "Unexpected function definition".to_string(),
+                ))
+            }
+        };
+        let ftype = self.ftypes.get(&**name);
+        let arg_types = args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| {
+                types
+                    .get(arg)
+                    .or_else(|| ftype.and_then(|ftype| ftype.params.get(i)))
+                    .cloned()
+            })
+            .collect();
+        let lambda = Expr::lambda(Lambda {
+            name: Some(name.to_string()),
+            code: self.compile(&args, body, env),
             args,
+            arg_types,
             body: Rc::new(body.clone()),
+            env: env.clone(),
         });
-        env.insert(name.clone(), lambda);
-        Ok(Expr::String(name.clone()))
+        env.define_function(name, lambda);
+
+        let mut linter = Linter::new(env, &self.ftypes, &self.classes);
+        linter.check_declared_types(&types);
+        linter.check(body);
+        self.warnings.extend(
+            linter
+                .diagnostics
+                .into_iter()
+                .map(|diagnostic| format!("In {}: {}", name, diagnostic)),
+        );
+        Ok(Expr::Symbol(name.clone()))
+    }
+
+    // (declaim (ftype (function (types...) type) names...)...). Other
+    // declarations are accepted and ignored.
+    pub fn eval_declaim(&mut self, args: &[Expr], env: &ExprEnv) -> Result<Expr, RispError> {
+        for spec in args {
+            let (ftype, names) = match spec {
+                Expr::List(spec) => match spec.as_ref() {
+                    [Expr::Symbol(head), ftype, names @ ..] if &**head == "FTYPE" => (ftype, names),
+                    _ => continue,
+                },
+                _ => {
+                    return Err(RispError::Expr(format!(
+                        "DECLAIM: invalid declaration {}",
+                        spec
+                    )))
+                }
+            };
+            for name in names {
+                let name = parse_symbol(name)?;
+                let ftype = FunctionType::parse(ftype)?;
+                let mut linter = Linter::new(env, &self.ftypes, &self.classes);
+                linter.check_function_type(&ftype, &name);
+                self.warnings.extend(
+                    linter
+                        .diagnostics
+                        .into_iter()
+                        .map(|diagnostic| format!("In DECLAIM: {}", diagnostic)),
+                );
+                self.ftypes.insert(name, ftype);
+            }
+        }
+        Ok(Expr::Nil)
+    }
+
+    // Statically checks a form against the functions defined in `env` and
+    // returns the problems found.
+    pub fn lint(&self, form: &Expr, env: &ExprEnv) -> Vec<String> {
+        let mut linter = Linter::new(env, &self.ftypes, &self.classes);
+        linter.check(form);
+        linter.diagnostics
+    }
+
+    // (defstruct name slot-name-or-(slot-name default)...)
+    pub fn eval_defstruct(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let (name, options, specs) = match args.split_first() {
+            Some((Expr::Symbol(name), specs)) => (name, &[][..], specs),
+            Some((Expr::List(head), specs)) => match head.split_first() {
+                Some((Expr::Symbol(name), options)) => (name, options, specs),
+                _ => {
+                    return Err(RispError::Expr(
+                        "DEFSTRUCT: invalid structure name".to_string(),
+                    ))
+                }
+            },
+            _ => {
+                return Err(RispError::Expr(
+                    "DEFSTRUCT: invalid structure name".to_string(),
+                ))
+            }
+        };
+        let slots = specs
+            .iter()
+            .map(|spec| {
+                let (name, default) = match spec {
+                    Expr::Symbol(slot) => (slot, Expr::Nil),
+                    Expr::List(spec) => match spec.as_ref() {
+                        [Expr::Symbol(slot)] => (slot, Expr::Nil),
+                        [Expr::Symbol(slot), default, ..] => (slot, default.clone()),
+                        _ => {
+                            return Err(RispError::Expr(format!(
+                                "DEFSTRUCT: invalid slot {}",
+                                spec[0]
+                            )))
+                        }
+                    },
+                    _ => return Err(RispError::Expr(format!("DEFSTRUCT: invalid slot {}", spec))),
+                };
+                Ok(Slot {
+                    name: symbol_name(name).to_string(),
+                    default,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut ty = StructType::new(name, slots);
+        for option in options {
+            parse_struct_option(&mut ty, option)?;
+        }
+        let ty = Rc::new(ty);
+        for (i, slot) in ty.slots.iter().enumerate() {
+            self.accessors
+                .insert(ty.accessor_name(slot), (ty.clone(), i));
+        }
+        define_struct(env, ty);
+        Ok(Expr::Symbol(name.clone()))
+    }
+
+    // (check-type place type) signals a type error unless the value of the
+    // place is of the unevaluated type.
+    pub fn eval_check_type(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let (place, spec) = match args {
+            [place, spec] => (place, spec),
+            _ => {
+                return Err(RispError::Expr(
+                    "CHECK-TYPE: invalid number of arguments".to_string(),
+                ))
+            }
+        };
+        let value = self.eval(place, env)?;
+        if typep(self, &value, spec, env)? {
+            Ok(Expr::Nil)
+        } else {
+            Err(type_error(&value, spec))
+        }
+    }
+
+    // (if test then [else])
+    pub fn eval_if(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let (test, then, otherwise) = match args {
+            [test, then] => (test, then, &Expr::Nil),
+            [test, then, otherwise] => (test, then, otherwise),
+            _ => {
+                return Err(RispError::Expr(
+                    "IF: invalid number of arguments".to_string(),
+                ))
+            }
+        };
+        match self.eval(test, env)? {
+            Expr::Nil => self.eval_form(otherwise, env),
+            _ => self.eval_form(then, env),
+        }
+    }
+
+    pub fn eval_quote(&mut self, args: &[Expr]) -> Result<Expr, RispError> {
+        match args {
+            [expr] => Ok(expr.clone()),
+            _ => Err(RispError::Expr("QUOTE expects exactly one arg".to_string())),
+        }
     }
 
     pub fn eval_setq(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
@@ -180,12 +825,102 @@ impl Evaluator {
             .ok_or(RispError::Expr("Expected second arg".to_string()))?;
         let value = self.eval(second, env)?;
 
-        env.insert(key, value.clone());
+        env.set(&key, value.clone())?;
         Ok(value.clone())
     }
 
+    // (setf place value ...) where a place is a symbol, (gethash key table),
+    // (aref array subscripts...), (slot-value instance slot-name),
+    // (symbol-value symbol), (symbol-function symbol) or a slot accessor of a
+    // structure or class.
+    pub fn eval_setf(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        if args.is_empty() || !args.len().is_multiple_of(2) {
+            return Err(RispError::Expr(
+                "SETF expects pairs of place and value".to_string(),
+            ));
+        }
+        let mut result = Expr::Nil;
+        for pair in args.chunks(2) {
+            result = match &pair[0] {
+                Expr::Symbol(_) => self.eval_setq(pair, env)?,
+                Expr::List(place) => match place.as_ref() {
+                    [Expr::Symbol(accessor), key, table, ..] if &**accessor == "GETHASH" => {
+                        let key = self.eval(key, env)?;
+                        let table = expect_hash_table(&self.eval(table, env)?)?;
+                        let value = self.eval(&pair[1], env)?;
+                        table.borrow_mut().insert(key, value.clone());
+                        value
+                    }
+                    [Expr::Symbol(accessor), array, subscripts @ ..] if &**accessor == "AREF" => {
+                        let array = expect_array(&self.eval(array, env)?)?;
+                        let subscripts = expect_subscripts(&self.eval_args(subscripts, env)?)?;
+                        let value = self.eval(&pair[1], env)?;
+                        array.borrow_mut().set(&subscripts, value.clone())?;
+                        value
+                    }
+                    [Expr::Symbol(accessor), object]
+                        if self.accessors.contains_key(&**accessor) =>
+                    {
+                        let (ty, i) = self.accessors[&**accessor].clone();
+                        let structure = expect_structure(&self.eval(object, env)?, &ty)?;
+                        let value = self.eval(&pair[1], env)?;
+                        structure.borrow_mut().values[i] = value.clone();
+                        value
+                    }
+                    [Expr::Symbol(accessor), object, slot] if &**accessor == "SLOT-VALUE" => {
+                        let instance = expect_instance(&self.eval(object, env)?)?;
+                        let slot = parse_symbol(&self.eval(slot, env)?)?;
+                        let value = self.eval(&pair[1], env)?;
+                        instance.borrow_mut().set_slot_value(&slot, value.clone())?;
+                        value
+                    }
+                    [Expr::Symbol(accessor), symbol] if &**accessor == "SYMBOL-VALUE" => {
+                        let symbol = parse_symbol(&self.eval(symbol, env)?)?;
+                        let value = self.eval(&pair[1], env)?;
+                        env.set_global(&symbol, value.clone())?;
+                        value
+                    }
+                    [Expr::Symbol(accessor), symbol] if &**accessor == "SYMBOL-FUNCTION" => {
+                        let symbol = parse_symbol(&self.eval(symbol, env)?)?;
+                        let value = self.eval(&pair[1], env)?;
+                        if !matches!(value, Expr::Func(_) | Expr::Lambda(_)) {
+                            return Err(RispError::Expr(format!("{} is not function", value)));
+                        }
+                        env.define_function(&symbol, value.clone());
+                        value
+                    }
+                    [Expr::Symbol(accessor), object]
+                        if self.slot_accessors.contains_key(&**accessor) =>
+                    {
+                        let slot = self.slot_accessors[&**accessor].clone();
+                        let instance = expect_instance(&self.eval(object, env)?)?;
+                        let value = self.eval(&pair[1], env)?;
+                        instance.borrow_mut().set_slot_value(&slot, value.clone())?;
+                        value
+                    }
+                    _ => {
+                        return Err(RispError::Expr(format!(
+                            "SETF: unsupported place {}",
+                            pair[0]
+                        )))
+                    }
+                },
+                place => return Err(RispError::Expr(format!("SETF: invalid place {}", place))),
+            };
+        }
+        Ok(result)
+    }
+
+    fn unbound_variable(&mut self, name: &str, env: &ExprEnv) -> RispError {
+        RispError::Expr(format!(
+            "not found symbol: {}, env: {}",
+            name,
+            self.print_env(env.clone())
+        ))
+    }
+
     fn print_env(&mut self, env: ExprEnv) -> String {
-        env.clone()
+        env.bindings()
             .iter()
             .map(|x| format!("{}={}", x.0, x.1))
             .collect::<Vec<String>>()
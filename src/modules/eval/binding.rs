@@ -0,0 +1,198 @@
+use super::{parse_symbol, Evaluator, ExprEnv};
+use crate::modules::ast::Expr;
+use crate::modules::env::SpecialBindings;
+use crate::modules::equality::eql;
+use crate::modules::error::RispError;
+use crate::modules::package::symbol_name;
+
+impl Evaluator {
+    // Runs `body` in a copy of `env` extending it with the bindings. Special
+    // variables are restored afterwards, also when the body fails.
+    pub fn with_bindings(
+        &mut self,
+        env: &ExprEnv,
+        bindings: impl IntoIterator<Item = (String, Expr)>,
+        body: impl FnOnce(&mut Self, &mut ExprEnv) -> Result<Expr, RispError>,
+    ) -> Result<Expr, RispError> {
+        let mut local_env = env.clone();
+        let mut specials = SpecialBindings::default();
+        for (name, value) in bindings {
+            if let Err(err) = local_env.bind(&name, value, &mut specials) {
+                local_env.unbind(specials);
+                return Err(err);
+            }
+        }
+        let result = body(self, &mut local_env);
+        local_env.unbind(specials);
+        result
+    }
+
+    // (let ((var value)...) body...) evaluating all the values before binding
+    // any variable.
+    pub fn eval_let(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let (bindings, body) = parse_let("LET", args)?;
+        let bindings = bindings
+            .into_iter()
+            .map(|(var, form)| Ok((var, self.eval(&form, env)?)))
+            .collect::<Result<Vec<_>, RispError>>()?;
+        self.with_bindings(env, bindings, |evaluator, env| {
+            evaluator.eval_body(body, env)
+        })
+    }
+
+    // (let* ((var value)...) body...) where each value sees the variables
+    // bound before it.
+    pub fn eval_let_star(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let (bindings, body) = parse_let("LET*", args)?;
+        let mut local_env = env.clone();
+        let mut specials = SpecialBindings::default();
+        let mut result = Ok(());
+        for (var, form) in bindings {
+            result = self
+                .eval(&form, &mut local_env)
+                .and_then(|value| local_env.bind(&var, value, &mut specials));
+            if result.is_err() {
+                break;
+            }
+        }
+        let result = result.and_then(|_| self.eval_body(body, &mut local_env));
+        local_env.unbind(specials);
+        result
+    }
+
+    // (defvar name [value [documentation]]) proclaims the variable special
+    // and sets it only when it is unbound.
+    pub fn eval_defvar(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let (name, value) = parse_definition("DEFVAR", args, 1)?;
+        self.warn_earmuffs("DEFVAR", &name);
+        env.proclaim_special(&name);
+        if let Some(value) = value {
+            if !env.is_defined(&name) {
+                let value = self.eval(value, env)?;
+                env.define(&name, value);
+            }
+        }
+        Ok(Expr::Symbol(name.into()))
+    }
+
+    // (defparameter name value [documentation]) proclaims the variable
+    // special and always sets it.
+    pub fn eval_defparameter(
+        &mut self,
+        args: &[Expr],
+        env: &mut ExprEnv,
+    ) -> Result<Expr, RispError> {
+        let (name, value) = parse_definition("DEFPARAMETER", args, 2)?;
+        self.warn_earmuffs("DEFPARAMETER", &name);
+        env.proclaim_special(&name);
+        let value = self.eval(value.unwrap(), env)?;
+        env.define(&name, value);
+        Ok(Expr::Symbol(name.into()))
+    }
+
+    // (defconstant name value [documentation]) which may be evaluated again
+    // with the same value.
+    pub fn eval_defconstant(
+        &mut self,
+        args: &[Expr],
+        env: &mut ExprEnv,
+    ) -> Result<Expr, RispError> {
+        let (name, value) = parse_definition("DEFCONSTANT", args, 2)?;
+        let value = self.eval(value.unwrap(), env)?;
+        if env.is_special(&name) {
+            return Err(RispError::Expr(format!(
+                "DEFCONSTANT: {} is a special variable",
+                name
+            )));
+        }
+        if env.is_constant(&name) {
+            let previous = env.get(&name).unwrap_or(Expr::Nil);
+            if !eql(&previous, &value) {
+                return Err(RispError::Expr(format!(
+                    "DEFCONSTANT: {} is already a constant with value {}",
+                    name, previous
+                )));
+            }
+        }
+        env.define_constant(&name, value);
+        Ok(Expr::Symbol(name.into()))
+    }
+
+    // Special variables are conventionally named *LIKE-THIS* so that they are
+    // not mistaken for lexical ones.
+    fn warn_earmuffs(&mut self, form: &str, name: &str) {
+        let name = symbol_name(name);
+        if !(name.len() > 2 && name.starts_with('*') && name.ends_with('*')) {
+            self.warnings.push(format!(
+                "{}: special variable {} is not named *{}*",
+                form, name, name
+            ));
+        }
+    }
+}
+
+// Variables with their unevaluated values.
+type Bindings = Vec<(String, Expr)>;
+
+// Splits the bindings of a LET into variables and unevaluated values, where a
+// bare variable or (var) is bound to NIL.
+fn parse_let<'a>(form: &str, args: &'a [Expr]) -> Result<(Bindings, &'a [Expr]), RispError> {
+    let (bindings, body) = args
+        .split_first()
+        .ok_or_else(|| RispError::Expr(format!("{}: missing bindings", form)))?;
+    let bindings = match bindings {
+        Expr::List(bindings) => bindings.to_vec(),
+        Expr::Nil => vec![],
+        _ => {
+            return Err(RispError::Expr(format!(
+                "{}: invalid bindings {}",
+                form, bindings
+            )))
+        }
+    };
+    let bindings = bindings
+        .iter()
+        .map(|binding| match binding {
+            Expr::Symbol(var) => Ok((var.to_string(), Expr::Nil)),
+            Expr::List(binding) => match binding.as_ref() {
+                [var] => Ok((parse_symbol(var)?, Expr::Nil)),
+                [var, value] => Ok((parse_symbol(var)?, value.clone())),
+                _ => Err(RispError::Expr(format!(
+                    "{}: invalid binding {}",
+                    form,
+                    Expr::List(binding.clone())
+                ))),
+            },
+            _ => Err(RispError::Expr(format!(
+                "{}: invalid binding {}",
+                form, binding
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((bindings, body))
+}
+
+// Parses (name value [documentation]) where the value is optional when
+// `required` is 1.
+fn parse_definition<'a>(
+    form: &str,
+    args: &'a [Expr],
+    required: usize,
+) -> Result<(String, Option<&'a Expr>), RispError> {
+    if args.len() < required || args.len() > 3 {
+        return Err(RispError::Expr(format!(
+            "{}: invalid number of arguments: {}",
+            form,
+            args.len()
+        )));
+    }
+    if let Some(documentation) = args.get(2) {
+        if !matches!(documentation, Expr::String(_)) {
+            return Err(RispError::Expr(format!(
+                "{}: invalid documentation {}",
+                form, documentation
+            )));
+        }
+    }
+    Ok((parse_symbol(&args[0])?, args.get(1)))
+}
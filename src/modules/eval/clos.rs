@@ -0,0 +1,318 @@
+use super::{parse_symbol, Evaluator, ExprEnv};
+use crate::modules::ast::{Expr, Native};
+use crate::modules::builtin::{boolean, define_native};
+use crate::modules::clos::{
+    combine, expect_instance, Class, EffectiveMethod, Generic, Method, Qualifier, SlotDefinition,
+};
+use crate::modules::error::RispError;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+impl Evaluator {
+    pub fn find_class(&self, name: &str) -> Option<Rc<Class>> {
+        self.classes.get(name).cloned()
+    }
+
+    // (defclass name (superclass) (slot-name-or-(slot-name options...)...))
+    // where the slot options are :initarg, :initform, :accessor and :reader.
+    pub fn eval_defclass(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let (name, superclasses, specs) = match args {
+            [Expr::Symbol(name), superclasses, specs, ..] => (name, superclasses, specs),
+            _ => return Err(RispError::Expr("DEFCLASS: invalid definition".to_string())),
+        };
+        let superclass = match superclasses {
+            Expr::Nil => None,
+            Expr::List(names) => match names.as_ref() {
+                [superclass] => {
+                    let superclass = parse_symbol(superclass)?;
+                    Some(self.find_class(&superclass).ok_or_else(|| {
+                        RispError::Expr(format!("DEFCLASS: undefined class {}", superclass))
+                    })?)
+                }
+                _ => {
+                    return Err(RispError::Expr(
+                        "DEFCLASS: only single inheritance is supported".to_string(),
+                    ))
+                }
+            },
+            _ => {
+                return Err(RispError::Expr(format!(
+                    "DEFCLASS: invalid superclasses {}",
+                    superclasses
+                )))
+            }
+        };
+        let specs = match specs {
+            Expr::List(specs) => specs.to_vec(),
+            Expr::Nil => vec![],
+            _ => {
+                return Err(RispError::Expr(format!(
+                    "DEFCLASS: invalid slots {}",
+                    specs
+                )))
+            }
+        };
+
+        let mut slots = match &superclass {
+            Some(superclass) => superclass.slots.clone(),
+            None => vec![],
+        };
+        let mut accessors = vec![];
+        for spec in &specs {
+            let (slot, options) = match spec {
+                Expr::Symbol(slot) => (slot.to_string(), &[][..]),
+                Expr::List(spec) => (parse_symbol(&spec[0])?, &spec[1..]),
+                _ => return Err(RispError::Expr(format!("DEFCLASS: invalid slot {}", spec))),
+            };
+            if !options.len().is_multiple_of(2) {
+                return Err(RispError::Expr(format!(
+                    "DEFCLASS: odd number of options for slot {}",
+                    slot
+                )));
+            }
+            let mut definition = SlotDefinition {
+                name: slot.clone(),
+                initargs: vec![],
+                initform: None,
+            };
+            for option in options.chunks(2) {
+                match (&option[0], &option[1]) {
+                    (Expr::Symbol(key), Expr::Symbol(initarg)) if &**key == ":INITARG" => {
+                        definition.initargs.push(initarg.to_string())
+                    }
+                    (Expr::Symbol(key), initform) if &**key == ":INITFORM" => {
+                        definition.initform = Some(initform.clone())
+                    }
+                    (Expr::Symbol(key), Expr::Symbol(accessor)) if &**key == ":ACCESSOR" => {
+                        accessors.push((accessor.to_string(), slot.clone(), true))
+                    }
+                    (Expr::Symbol(key), Expr::Symbol(reader)) if &**key == ":READER" => {
+                        accessors.push((reader.to_string(), slot.clone(), false))
+                    }
+                    (Expr::Symbol(key), _) if &**key == ":DOCUMENTATION" || &**key == ":TYPE" => {}
+                    (key, _) => {
+                        return Err(RispError::Expr(format!(
+                            "DEFCLASS: invalid slot option {}",
+                            key
+                        )))
+                    }
+                }
+            }
+            match slots.iter().position(|other| other.name == slot) {
+                Some(i) => slots[i] = definition,
+                None => slots.push(definition),
+            }
+        }
+
+        let class = Rc::new(Class {
+            name: name.to_string(),
+            superclass,
+            slots,
+        });
+        self.classes.insert(name.to_string(), class);
+        for (accessor, slot, writable) in accessors {
+            if writable {
+                self.slot_accessors.insert(accessor.clone(), slot.clone());
+            }
+            define_native(env, &accessor, 1, 1, move |_, args, _| {
+                expect_instance(&args[0])?.borrow().slot_value(&slot)
+            });
+        }
+        Ok(Expr::Symbol(name.clone()))
+    }
+
+    // Returns the generic function with the given name, creating it and
+    // binding its dispatcher when it does not exist yet or takes a different
+    // number of arguments.
+    fn ensure_generic(
+        &mut self,
+        name: &str,
+        required: usize,
+        env: &mut ExprEnv,
+    ) -> Rc<RefCell<Generic>> {
+        if let Some(generic) = self.generics.get(name) {
+            if generic.borrow().required == required {
+                return generic.clone();
+            }
+        }
+        let generic = Rc::new(RefCell::new(Generic {
+            name: name.to_string(),
+            required,
+            methods: vec![],
+        }));
+        self.generics.insert(name.to_string(), generic.clone());
+        let dispatcher = generic.clone();
+        define_native(env, name, required, required, move |evaluator, args, _| {
+            evaluator.call_generic(&dispatcher, args)
+        });
+        generic
+    }
+
+    // (defgeneric name (params...) options...)
+    pub fn eval_defgeneric(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let (name, params) = match args {
+            [Expr::Symbol(name), params, ..] => (name, params),
+            _ => {
+                return Err(RispError::Expr(
+                    "DEFGENERIC: invalid definition".to_string(),
+                ))
+            }
+        };
+        let required = match params {
+            Expr::List(params) => params.len(),
+            Expr::Nil => 0,
+            _ => {
+                return Err(RispError::Expr(format!(
+                    "DEFGENERIC: invalid lambda list {}",
+                    params
+                )))
+            }
+        };
+        self.ensure_generic(name, required, env);
+        Ok(Expr::Symbol(name.clone()))
+    }
+
+    // (defmethod name [qualifier] ((param class) param...) body...)
+    pub fn eval_defmethod(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let (name, rest) = match args.split_first() {
+            Some((Expr::Symbol(name), rest)) => (name, rest),
+            _ => {
+                return Err(RispError::Expr(
+                    "DEFMETHOD: invalid method name".to_string(),
+                ))
+            }
+        };
+        let (qualifier, rest) = match rest.split_first() {
+            Some((Expr::Symbol(qualifier), rest)) if qualifier.starts_with(':') => {
+                let qualifier = Qualifier::from_name(qualifier).ok_or_else(|| {
+                    RispError::Expr(format!("DEFMETHOD: unsupported qualifier {}", qualifier))
+                })?;
+                (qualifier, rest)
+            }
+            _ => (Qualifier::Primary, rest),
+        };
+        let (lambda_list, body) = rest
+            .split_first()
+            .ok_or_else(|| RispError::Expr("DEFMETHOD: missing lambda list".to_string()))?;
+        let lambda_list = match lambda_list {
+            Expr::List(params) => params.to_vec(),
+            Expr::Nil => vec![],
+            _ => {
+                return Err(RispError::Expr(format!(
+                    "DEFMETHOD: invalid lambda list {}",
+                    lambda_list
+                )))
+            }
+        };
+        let (params, specializers) = lambda_list
+            .iter()
+            .map(|param| match param {
+                Expr::Symbol(param) => Ok((param.to_string(), "T".to_string())),
+                Expr::List(spec) => match spec.as_ref() {
+                    [param, Expr::True] => Ok((parse_symbol(param)?, "T".to_string())),
+                    [param, class] => Ok((parse_symbol(param)?, parse_symbol(class)?)),
+                    _ => Err(RispError::Expr(format!(
+                        "DEFMETHOD: invalid parameter {}",
+                        param
+                    ))),
+                },
+                _ => Err(RispError::Expr(format!(
+                    "DEFMETHOD: invalid parameter {}",
+                    param
+                ))),
+            })
+            .collect::<Result<(Vec<_>, Vec<_>), RispError>>()?;
+        let generic = self.ensure_generic(name, params.len(), env);
+        generic.borrow_mut().add_method(Method {
+            qualifier,
+            specializers,
+            params,
+            body: body.into(),
+            env: env.clone(),
+        })?;
+        Ok(Expr::Symbol(name.clone()))
+    }
+
+    fn call_generic(
+        &mut self,
+        generic: &RefCell<Generic>,
+        args: &[Expr],
+    ) -> Result<Expr, RispError> {
+        // Methods may be added while the effective method runs, so the
+        // generic function is not kept borrowed.
+        let effective = combine(&generic.borrow().applicable_methods(args));
+        if !effective.has_next() {
+            return Err(RispError::Expr(format!(
+                "No applicable method for {} with arguments {}",
+                generic.borrow().name,
+                Expr::List(args.into())
+            )));
+        }
+        self.call_effective(&effective, args)
+    }
+
+    // Runs the around methods, which decide whether to continue with
+    // CALL-NEXT-METHOD, then the before methods, the most specific primary
+    // method and the after methods.
+    fn call_effective(
+        &mut self,
+        effective: &EffectiveMethod,
+        args: &[Expr],
+    ) -> Result<Expr, RispError> {
+        if let Some(around) = effective.arounds.first() {
+            return self.call_method(around, args, effective.after_around());
+        }
+        let primary = effective
+            .primaries
+            .first()
+            .ok_or_else(|| RispError::Expr("No next method".to_string()))?;
+        for before in &effective.befores {
+            self.call_method(before, args, EffectiveMethod::default())?;
+        }
+        let result = self.call_method(primary, args, effective.after_primary())?;
+        let values = self.values.take();
+        for after in &effective.afters {
+            self.call_method(after, args, EffectiveMethod::default())?;
+        }
+        self.values = values;
+        Ok(result)
+    }
+
+    fn call_method(
+        &mut self,
+        method: &Method,
+        args: &[Expr],
+        next: EffectiveMethod,
+    ) -> Result<Expr, RispError> {
+        let mut method_env = method.env.clone();
+        let has_next = next.has_next();
+        method_env.bind_function(
+            "NEXT-METHOD-P",
+            Expr::Func(Rc::new(Native::new(
+                "NEXT-METHOD-P",
+                0,
+                0,
+                move |_, _, _| Ok(boolean(has_next)),
+            ))),
+        );
+        let original = args.to_vec();
+        method_env.bind_function(
+            "CALL-NEXT-METHOD",
+            Expr::Func(Rc::new(Native::new(
+                "CALL-NEXT-METHOD",
+                0,
+                usize::MAX,
+                move |evaluator, args, _| {
+                    let args = if args.is_empty() { &original } else { args };
+                    evaluator.call_effective(&next, args)
+                },
+            ))),
+        );
+        let bindings = method.params.iter().cloned().zip(args.iter().cloned());
+        self.nested_call(|evaluator| {
+            evaluator.with_bindings(&method_env, bindings, |evaluator, env| {
+                evaluator.eval_body(&method.body, env)
+            })
+        })
+    }
+}
@@ -0,0 +1,154 @@
+use super::{Evaluator, ExprEnv};
+use crate::modules::ast::Expr;
+use crate::modules::builtin::list;
+use crate::modules::error::RispError;
+use crate::modules::lexer::Lexer;
+use crate::modules::parser::Parser;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+impl Evaluator {
+    // Adds a directory searched by REQUIRE after the ones added before.
+    pub fn add_load_path(&mut self, dir: impl Into<PathBuf>) {
+        self.load_path.push(dir.into());
+    }
+
+    // Resolves a relative path from the directory of the file being loaded,
+    // or from the working directory at the top level.
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        match self.loading.last().and_then(|file| file.parent()) {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    // Marks a file as being loaded until `end_load`, failing when it is
+    // already being loaded by one of the files loading it.
+    pub fn begin_load(&mut self, path: &Path) -> Result<(), RispError> {
+        let path = fs::canonicalize(path).map_err(|err| {
+            RispError::Expr(format!("LOAD: cannot open {}: {}", path.display(), err))
+        })?;
+        if let Some(i) = self.loading.iter().position(|file| *file == path) {
+            let cycle = self.loading[i..]
+                .iter()
+                .chain([&path])
+                .map(|file| file.display().to_string())
+                .collect::<Vec<_>>();
+            return Err(RispError::Expr(format!(
+                "LOAD: circular load {}",
+                cycle.join(" -> ")
+            )));
+        }
+        self.loading.push(path);
+        Ok(())
+    }
+
+    pub fn end_load(&mut self) {
+        self.loading.pop();
+    }
+
+    // Evaluates the forms of a file in the global environment. The current
+    // package is restored afterwards, so that IN-PACKAGE only applies to the
+    // rest of the file.
+    pub fn load(&mut self, path: &Path, env: &ExprEnv) -> Result<(), RispError> {
+        let path = self.resolve_path(path);
+        self.begin_load(&path)?;
+        let package = env.packages().borrow().current();
+        let result = self.load_forms(&path, &mut env.toplevel());
+        env.packages().borrow_mut().restore(package);
+        self.end_load();
+        result
+    }
+
+    fn load_forms(&mut self, path: &Path, env: &mut ExprEnv) -> Result<(), RispError> {
+        let in_file = |err: RispError| RispError::Expr(format!("{}: {}", path.display(), err));
+        let source = fs::read_to_string(path).map_err(|err| in_file(err.into()))?;
+        if source.is_empty() {
+            return Ok(());
+        }
+        let mut parser = Parser::new(Lexer::new(source)?).with_env(env);
+        while let Some(form) = parser.read_next(self).map_err(in_file)? {
+            self.eval(&form, env).map_err(in_file)?;
+        }
+        Ok(())
+    }
+
+    // Loads a module unless it has been provided or is being loaded, from
+    // the given files or else from NAME.lisp, or name.lisp, searched next to
+    // the file being loaded and then on the load path. Returns whether it
+    // was loaded.
+    pub fn require(
+        &mut self,
+        name: &str,
+        files: Option<Vec<PathBuf>>,
+        env: &mut ExprEnv,
+    ) -> Result<bool, RispError> {
+        if modules(env).iter().any(|module| module == name)
+            || self.requiring.iter().any(|module| module == name)
+        {
+            return Ok(false);
+        }
+        let files = match files {
+            Some(files) => files,
+            None => vec![self.find_module(name)?],
+        };
+        self.requiring.push(name.to_string());
+        let result = files.iter().try_for_each(|file| self.load(file, env));
+        self.requiring.pop();
+        result?;
+        provide(name, env)?;
+        Ok(true)
+    }
+
+    fn find_module(&self, name: &str) -> Result<PathBuf, RispError> {
+        let files = [
+            format!("{}.lisp", name),
+            format!("{}.lisp", name.to_lowercase()),
+        ];
+        for file in &files {
+            let path = self.resolve_path(Path::new(file));
+            if path.is_file() {
+                return Ok(path);
+            }
+        }
+        for dir in self.load_path.iter().chain(&self.risp_path) {
+            for file in &files {
+                let path = dir.join(file);
+                if path.is_file() {
+                    return Ok(path);
+                }
+            }
+        }
+        Err(RispError::Expr(format!(
+            "REQUIRE: module {} not found",
+            name
+        )))
+    }
+}
+
+// The directories listed in the RISP_PATH environment variable.
+pub fn risp_path() -> Vec<PathBuf> {
+    env::var_os("RISP_PATH")
+        .map(|paths| env::split_paths(&paths).collect())
+        .unwrap_or_default()
+}
+
+// The names of the modules provided so far, kept in *MODULES*.
+fn modules(env: &ExprEnv) -> Vec<String> {
+    match env.get_global("*MODULES*") {
+        Some(Expr::List(modules)) => modules.iter().map(|module| module.to_string()).collect(),
+        _ => vec![],
+    }
+}
+
+// Adds a module to *MODULES* unless it is there already.
+pub fn provide(name: &str, env: &mut ExprEnv) -> Result<(), RispError> {
+    let mut modules = match env.get_global("*MODULES*") {
+        Some(Expr::List(modules)) => modules.to_vec(),
+        _ => vec![],
+    };
+    if !modules.iter().any(|module| module.to_string() == name) {
+        modules.insert(0, Expr::string(name));
+    }
+    env.set_global("*MODULES*", list(modules))
+}
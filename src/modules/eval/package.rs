@@ -0,0 +1,66 @@
+use super::{Evaluator, ExprEnv};
+use crate::modules::ast::Expr;
+use crate::modules::error::RispError;
+use crate::modules::package::{designated_name, COMMON_LISP};
+
+impl Evaluator {
+    // (defpackage name (:use packages...) (:export symbols...)
+    // (:nicknames names...)) where a package uses COMMON-LISP unless it
+    // says otherwise.
+    pub fn eval_defpackage(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let (name, options) = args
+            .split_first()
+            .ok_or_else(|| RispError::Expr("DEFPACKAGE: missing package name".to_string()))?;
+        let name = designated_name(name)?;
+        let mut uses = None;
+        let mut exports = vec![];
+        let mut nicknames = vec![];
+        for option in options {
+            let (key, names) = match option {
+                Expr::List(option) => match option.split_first() {
+                    Some((Expr::Symbol(key), names)) => (&**key, names),
+                    _ => return Err(invalid_option(&Expr::List(option.clone()))),
+                },
+                _ => return Err(invalid_option(option)),
+            };
+            let names = || {
+                names
+                    .iter()
+                    .map(designated_name)
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            match key {
+                ":USE" => uses.get_or_insert_with(Vec::new).extend(names()?),
+                ":EXPORT" => exports.extend(names()?),
+                ":NICKNAMES" => nicknames.extend(names()?),
+                ":DOCUMENTATION" => {}
+                _ => return Err(invalid_option(option)),
+            }
+        }
+        let uses = uses.unwrap_or_else(|| vec![COMMON_LISP.to_string()]);
+        let package = env
+            .packages()
+            .borrow_mut()
+            .define(&name, &nicknames, &uses, &exports)?;
+        Ok(Expr::Package(package))
+    }
+
+    // (in-package name) makes the reader intern the following symbols in
+    // the package.
+    pub fn eval_in_package(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        match args {
+            [name] => {
+                let name = designated_name(name)?;
+                let package = env.packages().borrow_mut().set_current(&name)?;
+                Ok(Expr::Package(package))
+            }
+            _ => Err(RispError::Expr(
+                "IN-PACKAGE: invalid number of arguments".to_string(),
+            )),
+        }
+    }
+}
+
+fn invalid_option(option: &Expr) -> RispError {
+    RispError::Expr(format!("DEFPACKAGE: invalid option {}", option))
+}
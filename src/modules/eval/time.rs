@@ -0,0 +1,32 @@
+use super::{Evaluator, ExprEnv};
+use crate::modules::ast::Expr;
+use crate::modules::builtin::printer::output_stream;
+use crate::modules::error::RispError;
+use crate::modules::gc;
+use std::time::Instant;
+
+impl Evaluator {
+    // (time form) evaluates the form, prints the time it took and the number
+    // of values it allocated, and returns its values.
+    pub fn eval_time(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let form = match args {
+            [form] => form,
+            _ => {
+                return Err(RispError::Expr(
+                    "TIME: invalid number of arguments".to_string(),
+                ))
+            }
+        };
+        let allocations = gc::allocations();
+        let start = Instant::now();
+        let values = self.eval_values(form, env)?;
+        let elapsed = start.elapsed();
+        let allocated = gc::allocations() - allocations;
+        output_stream(None, env)?.write_str(&format!(
+            "Evaluation took:\n  {:.6} seconds of real time\n  {} objects allocated\n",
+            elapsed.as_secs_f64(),
+            allocated
+        ))?;
+        Ok(self.values(values))
+    }
+}
@@ -0,0 +1,88 @@
+use super::{parse_lambda_list, Evaluator, ExprEnv};
+use crate::modules::ast::Expr;
+use crate::modules::builtin::{expect_index, list};
+use crate::modules::error::RispError;
+
+impl Evaluator {
+    // Returns multiple values from a builtin: the primary value is returned
+    // and all of them are kept for the forms which receive multiple values.
+    pub fn values(&mut self, values: Vec<Expr>) -> Expr {
+        let primary = values.first().cloned().unwrap_or(Expr::Nil);
+        self.values = Some(values);
+        primary
+    }
+
+    // Evaluates a form for all its values.
+    pub fn eval_values(&mut self, expr: &Expr, env: &mut ExprEnv) -> Result<Vec<Expr>, RispError> {
+        self.values = None;
+        let primary = self.eval_form(expr, env)?;
+        Ok(self.values.take().unwrap_or_else(|| vec![primary]))
+    }
+
+    // Evaluates forms in order, passing on the values of the last one.
+    pub fn eval_body(&mut self, body: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        match body.split_last() {
+            Some((last, forms)) => {
+                for form in forms {
+                    self.eval(form, env)?;
+                }
+                self.eval_form(last, env)
+            }
+            None => Ok(Expr::Nil),
+        }
+    }
+
+    // (multiple-value-bind (vars...) values-form body...) where missing values
+    // are bound to NIL.
+    pub fn eval_multiple_value_bind(
+        &mut self,
+        args: &[Expr],
+        env: &mut ExprEnv,
+    ) -> Result<Expr, RispError> {
+        let (vars, form, body) = match args {
+            [vars, form, body @ ..] => (parse_lambda_list(vars)?, form, body),
+            _ => {
+                return Err(RispError::Expr(
+                    "MULTIPLE-VALUE-BIND: invalid form".to_string(),
+                ))
+            }
+        };
+        let mut values = self.eval_values(form, env)?.into_iter();
+        let bindings = vars
+            .into_iter()
+            .map(|var| (var, values.next().unwrap_or(Expr::Nil)))
+            .collect::<Vec<_>>();
+        self.with_bindings(env, bindings, |evaluator, env| {
+            evaluator.eval_body(body, env)
+        })
+    }
+
+    // (multiple-value-list values-form)
+    pub fn eval_multiple_value_list(
+        &mut self,
+        args: &[Expr],
+        env: &mut ExprEnv,
+    ) -> Result<Expr, RispError> {
+        match args {
+            [form] => Ok(list(self.eval_values(form, env)?)),
+            _ => Err(RispError::Expr(
+                "MULTIPLE-VALUE-LIST: invalid number of arguments".to_string(),
+            )),
+        }
+    }
+
+    // (nth-value n values-form)
+    pub fn eval_nth_value(&mut self, args: &[Expr], env: &mut ExprEnv) -> Result<Expr, RispError> {
+        let (n, form) = match args {
+            [n, form] => (n, form),
+            _ => {
+                return Err(RispError::Expr(
+                    "NTH-VALUE: invalid number of arguments".to_string(),
+                ))
+            }
+        };
+        let n = expect_index(&self.eval(n, env)?)?;
+        let values = self.eval_values(form, env)?;
+        Ok(values.get(n).cloned().unwrap_or(Expr::Nil))
+    }
+}
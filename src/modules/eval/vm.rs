@@ -0,0 +1,159 @@
+use super::{Evaluator, ExprEnv};
+use crate::modules::ast::Expr;
+use crate::modules::builtin::boolean;
+use crate::modules::compiler::{Arith, Chunk, Op};
+use crate::modules::error::RispError;
+use std::cmp::Ordering;
+
+impl Evaluator {
+    // Runs the bytecode of a function called with `args`, passing on all the
+    // values it returns. Functions are looked up in `env`, the environment the
+    // function was created in. Builtins, which only see global bindings, are
+    // given the environment of the caller instead of a copy of `env`.
+    pub(super) fn run(
+        &mut self,
+        chunk: &Chunk,
+        args: &[Expr],
+        env: &ExprEnv,
+        caller: &mut ExprEnv,
+    ) -> Result<Expr, RispError> {
+        // The local slots are at the bottom of the stack.
+        let mut stack = Vec::with_capacity(chunk.slots + 8);
+        stack.extend_from_slice(args);
+        stack.resize(chunk.slots, Expr::Nil);
+        let mut pc = 0;
+        loop {
+            let op = chunk.code[pc];
+            pc += 1;
+            match op {
+                Op::Const(i) => stack.push(chunk.constants[i].clone()),
+                Op::Local(slot) => stack.push(stack[slot].clone()),
+                Op::SetLocal(slot) => stack[slot] = top(&stack).clone(),
+                Op::Cell(i) => stack.push(chunk.cells[i].borrow().clone()),
+                Op::SetCell(i) => *chunk.cells[i].borrow_mut() = top(&stack).clone(),
+                Op::Global(i) => {
+                    let name = &chunk.names[i];
+                    match env.get_global(name) {
+                        Some(value) => stack.push(value),
+                        None => return Err(self.unbound_variable(name, env)),
+                    }
+                }
+                Op::SetGlobal(i) => caller.set_global(&chunk.names[i], top(&stack).clone())?,
+                Op::Function(i) => stack.push(function(chunk, i, env)?),
+                Op::Arith { op, .. } if arith_is_builtin(chunk, env) => {
+                    let b = stack.pop().expect("empty stack");
+                    let a = stack.pop().expect("empty stack");
+                    stack.push(arith(op, &a, &b)?);
+                }
+                Op::Arith { name, .. } => {
+                    self.call(chunk, name, 2, false, &mut stack, env, caller)?
+                }
+                Op::Call {
+                    name,
+                    argc,
+                    all_values,
+                } => self.call(chunk, name, argc, all_values, &mut stack, env, caller)?,
+                Op::Jump(target) => pc = target,
+                Op::JumpIfNil(target) => {
+                    if let Some(Expr::Nil) = stack.pop() {
+                        pc = target;
+                    }
+                }
+                Op::Pop => {
+                    stack.pop();
+                }
+                Op::Return => return Ok(stack.pop().unwrap_or(Expr::Nil)),
+            }
+        }
+    }
+}
+
+impl Evaluator {
+    // Calls the function of the `name`th name of the chunk with the `argc`
+    // arguments on top of the stack, replacing them with its value.
+    #[allow(clippy::too_many_arguments)]
+    fn call(
+        &mut self,
+        chunk: &Chunk,
+        name: usize,
+        argc: usize,
+        all_values: bool,
+        stack: &mut Vec<Expr>,
+        env: &ExprEnv,
+        caller: &mut ExprEnv,
+    ) -> Result<(), RispError> {
+        let func = function(chunk, name, env)?;
+        let base = stack.len() - argc;
+        let result = self.call_function(&func, &stack[base..], caller)?;
+        stack.truncate(base);
+        if !all_values && self.values.is_some() {
+            self.values = None;
+        }
+        stack.push(result);
+        Ok(())
+    }
+}
+
+fn top(stack: &[Expr]) -> &Expr {
+    stack.last().expect("empty stack")
+}
+
+fn arith(op: Arith, a: &Expr, b: &Expr) -> Result<Expr, RispError> {
+    let (a, b) = match (a, b) {
+        (Expr::Number(a), Expr::Number(b)) => (a, b),
+        (Expr::Number(_), x) | (x, _) => {
+            return Err(RispError::Expr(format!("{} is not number", x)))
+        }
+    };
+    Ok(match op {
+        Arith::Add => Expr::Number(a.add(b)),
+        Arith::Sub => Expr::Number(a.sub(b)),
+        Arith::Mul => Expr::Number(a.mul(b)),
+        Arith::Eq => boolean(a.num_cmp(b) == Some(Ordering::Equal)),
+        Arith::Lt => boolean(a.num_cmp(b) == Some(Ordering::Less)),
+        Arith::Gt => boolean(a.num_cmp(b) == Some(Ordering::Greater)),
+        Arith::Le => boolean(a.num_cmp(b).is_some_and(|ord| ord != Ordering::Greater)),
+        Arith::Ge => boolean(a.num_cmp(b).is_some_and(|ord| ord != Ordering::Less)),
+    })
+}
+
+// Whether the names of the open-coded arithmetic of the chunk are all still
+// bound to their builtins, checked again only when a global function has
+// been defined or removed since the last time.
+fn arith_is_builtin(chunk: &Chunk, env: &ExprEnv) -> bool {
+    let generation = env.function_generation();
+    if let Some((checked, builtin)) = chunk.arith_checked.get() {
+        if checked == generation {
+            return builtin;
+        }
+    }
+    let builtin = chunk.code.iter().all(|op| match op {
+        Op::Arith { name, .. } => {
+            let name = &chunk.names[*name];
+            matches!(env.get_function(name), Some(Expr::Func(native)) if native.name == *name)
+        }
+        _ => true,
+    });
+    chunk.arith_checked.set(Some((generation, builtin)));
+    builtin
+}
+
+// The function of the `i`th name of the chunk, looked up again only when a
+// global function has been defined or removed since the last time.
+fn function(chunk: &Chunk, i: usize, env: &ExprEnv) -> Result<Expr, RispError> {
+    let generation = env.function_generation();
+    let mut functions = chunk.functions.borrow_mut();
+    if chunk.generation.get() != generation || functions.is_empty() {
+        *functions = vec![None; chunk.names.len()];
+        chunk.generation.set(generation);
+    }
+    if let Some(func) = &functions[i] {
+        return Ok(func.clone());
+    }
+    let name = &chunk.names[i];
+    let func = env
+        .get_function(name)
+        .ok_or_else(|| RispError::Expr(format!("undefined function: {}", name)))?;
+    functions[i] = Some(func.clone());
+    Ok(func)
+}
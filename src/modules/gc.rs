@@ -0,0 +1,322 @@
+use super::ast::{Expr, Lambda};
+use super::env::Frame;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+// Values are reference counted, which frees them as soon as they are no
+// longer used except when they refer to each other: a hash table holding
+// itself, or a closure stored in a variable it captures. The collector finds
+// such cycles among the mutable objects allocated with `alloc`, whose
+// contents it can clear.
+//
+// The roots are not listed: an object is alive when it is referenced from
+// outside the objects the collector follows, such as the global environment,
+// the evaluator's stack or a Rust frame. Counting the references between the
+// objects and subtracting them from their reference counts leaves the ones
+// held by the roots, and the objects reachable from them are kept.
+
+// An object holding references to other values.
+pub trait Trace {
+    fn trace(&self, visit: &mut dyn FnMut(&Expr));
+    // Drops the references so that the objects of a garbage cycle are freed.
+    fn clear_references(&mut self);
+    // The kind of the object as ROOM reports it.
+    fn kind(&self) -> &'static str;
+}
+
+// The lexical variables captured by closures.
+impl Trace for Expr {
+    fn trace(&self, visit: &mut dyn FnMut(&Expr)) {
+        visit(self)
+    }
+
+    fn clear_references(&mut self) {
+        *self = Expr::Nil;
+    }
+
+    fn kind(&self) -> &'static str {
+        "closure cells"
+    }
+}
+
+type Object = Rc<RefCell<dyn Trace>>;
+
+// Objects tracked before the next collection runs, unless more were alive
+// after the last one.
+const MIN_THRESHOLD: usize = 10_000;
+
+// Allocations between the removals of the objects already freed, whose memory
+// is only released once they are no longer tracked.
+const PRUNE_INTERVAL: usize = 256;
+
+#[derive(Default)]
+struct Heap {
+    objects: Vec<Weak<RefCell<dyn Trace>>>,
+    allocated: usize,
+    threshold: usize,
+    // Collects on every allocation, to find objects the collector frees while
+    // they are still in use.
+    stress: bool,
+}
+
+thread_local! {
+    static HEAP: RefCell<Heap> = RefCell::new(Heap {
+        threshold: MIN_THRESHOLD,
+        ..Heap::default()
+    });
+    // Values allocated since the start, tracked or not, as TIME reports them.
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+// Allocates a mutable object tracked by the collector, collecting first when
+// the tracked objects have doubled since the last time.
+pub fn alloc<T: Trace + 'static>(value: T) -> Rc<RefCell<T>> {
+    let object = Rc::new(RefCell::new(value));
+    count_allocation();
+    let due = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        let weak: Weak<RefCell<dyn Trace>> = Rc::downgrade(&object) as _;
+        heap.objects.push(weak);
+        heap.allocated += 1;
+        if heap.allocated >= PRUNE_INTERVAL {
+            heap.objects.retain(|object| object.strong_count() > 0);
+            heap.allocated = 0;
+        }
+        heap.stress || heap.objects.len() >= heap.threshold
+    });
+    if due {
+        collect();
+    }
+    object
+}
+
+// Counts a value allocated on the heap: a tracked object, or a list, a
+// string or a closure.
+pub fn count_allocation() {
+    ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+}
+
+// The number of values allocated so far.
+pub fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+pub fn set_stress(stress: bool) {
+    HEAP.with(|heap| heap.borrow_mut().stress = stress);
+}
+
+// The number of objects alive and freed by a collection.
+pub struct Collection {
+    pub live: usize,
+    pub freed: usize,
+}
+
+pub fn collect() -> Collection {
+    let objects = HEAP.with(|heap| {
+        let heap = heap.borrow();
+        heap.objects
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>()
+    });
+    let tracked = objects.len();
+    let mut graph = Graph::default();
+    for object in objects {
+        graph.add(Node::Object(object));
+    }
+    graph.expand();
+    let live = graph.mark();
+
+    let mut freed = 0;
+    for (i, node) in graph.nodes.iter().enumerate() {
+        if let Node::Object(object) = node {
+            if !live[i] && i < tracked {
+                freed += 1;
+                if let Ok(mut object) = object.try_borrow_mut() {
+                    object.clear_references();
+                }
+            }
+        }
+    }
+    drop(graph);
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.retain(|object| object.strong_count() > 0);
+        heap.allocated = 0;
+        heap.threshold = MIN_THRESHOLD.max(heap.objects.len() * 2);
+        Collection {
+            live: heap.objects.len(),
+            freed,
+        }
+    })
+}
+
+// The number of tracked objects of each kind which have not been freed yet,
+// garbage included.
+pub fn room() -> Vec<(&'static str, usize)> {
+    let mut counts: Vec<(&'static str, usize)> = vec![];
+    HEAP.with(|heap| {
+        for object in heap.borrow().objects.iter().filter_map(Weak::upgrade) {
+            let kind = match object.try_borrow() {
+                Ok(object) => object.kind(),
+                Err(_) => continue,
+            };
+            match counts.iter_mut().find(|(name, _)| *name == kind) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((kind, 1)),
+            }
+        }
+    });
+    counts
+}
+
+// The values the collector follows: the tracked objects and the immutable
+// lists, closures and lexical frames through which they may refer to each
+// other. Frames are shared by the closures created in them and by the
+// environments the evaluator is running in, so they are followed as nodes of
+// their own: a frame referenced by a running environment keeps the variables
+// bound in it alive.
+enum Node {
+    List(Rc<[Expr]>),
+    Lambda(Rc<Lambda>),
+    Variables(Rc<Frame<Rc<RefCell<Expr>>>>),
+    Functions(Rc<Frame<Expr>>),
+    Object(Object),
+}
+
+impl Node {
+    fn of(x: &Expr) -> Option<Node> {
+        let object: Object = match x {
+            Expr::List(list) => return Some(Node::List(list.clone())),
+            Expr::Lambda(lambda) => return Some(Node::Lambda(lambda.clone())),
+            Expr::HashTable(table) => table.clone(),
+            Expr::Vector(array) | Expr::Array(array) => array.clone(),
+            Expr::Struct(structure) => structure.clone(),
+            Expr::Instance(instance) => instance.clone(),
+            _ => return None,
+        };
+        Some(Node::Object(object))
+    }
+
+    fn address(&self) -> usize {
+        match self {
+            Node::List(list) => Rc::as_ptr(list) as *const u8 as usize,
+            Node::Lambda(lambda) => Rc::as_ptr(lambda) as *const u8 as usize,
+            Node::Variables(frame) => Rc::as_ptr(frame) as *const u8 as usize,
+            Node::Functions(frame) => Rc::as_ptr(frame) as *const u8 as usize,
+            Node::Object(object) => Rc::as_ptr(object) as *const u8 as usize,
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::List(list) => Rc::strong_count(list),
+            Node::Lambda(lambda) => Rc::strong_count(lambda),
+            Node::Variables(frame) => Rc::strong_count(frame),
+            Node::Functions(frame) => Rc::strong_count(frame),
+            Node::Object(object) => Rc::strong_count(object),
+        }
+    }
+
+    // Visits the nodes referenced by this one. An object borrowed by the
+    // evaluator is not looked into, which keeps whatever it refers to alive.
+    fn children(&self) -> Vec<Node> {
+        let mut children = vec![];
+        let mut cells = vec![];
+        let mut globals = None;
+        let mut frames = vec![];
+        let mut visit = |x: &Expr| children.extend(Node::of(x));
+        match self {
+            Node::List(list) => list.iter().for_each(visit),
+            Node::Lambda(lambda) => {
+                globals = Some(lambda.env.globals());
+                frames.extend(lambda.env.variable_frame().cloned().map(Node::Variables));
+                frames.extend(lambda.env.function_frame().cloned().map(Node::Functions));
+                if let Some(code) = &lambda.code {
+                    cells.extend(code.cells.iter().cloned());
+                    code.constants.iter().for_each(&mut visit);
+                    if let Ok(functions) = code.functions.try_borrow() {
+                        functions.iter().flatten().for_each(&mut visit);
+                    }
+                }
+            }
+            Node::Variables(frame) => {
+                cells.push(frame.value().clone());
+                frames.extend(frame.outer().cloned().map(Node::Variables));
+            }
+            Node::Functions(frame) => {
+                visit(frame.value());
+                frames.extend(frame.outer().cloned().map(Node::Functions));
+            }
+            Node::Object(object) => {
+                if let Ok(object) = object.try_borrow() {
+                    object.trace(&mut visit);
+                }
+            }
+        }
+        children.extend(frames);
+        children.extend(globals.map(Node::Object));
+        children.extend(cells.into_iter().map(|cell| Node::Object(cell)));
+        children
+    }
+}
+
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    index: HashMap<usize, usize>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    fn add(&mut self, node: Node) -> usize {
+        let address = node.address();
+        if let Some(i) = self.index.get(&address) {
+            return *i;
+        }
+        self.nodes.push(node);
+        self.edges.push(vec![]);
+        self.index.insert(address, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    // Adds the nodes reachable from the ones added so far.
+    fn expand(&mut self) {
+        let mut i = 0;
+        while i < self.nodes.len() {
+            let children = self.nodes[i].children();
+            let edges = children.into_iter().map(|node| self.add(node)).collect();
+            self.edges[i] = edges;
+            i += 1;
+        }
+    }
+
+    // Tells which nodes are alive: those with references from outside the
+    // graph, besides the one held by the graph itself, and the nodes they
+    // reach.
+    fn mark(&self) -> Vec<bool> {
+        let mut external: Vec<isize> = self
+            .nodes
+            .iter()
+            .map(|node| node.strong_count() as isize - 1)
+            .collect();
+        for edges in &self.edges {
+            for to in edges {
+                external[*to] -= 1;
+            }
+        }
+        let mut live = vec![false; self.nodes.len()];
+        let mut stack = (0..self.nodes.len())
+            .filter(|i| external[*i] > 0)
+            .collect::<Vec<_>>();
+        while let Some(i) = stack.pop() {
+            if !live[i] {
+                live[i] = true;
+                stack.extend(&self.edges[i]);
+            }
+        }
+        live
+    }
+}
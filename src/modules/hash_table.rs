@@ -0,0 +1,306 @@
+use super::ast::Expr;
+use super::equality;
+use super::error::RispError;
+use super::gc::Trace;
+use super::number::Number;
+use num_rational::BigRational;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum HashTest {
+    Eq,
+    Eql,
+    Equal,
+    Equalp,
+}
+
+impl HashTest {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "EQ" => Some(HashTest::Eq),
+            "EQL" => Some(HashTest::Eql),
+            "EQUAL" => Some(HashTest::Equal),
+            "EQUALP" => Some(HashTest::Equalp),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashTest::Eq => "EQ",
+            HashTest::Eql => "EQL",
+            HashTest::Equal => "EQUAL",
+            HashTest::Equalp => "EQUALP",
+        }
+    }
+}
+
+// Hashable form of an expression under a given test, so that two keys are
+// equal exactly when the test considers the expressions equal.
+#[derive(PartialEq, Eq, Hash)]
+enum HashKey {
+    // Compared with EQUAL, the Rust-side equality of `Expr`.
+    Value(Expr),
+    // Strings and lists compared by identity under EQ and EQL.
+    Identity(usize),
+    // Folded elements of a list under EQUALP.
+    List(Vec<HashKey>),
+    // Dimensions and folded elements of an array under EQUALP.
+    Array(Vec<usize>, Vec<HashKey>),
+    // Type and folded slot values of a structure under EQUALP.
+    Struct(usize, Vec<HashKey>),
+    // A hash table under EQUALP, compared by its contents.
+    Table(TableKey),
+}
+
+impl HashKey {
+    fn trace(&self, visit: &mut dyn FnMut(&Expr)) {
+        match self {
+            HashKey::Value(x) => visit(x),
+            HashKey::Identity(_) => {}
+            HashKey::List(keys) | HashKey::Array(_, keys) | HashKey::Struct(_, keys) => {
+                keys.iter().for_each(|key| key.trace(visit))
+            }
+            HashKey::Table(TableKey(table)) => visit(&Expr::HashTable(table.clone())),
+        }
+    }
+
+    fn new(expr: &Expr, test: HashTest) -> Self {
+        match (test, expr) {
+            (HashTest::Eq | HashTest::Eql, Expr::String(s)) => {
+                HashKey::Identity(Rc::as_ptr(s) as *const u8 as usize)
+            }
+            (HashTest::Eq | HashTest::Eql, Expr::List(exprs)) => {
+                HashKey::Identity(Rc::as_ptr(exprs) as *const Expr as usize)
+            }
+            (HashTest::Equalp, _) => fold(expr),
+            _ => HashKey::Value(expr.clone()),
+        }
+    }
+}
+
+// Canonical form under EQUALP, applied all the way down: characters and
+// strings are downcased, numbers are replaced by their exact value since
+// EQUALP compares them with `=`, and arrays, structures and hash tables are
+// keyed by their contents.
+fn fold(expr: &Expr) -> HashKey {
+    match expr {
+        Expr::Number(Number::Float(f)) => match BigRational::from_float(*f) {
+            Some(ratio) => HashKey::Value(Expr::Number(Number::from_ratio(ratio))),
+            None => HashKey::Value(expr.clone()),
+        },
+        Expr::Char(ch) => HashKey::Value(Expr::Char(ch.to_lowercase().next().unwrap_or(*ch))),
+        Expr::String(s) => HashKey::Value(Expr::String(s.to_lowercase().into())),
+        Expr::List(exprs) => HashKey::List(exprs.iter().map(fold).collect()),
+        Expr::Vector(array) | Expr::Array(array) => {
+            let array = array.borrow();
+            let dimensions = match array.rank() {
+                1 => vec![array.len()],
+                _ => array.dimensions.clone(),
+            };
+            HashKey::Array(dimensions, array.elements().iter().map(fold).collect())
+        }
+        Expr::Struct(structure) => {
+            let structure = structure.borrow();
+            HashKey::Struct(
+                Rc::as_ptr(&structure.ty) as usize,
+                structure.values.iter().map(fold).collect(),
+            )
+        }
+        Expr::HashTable(table) => HashKey::Table(TableKey(table.clone())),
+        _ => HashKey::Value(expr.clone()),
+    }
+}
+
+// Tables compared with EQUALP. Their entries are unordered, so only the test
+// and the count go into the hash; equality compares the contents.
+struct TableKey(Rc<RefCell<HashTable>>);
+
+impl PartialEq for TableKey {
+    fn eq(&self, other: &Self) -> bool {
+        equality::equalp(
+            &Expr::HashTable(self.0.clone()),
+            &Expr::HashTable(other.0.clone()),
+        )
+    }
+}
+
+impl Eq for TableKey {}
+
+impl Hash for TableKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let table = self.0.borrow();
+        table.test.name().hash(state);
+        table.len().hash(state);
+    }
+}
+
+// Entries are kept in insertion order so that MAPHASH and printing are
+// deterministic. A removed entry leaves a hole, which is compacted away once
+// holes make up half of the entries.
+pub struct HashTable {
+    pub test: HashTest,
+    entries: Vec<Option<(Expr, Expr)>>,
+    index: HashMap<HashKey, usize>,
+}
+
+impl HashTable {
+    pub fn new(test: HashTest) -> Self {
+        Self {
+            test,
+            entries: vec![],
+            index: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, key: &Expr) -> Option<&Expr> {
+        self.index
+            .get(&HashKey::new(key, self.test))
+            .and_then(|i| self.entries[*i].as_ref())
+            .map(|(_, value)| value)
+    }
+
+    pub fn insert(&mut self, key: Expr, value: Expr) {
+        match self.index.get(&HashKey::new(&key, self.test)) {
+            Some(i) => {
+                if let Some(entry) = &mut self.entries[*i] {
+                    entry.1 = value;
+                }
+            }
+            None => {
+                self.index
+                    .insert(HashKey::new(&key, self.test), self.entries.len());
+                self.entries.push(Some((key, value)));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &Expr) -> bool {
+        let i = match self.index.remove(&HashKey::new(key, self.test)) {
+            Some(i) => i,
+            None => return false,
+        };
+        self.entries[i] = None;
+        if self.index.len() * 2 < self.entries.len() {
+            self.compact();
+        }
+        true
+    }
+
+    // Drops the holes left by removed entries, keeping the order of the
+    // others.
+    fn compact(&mut self) {
+        self.entries.retain(Option::is_some);
+        for (i, (key, _)) in self.entries.iter().flatten().enumerate() {
+            self.index.insert(HashKey::new(key, self.test), i);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn entries(&self) -> Vec<(Expr, Expr)> {
+        self.entries.iter().flatten().cloned().collect()
+    }
+}
+
+// Keys are also referenced from the index.
+impl Trace for HashTable {
+    fn trace(&self, visit: &mut dyn FnMut(&Expr)) {
+        for (key, value) in self.entries.iter().flatten() {
+            visit(key);
+            visit(value);
+        }
+        for key in self.index.keys() {
+            key.trace(visit);
+        }
+    }
+
+    fn clear_references(&mut self) {
+        self.clear();
+    }
+
+    fn kind(&self) -> &'static str {
+        "hash tables"
+    }
+}
+
+pub fn expect_hash_table(x: &Expr) -> Result<Rc<RefCell<HashTable>>, RispError> {
+    match x {
+        Expr::HashTable(table) => Ok(table.clone()),
+        _ => Err(RispError::Expr(format!("{} is not hash table", x))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Expr {
+        Expr::String(s.into())
+    }
+
+    #[test]
+    fn hash_table_tests() {
+        let tests = vec![
+            (HashTest::Eql, string("key"), string("key"), false),
+            (HashTest::Equal, string("key"), string("key"), true),
+            (HashTest::Equalp, string("KEY"), string("key"), true),
+            (
+                HashTest::Equalp,
+                Expr::Number(Number::Fixnum(1)),
+                Expr::Number(Number::Float(1.0)),
+                true,
+            ),
+            (
+                HashTest::Equal,
+                Expr::Number(Number::Fixnum(1)),
+                Expr::Number(Number::Float(1.0)),
+                false,
+            ),
+        ];
+        for (test, key, lookup, found) in tests {
+            let mut table = HashTable::new(test);
+            table.insert(key, Expr::True);
+            assert_eq!(table.get(&lookup).is_some(), found, "{}", test.name());
+        }
+    }
+
+    #[test]
+    fn hash_table_remove() {
+        let mut table = HashTable::new(HashTest::Equal);
+        for key in ["a", "b", "c"] {
+            table.insert(string(key), string(key));
+        }
+        assert!(table.remove(&string("a")));
+        assert!(!table.remove(&string("a")));
+        assert_eq!(table.len(), 2);
+        assert!(table.get(&string("c")) == Some(&string("c")));
+
+        // Removing compacts the holes without reordering the entries.
+        for i in 0..100 {
+            table.insert(Expr::Number(Number::Fixnum(i)), Expr::Nil);
+        }
+        for i in 0..99 {
+            assert!(table.remove(&Expr::Number(Number::Fixnum(i))));
+        }
+        assert!(table.remove(&string("b")));
+        assert!(table.entries.len() < 10);
+        let keys = table.entries().into_iter().map(|(key, _)| key.to_string());
+        assert_eq!(keys.collect::<Vec<_>>(), ["c", "99"]);
+        assert!(table.get(&Expr::Number(Number::Fixnum(99))).is_some());
+    }
+}
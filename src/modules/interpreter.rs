@@ -0,0 +1,331 @@
+use super::ast::Expr;
+use super::builtin::define_native;
+use super::convert::HostFn;
+use super::error::RispError;
+use super::eval::{default_env, Engine, Evaluator, ExprEnv};
+use super::lexer::Lexer;
+use super::parser::Parser;
+use std::path::Path;
+
+/// An interpreter with its own global environment, for embedding risp in a
+/// Rust program.
+///
+/// ```
+/// use risp::{Expr, Interpreter};
+///
+/// let mut interp = Interpreter::new();
+/// interp.eval_str("(defun square (x) (* x x))").unwrap();
+/// assert_eq!(interp.eval_str("(square 12)").unwrap().to_string(), "144");
+/// ```
+pub struct Interpreter {
+    evaluator: Evaluator,
+    env: ExprEnv,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    /// Creates an interpreter with the builtins defined.
+    pub fn new() -> Self {
+        Self {
+            evaluator: Evaluator::new(),
+            env: default_env(),
+        }
+    }
+
+    /// Selects how functions are run. The bytecode engine compiles them as
+    /// they are defined.
+    pub fn with_engine(self, engine: Engine) -> Self {
+        Self {
+            evaluator: self.evaluator.with_engine(engine),
+            ..self
+        }
+    }
+
+    /// Limits the number of nested function calls, 10000 by default. A call
+    /// beyond it signals an error rather than exhausting memory.
+    ///
+    /// ```
+    /// let mut interp = risp::Interpreter::new().with_max_depth(100);
+    /// interp.eval_str("(defun deep (n) (if (= n 0) 0 (+ 1 (deep (- n 1)))))").unwrap();
+    /// assert_eq!(interp.eval_str("(deep 99)").unwrap().to_string(), "99");
+    /// assert_eq!(
+    ///     interp.eval_str("(deep 100)").err().unwrap().to_string(),
+    ///     "call depth limit exceeded: more than 100 nested calls"
+    /// );
+    /// // The interpreter can be used again after the error.
+    /// assert_eq!(interp.eval_str("(deep 10)").unwrap().to_string(), "10");
+    /// ```
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Self {
+            evaluator: self.evaluator.with_max_depth(max_depth),
+            ..self
+        }
+    }
+
+    /// Evaluates the forms of `source` in order and returns the primary
+    /// value of the last one, or NIL when there are none.
+    ///
+    /// ```
+    /// let mut interp = risp::Interpreter::new();
+    /// let value = interp.eval_str("(defparameter *n* 2) (+ *n* 3)").unwrap();
+    /// assert_eq!(value.to_string(), "5");
+    /// assert!(interp.eval_str("(undefined-function)").is_err());
+    /// ```
+    pub fn eval_str(&mut self, source: &str) -> Result<Expr, RispError> {
+        let values = self.eval_str_values(source)?;
+        Ok(values.into_iter().next().unwrap_or(Expr::Nil))
+    }
+
+    /// Evaluates the forms of `source` in order and returns all the values
+    /// of the last one.
+    ///
+    /// ```
+    /// let mut interp = risp::Interpreter::new();
+    /// let values = interp.eval_str_values("(floor 7 2)").unwrap();
+    /// assert_eq!(values.len(), 2);
+    /// ```
+    pub fn eval_str_values(&mut self, source: &str) -> Result<Vec<Expr>, RispError> {
+        let mut values = vec![Expr::Nil];
+        if source.trim().is_empty() {
+            return Ok(values);
+        }
+        let lexer = Lexer::new(source.to_string())?;
+        let mut parser = Parser::new(lexer).with_env(&self.env);
+        while let Some(form) = parser.read_next(&mut self.evaluator)? {
+            values = self.evaluator.eval_values(&form, &mut self.env)?;
+        }
+        Ok(values)
+    }
+
+    /// Evaluates the forms of a file as LOAD does: files it loads are found
+    /// next to it, and the current package is restored afterwards.
+    pub fn eval_file(&mut self, path: impl AsRef<Path>) -> Result<(), RispError> {
+        self.evaluator.load(path.as_ref(), &self.env)
+    }
+
+    /// Defines a global function taking between `min_args` and `max_args`
+    /// arguments, which are checked before it is called.
+    ///
+    /// ```
+    /// use risp::{Expr, Interpreter, RispError};
+    ///
+    /// let mut interp = Interpreter::new();
+    /// interp.define_fn("greet", 1, 1, |args| match &args[0] {
+    ///     Expr::String(name) => Ok(Expr::String(format!("Hello, {}!", name).into())),
+    ///     x => Err(RispError::Expr(format!("{} is not string", x))),
+    /// });
+    /// let greeting = interp.eval_str("(greet \"risp\")").unwrap();
+    /// assert_eq!(greeting.to_string(), "Hello, risp!");
+    /// assert!(interp.eval_str("(greet)").is_err());
+    /// ```
+    pub fn define_fn(
+        &mut self,
+        name: &str,
+        min_args: usize,
+        max_args: usize,
+        func: impl Fn(&[Expr]) -> Result<Expr, RispError> + 'static,
+    ) {
+        let name = self.symbol(name);
+        define_native(
+            &mut self.env,
+            &name,
+            min_args,
+            max_args,
+            move |_, args, _| func(args),
+        );
+    }
+
+    /// Defines a global function calling a Rust closure. The number of
+    /// arguments is checked, and they are converted to the types the closure
+    /// takes, which signals a type error for values of other types. The
+    /// closure may return a `Result` to signal errors of its own.
+    ///
+    /// ```
+    /// use risp::{Interpreter, RispError};
+    ///
+    /// let mut interp = Interpreter::new();
+    /// interp.register("add", |a: f64, b: f64| a + b);
+    /// interp.register("words", |s: String| {
+    ///     s.split_whitespace().map(str::to_string).collect::<Vec<_>>()
+    /// });
+    /// interp.register("checked-div", |a: i64, b: i64| {
+    ///     a.checked_div(b)
+    ///         .ok_or_else(|| RispError::Expr("division by zero".to_string()))
+    /// });
+    /// assert_eq!(interp.eval_str("(add 1 2.5)").unwrap().to_string(), "3.5");
+    /// assert_eq!(
+    ///     interp.eval_str("(words \"a b c\")").unwrap().to_string(),
+    ///     "(a b c)"
+    /// );
+    /// assert!(interp.eval_str("(add 1)").is_err());
+    /// assert_eq!(
+    ///     interp.eval_str("(add 1 \"2\")").err().unwrap().to_string(),
+    ///     "The value \"2\" is not of type REAL"
+    /// );
+    /// assert!(interp.eval_str("(checked-div 1 0)").is_err());
+    /// ```
+    pub fn register<Args, F: HostFn<Args>>(&mut self, name: &str, func: F) {
+        let name = self.symbol(name);
+        define_native(
+            &mut self.env,
+            &name,
+            F::ARITY,
+            F::ARITY,
+            move |_, args, _| func.call(args),
+        );
+    }
+
+    /// The global value of a variable, named as it would be written in risp.
+    ///
+    /// ```
+    /// let mut interp = risp::Interpreter::new();
+    /// interp.eval_str("(defvar *limit* 10)").unwrap();
+    /// assert_eq!(interp.get_global("*limit*").unwrap().to_string(), "10");
+    /// assert!(interp.get_global("*unbound*").is_none());
+    /// ```
+    pub fn get_global(&self, name: &str) -> Option<Expr> {
+        self.env.get_global(&self.symbol(name))
+    }
+
+    /// Sets the global value of a variable, failing when it is a constant.
+    ///
+    /// ```
+    /// use risp::{Expr, Interpreter};
+    ///
+    /// let mut interp = Interpreter::new();
+    /// interp.set_global("*name*", Expr::String("risp".into())).unwrap();
+    /// let value = interp.eval_str("(string-upcase *name*)").unwrap();
+    /// assert_eq!(value.to_string(), "RISP");
+    /// interp.eval_str("(defconstant +answer+ 42)").unwrap();
+    /// assert!(interp.set_global("+answer+", Expr::Nil).is_err());
+    /// ```
+    pub fn set_global(&mut self, name: &str, value: Expr) -> Result<(), RispError> {
+        let name = self.symbol(name);
+        self.env.set_global(&name, value)
+    }
+
+    /// Checks a form without running it, returning the problems found.
+    /// Definitions are evaluated so that the calls after them can be checked.
+    pub fn lint_str(&mut self, source: &str) -> Vec<String> {
+        let form = Lexer::new(source.to_string()).and_then(|lexer| {
+            Parser::new(lexer)
+                .with_env(&self.env)
+                .read(&mut self.evaluator)
+        });
+        match form {
+            Ok(form) if is_definition(&form) => match self.evaluator.eval(&form, &mut self.env) {
+                Ok(_) => self.take_warnings(),
+                Err(err) => vec![err.to_string()],
+            },
+            Ok(form) => self.evaluator.lint(&form, &self.env),
+            Err(err) => vec![err.to_string()],
+        }
+    }
+
+    /// The warnings given since the last call, such as for calls which do
+    /// not match a declared function type.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        self.evaluator.warnings.drain(..).collect()
+    }
+
+    /// Adds a directory searched by REQUIRE, after the ones added before and
+    /// before the ones listed in the RISP_PATH environment variable.
+    ///
+    /// ```
+    /// let mut interp = risp::Interpreter::new();
+    /// interp.add_load_path(std::env::temp_dir());
+    /// assert!(interp.eval_str("(require :no-such-module)").is_err());
+    /// ```
+    pub fn add_load_path(&mut self, dir: impl AsRef<Path>) {
+        self.evaluator.add_load_path(dir.as_ref());
+    }
+
+    /// Evaluates the strings given until `end_file` as the forms of a file:
+    /// files they load are found next to it, and loading it again fails as a
+    /// circular load.
+    pub fn begin_file(&mut self, path: impl AsRef<Path>) -> Result<(), RispError> {
+        self.evaluator.begin_load(path.as_ref())
+    }
+
+    pub fn end_file(&mut self) {
+        self.evaluator.end_load();
+    }
+
+    // The symbol a name is read as in the current package.
+    fn symbol(&self, name: &str) -> String {
+        let name = name.to_uppercase();
+        self.env
+            .packages()
+            .borrow_mut()
+            .intern(&name)
+            .unwrap_or(name)
+    }
+}
+
+fn is_definition(expr: &Expr) -> bool {
+    const DEFINITIONS: [&str; 8] = [
+        "DEFUN",
+        "DECLAIM",
+        "DEFSTRUCT",
+        "DEFCLASS",
+        "DEFGENERIC",
+        "DEFMETHOD",
+        "DEFPACKAGE",
+        "IN-PACKAGE",
+    ];
+    match expr {
+        Expr::List(list) => matches!(
+            list.first(),
+            Some(Expr::Symbol(head)) if DEFINITIONS.contains(&&**head)
+        ),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::builtin::index_expr;
+    use crate::modules::printer::prin1_to_string;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn native_functions() {
+        let mut interp = Interpreter::new();
+        let counter = Rc::new(Cell::new(0));
+        let count = counter.clone();
+        define_native(&mut interp.env, "COUNTER", 0, 0, move |_, _, _| {
+            count.set(count.get() + 1);
+            Ok(index_expr(count.get()))
+        });
+        define_native(
+            &mut interp.env,
+            "CALL-TWICE",
+            1,
+            1,
+            |evaluator, args, env| {
+                evaluator.funcall(&args[0], &[], env)?;
+                evaluator.funcall(&args[0], &[], env)
+            },
+        );
+        let cases = [
+            ("(counter)", "1"),
+            ("(call-twice #'counter)", "3"),
+            ("(call-twice (lambda () (counter)))", "5"),
+            ("#'counter", "#<SYSTEM-FUNCTION COUNTER>"),
+        ];
+        for (input, want) in cases {
+            let got = prin1_to_string(&interp.eval_str(input).unwrap());
+            assert_eq!(got, want, "input: {}", input);
+        }
+        assert_eq!(counter.get(), 5);
+        let err = interp.eval_str("(counter 1)").err().unwrap();
+        assert_eq!(err.to_string(), "COUNTER: invalid number of arguments: 1");
+    }
+}
@@ -1,4 +1,5 @@
 use super::error::RispError;
+use super::number::Number;
 use super::token::Token;
 
 #[derive(Debug)]
@@ -14,7 +15,7 @@ pub struct Lexer {
 
 impl Lexer {
     pub fn new(input: String) -> Result<Self, RispError> {
-        let length = input.len() - 1;
+        let length = input.chars().count().saturating_sub(1);
         let mut lexer = Self {
             ch: '\0',
             input,
@@ -29,26 +30,79 @@ impl Lexer {
     }
 
     pub fn next_token(&mut self) -> Result<Token, RispError> {
-        // Skip if char is whitespace
-        while self.ch.is_whitespace() {
-            self.read()?;
+        // Skip whitespace and comments, which run from a semicolon to the end
+        // of the line.
+        loop {
+            while self.ch.is_whitespace() {
+                self.read()?;
+            }
+            if self.ch != ';' {
+                break;
+            }
+            while self.ch != '\n' && self.ch != '\0' {
+                self.read()?;
+            }
         }
         let token = match self.ch {
             '(' => Token::LPAREN,
             ')' => Token::RPAREN,
-            '*' => Token::ASTERISK,
-            '/' => Token::SLASH,
+            '*' => match self.peek()? {
+                ch if is_symbol_char(ch) => self.read_as_literal()?,
+                _ => Token::ASTERISK,
+            },
+            '/' => match self.peek()? {
+                '=' => self.read_as_literal()?,
+                _ => Token::SLASH,
+            },
             '+' => match self.peek().unwrap() {
                 '0'..='9' => self.read_as_number()?,
+                ch if is_symbol_char(ch) => self.read_as_literal()?,
                 _ => Token::PLUS,
             },
             '-' => match self.peek().unwrap() {
                 '0'..='9' => self.read_as_number()?,
+                ch if is_symbol_char(ch) => self.read_as_literal()?,
                 _ => Token::MINUS,
             },
             '0'..='9' => self.read_as_number()?,
-            '"' => self.read_as_string().unwrap(),
-            'a'..='z' | 'A'..='Z' => self.read_as_literal().unwrap(),
+            '"' => self.read_as_string()?,
+            '\'' => Token::QUOTE,
+            '#' => match self.peek()? {
+                '\\' => self.read_as_char()?,
+                '(' => {
+                    self.read()?;
+                    Token::VECTOR
+                }
+                ':' => {
+                    self.read()?;
+                    match self.peek()? {
+                        ch if is_symbol_char(ch) => {
+                            self.read()?;
+                            match self.read_as_literal()? {
+                                Token::LITERAL(name) => Token::UNINTERNED(name),
+                                token => Token::UNINTERNED(token.to_string()),
+                            }
+                        }
+                        _ => Token::ILLEGAL("#:".to_string()),
+                    }
+                }
+                'S' | 's' => {
+                    self.read()?;
+                    match self.peek()? {
+                        '(' => {
+                            self.read()?;
+                            Token::STRUCT
+                        }
+                        _ => Token::ILLEGAL(format!("#{}", self.ch)),
+                    }
+                }
+                '\'' => {
+                    self.read()?;
+                    Token::FUNCTION
+                }
+                _ => Token::ILLEGAL(self.ch.to_string()),
+            },
+            'a'..='z' | 'A'..='Z' | ':' | '<' | '>' | '=' => self.read_as_literal()?,
             '\0' => Token::EOF,
             _ => Token::ILLEGAL(self.ch.to_string()),
         };
@@ -57,49 +111,86 @@ impl Lexer {
     }
 
     fn read_as_literal(&mut self) -> Result<Token, RispError> {
-        if self.ch == 't' && self.peek()? == '\0' {
-            return Ok(Token::TRUE);
-        }
         let mut s = String::from("");
         loop {
             s.push(self.ch);
             match self.peek()? {
-                'a'..='z' | 'A'..='Z' => self.read()?,
+                ch if is_symbol_char(ch) => self.read()?,
                 _ => break,
             }
         }
-        if s.to_uppercase() == "NIL" {
-            return Ok(Token::NIL);
+        match s.to_uppercase().as_str() {
+            "NIL" => Ok(Token::NIL),
+            "T" => Ok(Token::TRUE),
+            _ => Ok(Token::LITERAL(s.to_uppercase())),
         }
-        Ok(Token::LITERAL(s.to_uppercase()))
+    }
+
+    // Reads a character literal such as `#\a`, `#\Space` or `#\Newline`.
+    fn read_as_char(&mut self) -> Result<Token, RispError> {
+        self.read()?;
+        self.read()?;
+        let mut name = String::from(self.ch);
+        while is_symbol_char(self.peek()?) && self.ch.is_alphanumeric() {
+            self.read()?;
+            name.push(self.ch);
+        }
+        if name.chars().count() == 1 {
+            return Ok(Token::CHAR(self.ch));
+        }
+        let ch = match name.to_uppercase().as_str() {
+            "SPACE" => ' ',
+            "NEWLINE" | "LINEFEED" => '\n',
+            "TAB" => '\t',
+            "RETURN" => '\r',
+            "NUL" | "NULL" => '\0',
+            _ => return Ok(Token::ILLEGAL(format!("#\\{}", name))),
+        };
+        Ok(Token::CHAR(ch))
     }
 
     fn read_as_string(&mut self) -> Result<Token, RispError> {
         let mut s = String::new();
         loop {
             self.read()?;
-            s.push(self.ch);
-            if self.peek()? == '"' {
-                self.read()?;
-                break;
+            match self.ch {
+                '"' => break,
+                '\\' => {
+                    self.read()?;
+                    s.push(self.ch);
+                }
+                '\0' => return Err(RispError::Read("Unterminated string".to_string())),
+                ch => s.push(ch),
             }
         }
         Ok(Token::STRING(s))
     }
 
+    // Reads an integer, a ratio such as `1/3` or a float such as `1.5`,
+    // `1.5e10` or `2d-3`, whose exponent marker is any of E, D, F, S and L.
     fn read_as_number(&mut self) -> Result<Token, RispError> {
         let mut chars = Vec::<char>::new();
         loop {
             chars.push(self.ch);
             match self.peek()? {
-                '0'..='9' | '.' => {
+                '0'..='9' | '.' | '/' => {
+                    self.read()?;
+                }
+                ch if is_exponent_marker(ch)
+                    && !chars.contains(&'/')
+                    && self.exponent_follows() =>
+                {
                     self.read()?;
+                    if let '+' | '-' = self.peek()? {
+                        chars.push(self.ch);
+                        self.read()?;
+                    }
                 }
                 _ => break,
             }
         }
         let s = chars.iter().collect::<String>();
-        let parsed = s.parse::<f64>()?;
+        let parsed = Number::parse(&s)?;
         Ok(Token::NUMBER(parsed))
     }
 
@@ -122,6 +213,17 @@ impl Lexer {
         Ok(())
     }
 
+    // Whether the character after the next one starts the digits of an
+    // exponent, possibly signed.
+    fn exponent_follows(&self) -> bool {
+        let mut rest = self.input.chars().skip(self.read_position + 1);
+        match rest.next() {
+            Some('+' | '-') => matches!(rest.next(), Some('0'..='9')),
+            Some('0'..='9') => true,
+            _ => false,
+        }
+    }
+
     fn peek(&mut self) -> Result<char, RispError> {
         if self.read_position > self.length {
             Ok('\0')
@@ -137,6 +239,14 @@ impl Lexer {
     }
 }
 
+fn is_exponent_marker(ch: char) -> bool {
+    "eEdDfFsSlL".contains(ch)
+}
+
+fn is_symbol_char(ch: char) -> bool {
+    ch.is_alphanumeric() || "-+*/<>=!?%&_$^~:".contains(ch)
+}
+
 #[cfg(test)]
 mod tests {
     use super::Token;
@@ -193,6 +303,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn read_escaped_string() {
+        let tests = vec![
+            (r#""""#, ""),
+            (r#""say \"hi\"""#, r#"say "hi""#),
+            (r#""back\\slash""#, r"back\slash"),
+            (r#""héllo wörld""#, "héllo wörld"),
+        ];
+        for (input, want) in tests {
+            let mut lexer = Lexer::new(String::from(input)).unwrap();
+            assert_eq!(lexer.next_token().unwrap(), Token::STRING(want.into()));
+        }
+        let mut lexer = Lexer::new(String::from(r#""open"#)).unwrap();
+        assert!(lexer.next_token().is_err());
+    }
+
+    #[test]
+    fn skip_comments() {
+        let mut lexer = Lexer::new(String::from("; comment\n(a ; b\n c);")).unwrap();
+        let wants = vec![
+            Token::LPAREN,
+            Token::LITERAL(String::from("A")),
+            Token::LITERAL(String::from("C")),
+            Token::RPAREN,
+            Token::EOF,
+        ];
+        for want in wants {
+            assert_eq!(lexer.next_token().unwrap(), want);
+        }
+    }
+
+    #[test]
+    fn read_quote_and_keyword() {
+        let mut lexer = Lexer::new(String::from("'(a :key)")).unwrap();
+        let wants = vec![
+            Token::QUOTE,
+            Token::LPAREN,
+            Token::LITERAL(String::from("A")),
+            Token::LITERAL(String::from(":KEY")),
+            Token::RPAREN,
+            Token::EOF,
+        ];
+        for want in wants {
+            assert_eq!(lexer.next_token().unwrap(), want);
+        }
+    }
+
     #[test]
     fn read_literal() {
         let mut lexer = Lexer::new(String::from("(setq a 2)")).unwrap();
@@ -205,7 +362,10 @@ mod tests {
             lexer.next_token().unwrap(),
             Token::LITERAL(String::from("A"))
         );
-        assert_eq!(lexer.next_token().unwrap(), Token::NUMBER(2.0));
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            Token::NUMBER(Number::Fixnum(2))
+        );
         assert_eq!(lexer.next_token().unwrap(), Token::RPAREN);
     }
 
@@ -218,7 +378,10 @@ mod tests {
             lexer.next_token().unwrap(),
             Token::LITERAL(String::from("A"))
         );
-        assert_eq!(lexer.next_token().unwrap(), Token::NUMBER(2.0));
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            Token::NUMBER(Number::Fixnum(2))
+        );
         assert_eq!(
             lexer.next_token().unwrap(),
             Token::LITERAL(String::from("A"))
@@ -226,12 +389,93 @@ mod tests {
         assert_eq!(lexer.next_token().unwrap(), Token::RPAREN);
     }
 
+    #[test]
+    fn read_comparison() {
+        let mut lexer = Lexer::new(String::from("(<= a /= b)")).unwrap();
+        let wants = vec![
+            Token::LPAREN,
+            Token::LITERAL(String::from("<=")),
+            Token::LITERAL(String::from("A")),
+            Token::LITERAL(String::from("/=")),
+            Token::LITERAL(String::from("B")),
+            Token::RPAREN,
+            Token::EOF,
+        ];
+        for want in wants {
+            assert_eq!(lexer.next_token().unwrap(), want);
+        }
+    }
+
+    #[test]
+    fn read_earmuffs() {
+        let mut lexer = Lexer::new(String::from("(- *x* +c+ -1)")).unwrap();
+        let wants = vec![
+            Token::LPAREN,
+            Token::MINUS,
+            Token::LITERAL(String::from("*X*")),
+            Token::LITERAL(String::from("+C+")),
+            Token::NUMBER(Number::Fixnum(-1)),
+            Token::RPAREN,
+            Token::EOF,
+        ];
+        for want in wants {
+            assert_eq!(lexer.next_token().unwrap(), want);
+        }
+    }
+
+    #[test]
+    fn read_vector() {
+        let mut lexer = Lexer::new(String::from(r"#(1 #\a)")).unwrap();
+        let wants = vec![
+            Token::VECTOR,
+            Token::NUMBER(Number::Fixnum(1)),
+            Token::CHAR('a'),
+            Token::RPAREN,
+            Token::EOF,
+        ];
+        for want in wants {
+            assert_eq!(lexer.next_token().unwrap(), want);
+        }
+    }
+
+    #[test]
+    fn read_char() {
+        let mut lexer = Lexer::new(String::from(r"(char= #\a #\Space #\Newline #\()")).unwrap();
+        let wants = vec![
+            Token::LPAREN,
+            Token::LITERAL(String::from("CHAR=")),
+            Token::CHAR('a'),
+            Token::CHAR(' '),
+            Token::CHAR('\n'),
+            Token::CHAR('('),
+            Token::RPAREN,
+            Token::EOF,
+        ];
+        for want in wants {
+            assert_eq!(lexer.next_token().unwrap(), want);
+        }
+        let mut lexer = Lexer::new(String::from(r"#\Unknown")).unwrap();
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            Token::ILLEGAL(String::from(r"#\Unknown"))
+        );
+    }
+
     #[test]
     fn read_number() {
         let tests = vec![
-            ("1", Token::NUMBER(1.0)),
-            ("1.5", Token::NUMBER(1.5)),
-            ("2.345", Token::NUMBER(2.345)),
+            ("1", Token::NUMBER(Number::Fixnum(1))),
+            ("1.5", Token::NUMBER(Number::Float(1.5))),
+            ("2.345", Token::NUMBER(Number::Float(2.345))),
+            ("1.5e10", Token::NUMBER(Number::Float(1.5e10))),
+            ("1.0d0", Token::NUMBER(Number::Float(1.0))),
+            ("-2.5E-3", Token::NUMBER(Number::Float(-2.5e-3))),
+            ("1e3", Token::NUMBER(Number::Float(1000.0))),
+            ("-1/3", Token::NUMBER(Number::parse("-1/3").unwrap())),
+            (
+                "123456789012345678901234567890",
+                Token::NUMBER(Number::parse("123456789012345678901234567890").unwrap()),
+            ),
         ];
         for test in tests {
             let mut lexer = Lexer::new(test.0.to_string()).unwrap();
@@ -244,8 +488,14 @@ mod tests {
         let mut lexer = Lexer::new(String::from("(+ 1 2)")).unwrap();
         assert_eq!(lexer.next_token().unwrap(), Token::LPAREN);
         assert_eq!(lexer.next_token().unwrap(), Token::PLUS);
-        assert_eq!(lexer.next_token().unwrap(), Token::NUMBER(1.0));
-        assert_eq!(lexer.next_token().unwrap(), Token::NUMBER(2.0));
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            Token::NUMBER(Number::Fixnum(1))
+        );
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            Token::NUMBER(Number::Fixnum(2))
+        );
         assert_eq!(lexer.next_token().unwrap(), Token::RPAREN);
         assert_eq!(lexer.next_token().unwrap(), Token::EOF);
     }
@@ -258,17 +508,17 @@ mod tests {
             Token::PLUS,
             Token::LPAREN,
             Token::MINUS,
-            Token::NUMBER(30.0),
-            Token::NUMBER(2.0),
+            Token::NUMBER(Number::Fixnum(30)),
+            Token::NUMBER(Number::Fixnum(2)),
             Token::RPAREN,
             Token::LPAREN,
             Token::ASTERISK,
             Token::LPAREN,
             Token::SLASH,
-            Token::NUMBER(4.0),
-            Token::NUMBER(2.0),
+            Token::NUMBER(Number::Fixnum(4)),
+            Token::NUMBER(Number::Fixnum(2)),
             Token::RPAREN,
-            Token::NUMBER(3.0),
+            Token::NUMBER(Number::Fixnum(3)),
             Token::RPAREN,
             Token::RPAREN,
             Token::EOF,
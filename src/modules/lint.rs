@@ -0,0 +1,325 @@
+use super::ast::Expr;
+use super::clos::Class;
+use super::error::RispError;
+use super::eval::ExprEnv;
+use super::printer::prin1_to_string;
+use super::types::{is_builtin_type, TypeSpec};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// A function type declared by (declaim (ftype (function (params...) result) names...)).
+// The type specifiers are kept unparsed, and the result type is not checked.
+pub struct FunctionType {
+    pub params: Vec<Expr>,
+    pub result: Expr,
+}
+
+impl FunctionType {
+    pub fn parse(x: &Expr) -> Result<Self, RispError> {
+        if let Expr::List(list) = x {
+            if let [Expr::Symbol(function), params, result] = list.as_ref() {
+                if &**function == "FUNCTION" {
+                    let params = match params {
+                        Expr::List(params) => params.to_vec(),
+                        Expr::Nil => vec![],
+                        _ => return Err(invalid_declaration(x)),
+                    };
+                    return Ok(Self {
+                        params,
+                        result: result.clone(),
+                    });
+                }
+            }
+        }
+        Err(invalid_declaration(x))
+    }
+}
+
+fn invalid_declaration(x: &Expr) -> RispError {
+    RispError::Expr(format!("Invalid declaration {}", prin1_to_string(x)))
+}
+
+// Splits the leading (declare ...) forms off a body, returning the declared
+// types of variables and the remaining forms. Besides (type spec vars...),
+// the (spec vars...) shorthand is accepted and IGNORE, IGNORABLE and
+// OPTIMIZE clauses are skipped.
+pub fn parse_declarations(forms: &[Expr]) -> Result<(HashMap<String, Expr>, &[Expr]), RispError> {
+    let mut types = HashMap::new();
+    let mut rest = forms;
+    while let Some((Expr::List(form), next)) = rest.split_first() {
+        let clauses = match form.split_first() {
+            Some((Expr::Symbol(declare), clauses)) if &**declare == "DECLARE" => clauses,
+            _ => break,
+        };
+        for clause in clauses {
+            let (head, args) = match clause {
+                Expr::List(list) => match list.split_first() {
+                    Some((Expr::Symbol(head), args)) => (&**head, args),
+                    _ => return Err(invalid_declaration(clause)),
+                },
+                _ => return Err(invalid_declaration(clause)),
+            };
+            let (spec, vars) = match (head, args) {
+                ("IGNORE" | "IGNORABLE" | "OPTIMIZE", _) => continue,
+                ("TYPE", [spec, vars @ ..]) => (spec.clone(), vars),
+                ("TYPE", []) => return Err(invalid_declaration(clause)),
+                (_, vars) => (Expr::Symbol(head.into()), vars),
+            };
+            for var in vars {
+                match var {
+                    Expr::Symbol(var) => {
+                        types.insert(var.to_string(), spec.clone());
+                    }
+                    _ => return Err(invalid_declaration(clause)),
+                }
+            }
+        }
+        rest = next;
+    }
+    Ok((types, rest))
+}
+
+// Arity and parameter types of a function as far as they are known before
+// running it.
+struct Signature {
+    min_args: usize,
+    max_args: usize,
+    types: Vec<Option<Expr>>,
+}
+
+// A static pass over forms reporting calls with the wrong number of arguments,
+// literal arguments which do not match the declared parameter types, and
+// declared types which are not known.
+pub struct Linter<'a> {
+    env: &'a ExprEnv,
+    ftypes: &'a HashMap<String, FunctionType>,
+    classes: &'a HashMap<String, Rc<Class>>,
+    pub diagnostics: Vec<String>,
+}
+
+impl<'a> Linter<'a> {
+    pub fn new(
+        env: &'a ExprEnv,
+        ftypes: &'a HashMap<String, FunctionType>,
+        classes: &'a HashMap<String, Rc<Class>>,
+    ) -> Self {
+        Self {
+            env,
+            ftypes,
+            classes,
+            diagnostics: vec![],
+        }
+    }
+
+    pub fn check(&mut self, form: &Expr) {
+        let list = match form {
+            Expr::List(list) => list,
+            _ => return,
+        };
+        let (head, args) = match list.split_first() {
+            Some((Expr::Symbol(head), args)) => (&**head, args),
+            Some((head, args)) => {
+                self.check(head);
+                self.check_all(args);
+                return;
+            }
+            None => return,
+        };
+        match head {
+            "QUOTE" | "DECLAIM" | "DECLARE" | "DEFSTRUCT" | "DEFCLASS" | "DEFGENERIC"
+            | "DEFMETHOD" | "DEFPACKAGE" | "IN-PACKAGE" => {}
+            "SETQ" => self.check_all(args.iter().skip(1).step_by(2)),
+            "IF" | "SETF" | "FUNCTION" => self.check_all(args),
+            "CHECK-TYPE" => self.check_all(args.first()),
+            "LAMBDA" => {
+                if let [_, body @ ..] = args {
+                    self.check_body(body);
+                }
+            }
+            "LET" | "LET*" => {
+                if let [Expr::List(bindings), body @ ..] = args {
+                    for binding in bindings.iter() {
+                        if let Expr::List(binding) = binding {
+                            self.check_all(binding.iter().skip(1));
+                        }
+                    }
+                    self.check_body(body);
+                }
+            }
+            "DEFVAR" | "DEFPARAMETER" | "DEFCONSTANT" => self.check_all(args.get(1)),
+            "MULTIPLE-VALUE-BIND" => {
+                if let [_, form, body @ ..] = args {
+                    self.check(form);
+                    self.check_body(body);
+                }
+            }
+            "DEFUN" => {
+                if let [_, _, body @ ..] = args {
+                    self.check_body(body);
+                }
+            }
+            _ => {
+                self.check_call(head, args);
+                self.check_all(args);
+            }
+        }
+    }
+
+    fn check_all<'b>(&mut self, forms: impl IntoIterator<Item = &'b Expr>) {
+        for form in forms {
+            self.check(form);
+        }
+    }
+
+    fn check_body(&mut self, body: &[Expr]) {
+        match parse_declarations(body) {
+            Ok((types, body)) => {
+                self.check_declared_types(&types);
+                self.check_all(body);
+            }
+            Err(err) => self.diagnostics.push(err.to_string()),
+        }
+    }
+
+    // Checks the types declared for variables, in the order of the names.
+    pub fn check_declared_types(&mut self, types: &HashMap<String, Expr>) {
+        let mut vars: Vec<_> = types.iter().collect();
+        vars.sort_by_key(|(var, _)| *var);
+        for (var, spec) in vars {
+            self.check_type(spec, var);
+        }
+    }
+
+    // Checks the parameter and result types of a declaimed function type.
+    pub fn check_function_type(&mut self, ftype: &FunctionType, name: &str) {
+        for spec in ftype.params.iter().chain([&ftype.result]) {
+            self.check_type(spec, name);
+        }
+    }
+
+    // Reports the type names in a specifier declared for `name` which TYPEP
+    // would not recognise: builtin types, structures and classes.
+    fn check_type(&mut self, spec: &Expr, name: &str) {
+        let spec = match TypeSpec::parse(spec) {
+            Ok(spec) => spec,
+            Err(err) => return self.diagnostics.push(err.to_string()),
+        };
+        for ty in spec.names() {
+            if !is_builtin_type(ty)
+                && self.env.struct_type(ty).is_none()
+                && !self.classes.contains_key(ty)
+            {
+                self.diagnostics.push(format!(
+                    "Unknown type {} in the declaration of {}",
+                    ty, name
+                ));
+            }
+        }
+    }
+
+    fn signature(&self, name: &str) -> Option<Signature> {
+        if let Some(ftype) = self.ftypes.get(name) {
+            return Some(Signature {
+                min_args: ftype.params.len(),
+                max_args: ftype.params.len(),
+                types: ftype.params.iter().cloned().map(Some).collect(),
+            });
+        }
+        match &self.env.get_function(name)? {
+            Expr::Func(native) => Some(Signature {
+                min_args: native.min_args,
+                max_args: native.max_args,
+                types: vec![],
+            }),
+            Expr::Lambda(lambda) => Some(Signature {
+                min_args: lambda.args.len(),
+                max_args: lambda.args.len(),
+                types: lambda.arg_types.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    fn check_call(&mut self, name: &str, args: &[Expr]) {
+        let signature = match self.signature(name) {
+            Some(signature) => signature,
+            None => return,
+        };
+        if args.len() < signature.min_args || args.len() > signature.max_args {
+            let expected = if signature.max_args == usize::MAX {
+                format!("at least {}", signature.min_args)
+            } else if signature.min_args == signature.max_args {
+                signature.min_args.to_string()
+            } else {
+                format!("{} to {}", signature.min_args, signature.max_args)
+            };
+            self.diagnostics.push(format!(
+                "{} is called with {} arguments, but takes {}",
+                name,
+                args.len(),
+                expected
+            ));
+        }
+        for (i, (arg, spec)) in args.iter().zip(&signature.types).enumerate() {
+            let (value, spec) = match (literal_value(arg), spec) {
+                (Some(value), Some(spec)) => (value, spec),
+                _ => continue,
+            };
+            let matches =
+                TypeSpec::parse(spec).and_then(|ty| ty.matches(&value, &mut |_, _| Ok(true)));
+            match matches {
+                Ok(true) => {}
+                Ok(false) => self.diagnostics.push(format!(
+                    "The argument {} of {}, {}, is not of type {}",
+                    i + 1,
+                    name,
+                    prin1_to_string(&value),
+                    prin1_to_string(spec)
+                )),
+                Err(err) => self.diagnostics.push(err.to_string()),
+            }
+        }
+    }
+}
+
+// The value of a form whose value is known without evaluating it.
+fn literal_value(x: &Expr) -> Option<Expr> {
+    match x {
+        Expr::Symbol(sym) if sym.starts_with(':') => Some(x.clone()),
+        Expr::Symbol(_) => None,
+        Expr::List(list) => match list.as_ref() {
+            [Expr::Symbol(quote), value] if &**quote == "QUOTE" => Some(value.clone()),
+            _ => None,
+        },
+        _ => Some(x.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::lexer::Lexer;
+    use crate::modules::parser::Parser;
+
+    fn read(s: &str) -> Expr {
+        Parser::new(Lexer::new(s.to_string()).unwrap())
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn declarations() {
+        let forms = [
+            read("(declare (type (integer 0 *) x y) (ignore z))"),
+            read("(declare (string s))"),
+            read("(+ x y)"),
+        ];
+        let (types, body) = parse_declarations(&forms).unwrap();
+        assert_eq!(body.len(), 1);
+        assert_eq!(prin1_to_string(&types["X"]), "(INTEGER 0 *)");
+        assert_eq!(prin1_to_string(&types["Y"]), "(INTEGER 0 *)");
+        assert_eq!(prin1_to_string(&types["S"]), "STRING");
+        assert!(!types.contains_key("Z"));
+        assert!(parse_declarations(&[read("(declare (type))")]).is_err());
+    }
+}
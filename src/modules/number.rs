@@ -0,0 +1,398 @@
+use super::error::RispError;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+// Numeric tower: Fixnum < Bignum < Ratio < Float.
+// Exact values are always kept normalized, so that an integer which fits in i64
+// is a Fixnum and a ratio whose denominator is 1 is an integer.
+#[derive(Debug, Clone)]
+pub enum Number {
+    Fixnum(i64),
+    Bignum(BigInt),
+    Ratio(BigRational),
+    Float(f64),
+}
+
+// Equality follows EQL: the same type and value, with floats compared by
+// their representation so that equality agrees with hashing.
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Number::Fixnum(a), Number::Fixnum(b)) => a == b,
+            (Number::Bignum(a), Number::Bignum(b)) => a == b,
+            (Number::Ratio(a), Number::Ratio(b)) => a == b,
+            (Number::Float(a), Number::Float(b)) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Number {}
+
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Number::Fixnum(n) => n.hash(state),
+            Number::Bignum(n) => n.hash(state),
+            Number::Ratio(r) => r.hash(state),
+            Number::Float(f) => f.to_bits().hash(state),
+        }
+    }
+}
+
+impl Number {
+    pub fn from_bigint(num: BigInt) -> Self {
+        match num.to_i64() {
+            Some(n) => Number::Fixnum(n),
+            None => Number::Bignum(num),
+        }
+    }
+
+    pub fn from_ratio(ratio: BigRational) -> Self {
+        if ratio.is_integer() {
+            Number::from_bigint(ratio.to_integer())
+        } else {
+            Number::Ratio(ratio)
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, RispError> {
+        // Floats have a decimal point or an exponent, which may be marked with
+        // any of the letters CL uses for its float formats.
+        if let Some((mantissa, exponent)) = s.split_once(|ch: char| "eEdDfFsSlL".contains(ch)) {
+            return Ok(Number::Float(
+                format!("{}e{}", mantissa, exponent).parse::<f64>()?,
+            ));
+        }
+        if s.contains('.') {
+            return Ok(Number::Float(s.parse::<f64>()?));
+        }
+        if let Some((numer, denom)) = s.split_once('/') {
+            let numer = numer.parse::<BigInt>()?;
+            let denom = denom.parse::<BigInt>()?;
+            if denom.is_zero() {
+                return Err(RispError::Expr(format!("Division by zero: {}", s)));
+            }
+            return Ok(Number::from_ratio(BigRational::new(numer, denom)));
+        }
+        match s.parse::<i64>() {
+            Ok(n) => Ok(Number::Fixnum(n)),
+            Err(_) => Ok(Number::from_bigint(s.parse::<BigInt>()?)),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Number::Fixnum(n) => *n == 0,
+            Number::Bignum(n) => n.is_zero(),
+            Number::Ratio(r) => r.is_zero(),
+            Number::Float(f) => *f == 0.0,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Number::Float(_))
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Fixnum(n) => *n as f64,
+            Number::Bignum(n) => n.to_f64().unwrap_or(f64::NAN),
+            Number::Ratio(r) => r.to_f64().unwrap_or(f64::NAN),
+            Number::Float(f) => *f,
+        }
+    }
+
+    // Exact rational value of a number. Infinite and NaN floats have none.
+    pub fn to_ratio(&self) -> Option<BigRational> {
+        match self {
+            Number::Fixnum(n) => Some(BigRational::from_integer(BigInt::from(*n))),
+            Number::Bignum(n) => Some(BigRational::from_integer(n.clone())),
+            Number::Ratio(r) => Some(r.clone()),
+            Number::Float(f) => BigRational::from_float(*f),
+        }
+    }
+
+    // Exact integer value of a Fixnum or Bignum.
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            Number::Fixnum(n) => Some(BigInt::from(*n)),
+            Number::Bignum(n) => Some(n.clone()),
+            _ => None,
+        }
+    }
+
+    // Applies the contagion rules: any float makes the result a float,
+    // otherwise any ratio makes it a ratio, otherwise the result is an integer
+    // which overflows from Fixnum into Bignum.
+    fn contagion(
+        &self,
+        other: &Number,
+        fixnum: fn(i64, i64) -> Option<i64>,
+        integer: fn(&BigInt, &BigInt) -> BigInt,
+        ratio: fn(&BigRational, &BigRational) -> BigRational,
+        float: fn(f64, f64) -> f64,
+    ) -> Number {
+        match (self, other) {
+            (Number::Float(_), _) | (_, Number::Float(_)) => {
+                Number::Float(float(self.to_f64(), other.to_f64()))
+            }
+            (Number::Ratio(_), _) | (_, Number::Ratio(_)) => {
+                match (self.to_ratio(), other.to_ratio()) {
+                    (Some(a), Some(b)) => Number::from_ratio(ratio(&a, &b)),
+                    _ => unreachable!(),
+                }
+            }
+            (Number::Fixnum(a), Number::Fixnum(b)) => match fixnum(*a, *b) {
+                Some(n) => Number::Fixnum(n),
+                None => Number::from_bigint(integer(&BigInt::from(*a), &BigInt::from(*b))),
+            },
+            _ => match (self.to_bigint(), other.to_bigint()) {
+                (Some(a), Some(b)) => Number::from_bigint(integer(&a, &b)),
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    pub fn add(&self, other: &Number) -> Number {
+        self.contagion(
+            other,
+            i64::checked_add,
+            |a, b| a + b,
+            |a, b| a + b,
+            |a, b| a + b,
+        )
+    }
+
+    pub fn sub(&self, other: &Number) -> Number {
+        self.contagion(
+            other,
+            i64::checked_sub,
+            |a, b| a - b,
+            |a, b| a - b,
+            |a, b| a - b,
+        )
+    }
+
+    pub fn mul(&self, other: &Number) -> Number {
+        self.contagion(
+            other,
+            i64::checked_mul,
+            |a, b| a * b,
+            |a, b| a * b,
+            |a, b| a * b,
+        )
+    }
+
+    // Division of two integers is exact and produces a ratio when the
+    // divisor does not divide the dividend.
+    pub fn div(&self, other: &Number) -> Result<Number, RispError> {
+        if other.is_zero() {
+            return Err(RispError::Expr(format!(
+                "Division by zero: {} / {}",
+                self, other
+            )));
+        }
+        if self.is_float() || other.is_float() {
+            return Ok(Number::Float(self.to_f64() / other.to_f64()));
+        }
+        match (self.to_ratio(), other.to_ratio()) {
+            (Some(a), Some(b)) => Ok(Number::from_ratio(a / b)),
+            _ => unreachable!(),
+        }
+    }
+
+    // Division giving an integer quotient rounded as FLOOR, CEILING, TRUNCATE
+    // and ROUND do, the last rounding halves to even, and the remainder, which
+    // is a float when either number is.
+    pub fn divide(
+        &self,
+        other: &Number,
+        rounding: Rounding,
+    ) -> Result<(Number, Number), RispError> {
+        let quotient = match self.div(other)? {
+            Number::Float(f) => {
+                let q = match rounding {
+                    Rounding::Floor => f.floor(),
+                    Rounding::Ceiling => f.ceil(),
+                    Rounding::Truncate => f.trunc(),
+                    Rounding::Round => f.round_ties_even(),
+                };
+                BigInt::from_f64(q).ok_or_else(|| {
+                    RispError::Expr(format!("Cannot convert {} to integer", Number::Float(q)))
+                })?
+            }
+            quotient => {
+                let r = quotient.to_ratio().unwrap();
+                match rounding {
+                    Rounding::Floor => r.floor().to_integer(),
+                    Rounding::Ceiling => r.ceil().to_integer(),
+                    Rounding::Truncate => r.trunc().to_integer(),
+                    Rounding::Round => {
+                        let floor = r.floor();
+                        let half = BigRational::new(BigInt::one(), BigInt::from(2));
+                        let diff = &r - &floor;
+                        let floor = floor.to_integer();
+                        if diff > half || (diff == half && !(&floor % BigInt::from(2)).is_zero()) {
+                            floor + 1
+                        } else {
+                            floor
+                        }
+                    }
+                }
+            }
+        };
+        let quotient = Number::from_bigint(quotient);
+        let remainder = self.sub(&quotient.mul(other));
+        Ok((quotient, remainder))
+    }
+
+    // Numeric comparison across the tower as done by `=` and `<`, where
+    // rationals and floats are compared exactly.
+    pub fn num_cmp(&self, other: &Number) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Fixnum(a), Number::Fixnum(b)) => Some(a.cmp(b)),
+            (Number::Float(a), Number::Float(b)) => a.partial_cmp(b),
+            _ => match (self.to_ratio(), other.to_ratio()) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                _ => self.to_f64().partial_cmp(&other.to_f64()),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Rounding {
+    Floor,
+    Ceiling,
+    Truncate,
+    Round,
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Number::Fixnum(n) => write!(f, "{}", n),
+            Number::Bignum(n) => write!(f, "{}", n),
+            Number::Ratio(r) => write!(f, "{}/{}", r.numer(), r.denom()),
+            Number::Float(num) => {
+                // Floats always carry a decimal point so they read back as floats.
+                let s = format!("{:?}", num);
+                match s.split_once('e') {
+                    Some((mantissa, exp)) if !mantissa.contains('.') => {
+                        write!(f, "{}.0e{}", mantissa, exp)
+                    }
+                    _ => write!(f, "{}", s),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_number() {
+        let tests = vec![
+            ("1", Number::Fixnum(1)),
+            ("-12", Number::Fixnum(-12)),
+            ("1.5", Number::Float(1.5)),
+            ("1.5e10", Number::Float(1.5e10)),
+            ("2d-3", Number::Float(0.002)),
+            ("4/2", Number::Fixnum(2)),
+            (
+                "99999999999999999999",
+                Number::Bignum("99999999999999999999".parse().unwrap()),
+            ),
+        ];
+        for (input, want) in tests {
+            assert_eq!(Number::parse(input).unwrap(), want);
+        }
+    }
+
+    // Floats print in a form that reads back as the same float.
+    #[test]
+    fn float_round_trip() {
+        for x in [
+            1.5,
+            -0.25,
+            1.0e20,
+            1.5e-7,
+            123456789.0,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+        ] {
+            let printed = Number::Float(x).to_string();
+            assert_eq!(
+                Number::parse(&printed).unwrap(),
+                Number::Float(x),
+                "{}",
+                printed
+            );
+        }
+    }
+
+    #[test]
+    fn fixnum_overflow() {
+        let a = Number::Fixnum(99999999999);
+        let result = a.mul(&a);
+        assert_eq!(result.to_string(), "9999999999800000000001");
+        assert_eq!(result.sub(&result), Number::Fixnum(0));
+    }
+
+    #[test]
+    fn exact_division() {
+        let result = Number::Fixnum(1).div(&Number::Fixnum(3)).unwrap();
+        assert_eq!(result.to_string(), "1/3");
+        assert_eq!(result.mul(&Number::Fixnum(3)), Number::Fixnum(1));
+        assert!(Number::Fixnum(1).div(&Number::Fixnum(0)).is_err());
+    }
+
+    #[test]
+    fn float_contagion() {
+        let tests = vec![
+            (Number::Fixnum(1).add(&Number::Float(0.5)), "1.5"),
+            (
+                Number::parse("1/2").unwrap().add(&Number::Float(0.5)),
+                "1.0",
+            ),
+            (Number::Float(1e20), "1.0e20"),
+        ];
+        for (result, want) in tests {
+            assert_eq!(result.to_string(), want);
+        }
+    }
+
+    #[test]
+    fn rounding_division() {
+        let tests = vec![
+            ("-7", "2", Rounding::Floor, "-4", "1"),
+            ("-7", "2", Rounding::Ceiling, "-3", "-1"),
+            ("-7", "2", Rounding::Truncate, "-3", "-1"),
+            ("5", "2", Rounding::Round, "2", "1"),
+            ("-5", "2", Rounding::Round, "-2", "-1"),
+            ("7/2", "1", Rounding::Floor, "3", "1/2"),
+            ("2.5", "1", Rounding::Round, "2", "0.5"),
+        ];
+        for (x, y, rounding, quotient, remainder) in tests {
+            let (q, r) = Number::parse(x)
+                .unwrap()
+                .divide(&Number::parse(y).unwrap(), rounding)
+                .unwrap();
+            assert_eq!(
+                (q.to_string(), r.to_string()),
+                (quotient.into(), remainder.into())
+            );
+        }
+        assert!(Number::Fixnum(1)
+            .divide(&Number::Fixnum(0), Rounding::Floor)
+            .is_err());
+    }
+}
@@ -0,0 +1,411 @@
+use super::ast::Expr;
+use super::error::RispError;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+pub const COMMON_LISP: &str = "COMMON-LISP";
+pub const COMMON_LISP_USER: &str = "COMMON-LISP-USER";
+
+// Symbols of the COMMON-LISP package which are not the names of builtin
+// functions or variables: constants, special operators, declarations and type
+// names.
+const SPECIAL_SYMBOLS: [&str; 66] = [
+    "T",
+    "NIL",
+    "QUOTE",
+    "IF",
+    "SETQ",
+    "SETF",
+    "DEFUN",
+    "LAMBDA",
+    "FUNCTION",
+    "DECLAIM",
+    "DECLARE",
+    "DEFSTRUCT",
+    "DEFCLASS",
+    "DEFGENERIC",
+    "DEFMETHOD",
+    "CALL-NEXT-METHOD",
+    "NEXT-METHOD-P",
+    "CHECK-TYPE",
+    "LET",
+    "LET*",
+    "DEFVAR",
+    "DEFPARAMETER",
+    "DEFCONSTANT",
+    "MULTIPLE-VALUE-BIND",
+    "MULTIPLE-VALUE-LIST",
+    "NTH-VALUE",
+    "DEFPACKAGE",
+    "IN-PACKAGE",
+    "TIME",
+    "TYPE",
+    "FTYPE",
+    "IGNORE",
+    "IGNORABLE",
+    "OPTIMIZE",
+    "ATOM",
+    "BOOLEAN",
+    "FIXNUM",
+    "BIGNUM",
+    "INTEGER",
+    "RATIO",
+    "RATIONAL",
+    "REAL",
+    "FLOAT",
+    "DOUBLE-FLOAT",
+    "NUMBER",
+    "STRING",
+    "VECTOR",
+    "ARRAY",
+    "SEQUENCE",
+    "CHARACTER",
+    "KEYWORD",
+    "SYMBOL",
+    "NULL",
+    "LIST",
+    "CONS",
+    "STREAM",
+    "HASH-TABLE",
+    "PACKAGE",
+    "STRUCTURE-OBJECT",
+    "STANDARD-OBJECT",
+    "OR",
+    "AND",
+    "NOT",
+    "MEMBER",
+    "EQL",
+    "SATISFIES",
+];
+
+// A namespace of symbols. Symbols are kept as strings: those of COMMON-LISP
+// and COMMON-LISP-USER by their bare names, which is what the evaluator and
+// the builtins see, and the others qualified as PACKAGE::NAME.
+pub struct Package {
+    pub name: String,
+    pub nicknames: Vec<String>,
+    // Packages whose external symbols are accessible without a prefix.
+    pub uses: Vec<String>,
+    // Names of the symbols whose home is this package.
+    symbols: HashSet<String>,
+    // External symbols by name. A package may export a symbol it inherits.
+    exports: HashMap<String, String>,
+}
+
+impl Package {
+    fn new(name: &str, nicknames: &[&str], uses: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            nicknames: nicknames.iter().map(|name| name.to_string()).collect(),
+            uses: uses.iter().map(|name| name.to_string()).collect(),
+            symbols: HashSet::new(),
+            exports: HashMap::new(),
+        }
+    }
+
+    // The symbol named `name` whose home is this package.
+    fn symbol(&self, name: &str) -> String {
+        if self.name == COMMON_LISP || self.name == COMMON_LISP_USER {
+            name.to_string()
+        } else {
+            format!("{}::{}", self.name, name)
+        }
+    }
+}
+
+pub fn expect_package(x: &Expr) -> Result<Rc<RefCell<Package>>, RispError> {
+    match x {
+        Expr::Package(package) => Ok(package.clone()),
+        _ => Err(RispError::Expr(format!("{} is not package", x))),
+    }
+}
+
+// The name designated by a string, a symbol or a keyword, as packages and
+// modules are named.
+pub fn designated_name(x: &Expr) -> Result<String, RispError> {
+    match x {
+        Expr::String(s) => Ok(s.to_string()),
+        Expr::Symbol(sym) => Ok(symbol_name(sym.strip_prefix(':').unwrap_or(sym)).to_string()),
+        Expr::Package(package) => Ok(package.borrow().name.clone()),
+        _ => Err(RispError::Expr(format!("{} is not package designator", x))),
+    }
+}
+
+// The name of a symbol without its package prefix.
+pub fn symbol_name(symbol: &str) -> &str {
+    match symbol.find("::") {
+        Some(i) => &symbol[i + 2..],
+        None => symbol,
+    }
+}
+
+// The symbol named `name` in the package of `symbol`, such as the accessors
+// DEFSTRUCT derives from the name of a structure.
+pub fn sibling_symbol(symbol: &str, name: &str) -> String {
+    match symbol.find("::") {
+        Some(i) => format!("{}{}", &symbol[..i + 2], name),
+        None => name.to_string(),
+    }
+}
+
+// All the packages and the current one, in which the reader interns the
+// symbols it reads.
+pub struct Packages {
+    packages: Vec<Rc<RefCell<Package>>>,
+    current: Rc<RefCell<Package>>,
+}
+
+impl Default for Packages {
+    fn default() -> Self {
+        let mut common_lisp = Package::new(COMMON_LISP, &["CL", "RISP"], &[]);
+        for name in SPECIAL_SYMBOLS {
+            common_lisp.symbols.insert(name.to_string());
+            common_lisp
+                .exports
+                .insert(name.to_string(), name.to_string());
+        }
+        let user = Package::new(COMMON_LISP_USER, &["CL-USER"], &[COMMON_LISP]);
+        let current = Rc::new(RefCell::new(user));
+        Self {
+            packages: vec![Rc::new(RefCell::new(common_lisp)), current.clone()],
+            current,
+        }
+    }
+}
+
+impl Packages {
+    pub fn find(&self, name: &str) -> Option<Rc<RefCell<Package>>> {
+        self.packages
+            .iter()
+            .find(|package| {
+                let package = package.borrow();
+                package.name == name || package.nicknames.iter().any(|nickname| nickname == name)
+            })
+            .cloned()
+    }
+
+    fn expect(&self, name: &str) -> Result<Rc<RefCell<Package>>, RispError> {
+        self.find(name)
+            .ok_or_else(|| RispError::Read(format!("Package {} does not exist", name)))
+    }
+
+    pub fn current(&self) -> Rc<RefCell<Package>> {
+        self.current.clone()
+    }
+
+    // Makes a package current again, as after loading a file.
+    pub fn restore(&mut self, package: Rc<RefCell<Package>>) {
+        self.current = package;
+    }
+
+    pub fn set_current(&mut self, name: &str) -> Result<Rc<RefCell<Package>>, RispError> {
+        self.current = self.expect(name)?;
+        Ok(self.current.clone())
+    }
+
+    // Makes the names external symbols of COMMON-LISP, once the builtins
+    // defining them are registered.
+    pub fn export_common_lisp<'a>(&mut self, names: impl IntoIterator<Item = &'a String>) {
+        let common_lisp = self.expect(COMMON_LISP).unwrap();
+        let mut common_lisp = common_lisp.borrow_mut();
+        for name in names {
+            common_lisp.symbols.insert(name.clone());
+            common_lisp.exports.insert(name.clone(), name.clone());
+        }
+    }
+
+    // Creates a package, or updates the one of the same name, as DEFPACKAGE
+    // does. The exported names are interned in the package unless they are
+    // inherited from a used package. Used packages may not export different
+    // symbols of the same name.
+    pub fn define(
+        &mut self,
+        name: &str,
+        nicknames: &[String],
+        uses: &[String],
+        exports: &[String],
+    ) -> Result<Rc<RefCell<Package>>, RispError> {
+        let uses = uses
+            .iter()
+            .map(|used| Ok(self.expect(used)?.borrow().name.clone()))
+            .collect::<Result<Vec<_>, RispError>>()?;
+        let mut all_uses = match self.find(name) {
+            Some(package) => package.borrow().uses.clone(),
+            None => vec![],
+        };
+        all_uses.extend(uses.iter().cloned());
+        self.check_use_conflicts(name, &all_uses)?;
+        let package = match self.find(name) {
+            Some(package) => package,
+            None => {
+                let package = Rc::new(RefCell::new(Package::new(name, &[], &[])));
+                self.packages.push(package.clone());
+                package
+            }
+        };
+        {
+            let mut package = package.borrow_mut();
+            for nickname in nicknames {
+                if !package.nicknames.contains(nickname) {
+                    package.nicknames.push(nickname.clone());
+                }
+            }
+            for used in uses {
+                if !package.uses.contains(&used) {
+                    package.uses.push(used);
+                }
+            }
+        }
+        for name in exports {
+            let symbol = self.intern_in(&package, name);
+            package.borrow_mut().exports.insert(name.clone(), symbol);
+        }
+        Ok(package)
+    }
+
+    // Fails if two of the packages used by `name` export different symbols
+    // of the same name, since either could be meant by it.
+    fn check_use_conflicts(&self, name: &str, uses: &[String]) -> Result<(), RispError> {
+        let mut inherited: HashMap<String, (String, String)> = HashMap::new();
+        for used in uses.iter().filter(|used| **used != name) {
+            let used = self.expect(used)?;
+            let used = used.borrow();
+            let mut exports: Vec<_> = used.exports.iter().collect();
+            exports.sort();
+            for (export, symbol) in exports {
+                match inherited.get(export) {
+                    Some((other, previous)) if previous != symbol => {
+                        return Err(RispError::Expr(format!(
+                            "Package {} cannot use both {} and {}, which export different symbols named {}",
+                            name, other, used.name, export
+                        )))
+                    }
+                    Some(_) => {}
+                    None => {
+                        inherited.insert(export.clone(), (used.name.clone(), symbol.clone()));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Resolves a symbol as read, such as FOO, PKG:FOO or PKG::FOO, to the
+    // symbol it names in the current package, interning it when it is new.
+    pub fn intern(&mut self, token: &str) -> Result<String, RispError> {
+        if token.starts_with(':') {
+            return Ok(token.to_string());
+        }
+        let i = match token.find(':') {
+            Some(i) => i,
+            None => {
+                let current = self.current.clone();
+                return Ok(self.intern_in(&current, token));
+            }
+        };
+        let package = self.expect(&token[..i])?;
+        match token[i..].strip_prefix("::") {
+            Some(name) => Ok(self.intern_in(&package, name)),
+            None => {
+                let name = &token[i + 1..];
+                let package = package.borrow();
+                package.exports.get(name).cloned().ok_or_else(|| {
+                    RispError::Read(format!(
+                        "Symbol {} is not external in package {}",
+                        name, package.name
+                    ))
+                })
+            }
+        }
+    }
+
+    // The symbol accessible as `name` in the package: its own symbol, an
+    // external symbol of a used package or a new symbol.
+    fn intern_in(&self, package: &Rc<RefCell<Package>>, name: &str) -> String {
+        let mut package = package.borrow_mut();
+        if package.symbols.contains(name) {
+            return package.symbol(name);
+        }
+        for used in package.uses.iter().filter(|used| **used != package.name) {
+            if let Some(symbol) = self
+                .find(used)
+                .and_then(|used| used.borrow().exports.get(name).cloned())
+            {
+                return symbol;
+            }
+        }
+        package.symbols.insert(name.to_string());
+        package.symbol(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn intern_symbols() {
+        let mut packages = Packages::default();
+        packages.export_common_lisp(&names(&["CAR"]));
+        assert_eq!(packages.intern("FOO").unwrap(), "FOO");
+        assert_eq!(packages.intern("CL:CAR").unwrap(), "CAR");
+        assert_eq!(packages.intern(":KEY").unwrap(), ":KEY");
+        assert!(packages.intern("CL:FOO").is_err());
+        assert!(packages.intern("NONE:FOO").is_err());
+
+        packages
+            .define(
+                "SHAPES",
+                &names(&["S"]),
+                &names(&["CL"]),
+                &names(&["AREA", "CAR"]),
+            )
+            .unwrap();
+        assert_eq!(packages.intern("SHAPES:AREA").unwrap(), "SHAPES::AREA");
+        assert_eq!(packages.intern("S:CAR").unwrap(), "CAR");
+        assert!(packages.intern("SHAPES:SIDE").is_err());
+        assert_eq!(packages.intern("SHAPES::SIDE").unwrap(), "SHAPES::SIDE");
+
+        packages.set_current("SHAPES").unwrap();
+        assert_eq!(packages.intern("AREA").unwrap(), "SHAPES::AREA");
+        assert_eq!(packages.intern("CAR").unwrap(), "CAR");
+        assert_eq!(packages.intern("FOO").unwrap(), "SHAPES::FOO");
+
+        packages
+            .define("APP", &[], &names(&["SHAPES"]), &[])
+            .unwrap();
+        packages.set_current("APP").unwrap();
+        assert_eq!(packages.intern("AREA").unwrap(), "SHAPES::AREA");
+        assert_eq!(packages.intern("FOO").unwrap(), "APP::FOO");
+        assert_eq!(packages.intern("CAR").unwrap(), "CAR");
+        // APP does not use COMMON-LISP.
+        assert_eq!(packages.intern("QUOTE").unwrap(), "APP::QUOTE");
+
+        packages
+            .define("GEOMETRY", &[], &[], &names(&["AREA", "CAR"]))
+            .unwrap();
+        let err = packages
+            .define("MIXED", &[], &names(&["SHAPES", "GEOMETRY"]), &[])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Package MIXED cannot use both SHAPES and GEOMETRY, which export different symbols named AREA"
+        );
+        assert!(packages.find("MIXED").is_none());
+        // Exporting the same symbol is no conflict.
+        packages
+            .define("BOTH", &[], &names(&["CL", "SHAPES"]), &[])
+            .unwrap();
+        let err = packages
+            .define("APP", &[], &names(&["GEOMETRY"]), &[])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("SHAPES and GEOMETRY"));
+    }
+}
@@ -1,47 +1,173 @@
+use super::array::Array;
 use super::ast::*;
 use super::error::*;
+use super::eval::{Evaluator, ExprEnv};
 use super::lexer::*;
+use super::package::symbol_name;
 use super::token::*;
 
 pub struct Parser {
     lexer: Lexer,
+    // Interns the symbols read in the current package and finds the
+    // structure types #S reads. Without an environment symbols are read as
+    // they are written.
+    env: Option<ExprEnv>,
 }
 
 impl Parser {
     pub fn new(lexer: Lexer) -> Self {
-        Self { lexer }
+        Self { lexer, env: None }
+    }
+
+    pub fn with_env(self, env: &ExprEnv) -> Self {
+        Self {
+            env: Some(env.clone()),
+            ..self
+        }
     }
 
     pub fn parse(&mut self) -> Result<Expr, RispError> {
         let token = self.lexer.next_token()?;
+        self.parse_token(token, None)
+    }
+
+    // Parses the next of a sequence of expressions, such as the forms of a
+    // file, returning `None` at the end of the input.
+    pub fn parse_next(&mut self) -> Result<Option<Expr>, RispError> {
+        self.parse_next_in(None)
+    }
+
+    // Like `parse`, running the constructors of the structures #S reads
+    // with the caller's evaluator, so that their slot defaults see its
+    // classes and generic functions.
+    pub fn read(&mut self, evaluator: &mut Evaluator) -> Result<Expr, RispError> {
+        let token = self.lexer.next_token()?;
+        self.parse_token(token, Some(evaluator))
+    }
+
+    // Like `parse_next`, with the caller's evaluator as in `read`.
+    pub fn read_next(&mut self, evaluator: &mut Evaluator) -> Result<Option<Expr>, RispError> {
+        self.parse_next_in(Some(evaluator))
+    }
+
+    fn parse_next_in(
+        &mut self,
+        evaluator: Option<&mut Evaluator>,
+    ) -> Result<Option<Expr>, RispError> {
+        match self.lexer.next_token()? {
+            Token::EOF => Ok(None),
+            Token::RPAREN => Err(RispError::Read("Unexpected )".to_string())),
+            token => Ok(Some(self.parse_token(token, evaluator)?)),
+        }
+    }
+
+    fn parse_token(
+        &mut self,
+        token: Token,
+        evaluator: Option<&mut Evaluator>,
+    ) -> Result<Expr, RispError> {
         match token {
             Token::NUMBER(num) => Ok(Expr::Number(num)),
-            Token::STRING(s) => Ok(Expr::String(s)),
-            Token::LITERAL(symbol) => Ok(Expr::Symbol(symbol)),
-            Token::ASTERISK => Ok(Expr::Symbol("*".to_string())),
-            Token::MINUS => Ok(Expr::Symbol("-".to_string())),
-            Token::PLUS => Ok(Expr::Symbol("+".to_string())),
-            Token::SLASH => Ok(Expr::Symbol("/".to_string())),
+            Token::STRING(s) => Ok(Expr::string(s)),
+            Token::CHAR(ch) => Ok(Expr::Char(ch)),
+            Token::LITERAL(symbol) => match &self.env {
+                Some(env) => match env.packages().borrow_mut().intern(&symbol)?.as_str() {
+                    "NIL" => Ok(Expr::Nil),
+                    "T" => Ok(Expr::True),
+                    symbol => Ok(Expr::Symbol(symbol.into())),
+                },
+                None => Ok(Expr::Symbol(symbol.into())),
+            },
+            // A symbol in no package, such as the names DEFPACKAGE takes.
+            Token::UNINTERNED(symbol) => Ok(Expr::Symbol(symbol.into())),
+            Token::ASTERISK => Ok(Expr::Symbol("*".into())),
+            Token::MINUS => Ok(Expr::Symbol("-".into())),
+            Token::PLUS => Ok(Expr::Symbol("+".into())),
+            Token::SLASH => Ok(Expr::Symbol("/".into())),
             Token::TRUE => Ok(Expr::True),
             Token::NIL => Ok(Expr::Nil),
             Token::ILLEGAL(token) => Err(RispError::Expr(format!("Invalid token: {}", token))),
             Token::EOF | Token::RPAREN => Ok(Expr::Nil),
+            Token::QUOTE => self.parse_quoted("QUOTE", evaluator),
+            Token::FUNCTION => self.parse_quoted("FUNCTION", evaluator),
             Token::LPAREN => {
-                let mut list = Vec::<Expr>::new();
-                loop {
-                    match self.parse() {
-                        Ok(expr) => {
-                            if expr == Expr::Nil {
-                                return Ok(Expr::List(list));
-                            }
-                            list.push(expr);
-                        }
-                        Err(e) => {
-                            return Err(e);
-                        }
-                    }
+                let list = self.parse_elements(evaluator)?;
+                if list.is_empty() {
+                    Ok(Expr::Nil)
+                } else {
+                    Ok(Expr::list(list))
                 }
             }
+            Token::VECTOR => Ok(Array::from_vec(self.parse_elements(evaluator)?).into_expr()),
+            Token::STRUCT => self.parse_struct(evaluator),
+        }
+    }
+
+    // Parses #S(name slot value...) as the structure its constructor makes,
+    // given the slots as keywords.
+    fn parse_struct(&mut self, mut evaluator: Option<&mut Evaluator>) -> Result<Expr, RispError> {
+        let elements = self.parse_elements(evaluator.as_deref_mut())?;
+        let (name, slots) = match elements.split_first() {
+            Some((Expr::Symbol(name), slots)) if slots.len() % 2 == 0 => (name, slots),
+            _ => return Err(RispError::Read("#S: invalid structure syntax".to_string())),
+        };
+        let (mut env, evaluator) = match (&self.env, evaluator) {
+            (Some(env), Some(evaluator)) => (env.clone(), evaluator),
+            _ => return Err(RispError::Read("#S: no structure types".to_string())),
+        };
+        let ty = env
+            .struct_type(name)
+            .ok_or_else(|| RispError::Read(format!("#S: {} is not a structure type", name)))?;
+        let constructor = ty
+            .constructor
+            .as_ref()
+            .and_then(|constructor| env.get_global_function(constructor))
+            .ok_or_else(|| RispError::Read(format!("#S: {} has no constructor", name)))?;
+        let mut args = vec![];
+        for pair in slots.chunks(2) {
+            let slot = match &pair[0] {
+                Expr::Symbol(slot) if slot.starts_with(':') => slot.clone(),
+                Expr::Symbol(slot) => format!(":{}", symbol_name(slot)).into(),
+                x => return Err(RispError::Read(format!("#S: {} is not a slot name", x))),
+            };
+            args.push(Expr::Symbol(slot));
+            args.push(pair[1].clone());
+        }
+        // The constructor only evaluates the default forms of the slots not
+        // given, in the global environment.
+        evaluator.apply(&constructor, &args, &mut env)
+    }
+
+    // Parses the expression after ' or #' as (QUOTE x) or (FUNCTION x).
+    fn parse_quoted(
+        &mut self,
+        operator: &str,
+        evaluator: Option<&mut Evaluator>,
+    ) -> Result<Expr, RispError> {
+        match self.lexer.next_token()? {
+            token @ (Token::EOF | Token::RPAREN) => Err(RispError::Expr(format!(
+                "Nothing to quote before {}",
+                token
+            ))),
+            token => Ok(Expr::list(vec![
+                Expr::Symbol(operator.into()),
+                self.parse_token(token, evaluator)?,
+            ])),
+        }
+    }
+
+    // Parses expressions up to the closing parenthesis.
+    fn parse_elements(
+        &mut self,
+        mut evaluator: Option<&mut Evaluator>,
+    ) -> Result<Vec<Expr>, RispError> {
+        let mut list = Vec::<Expr>::new();
+        loop {
+            match self.lexer.next_token()? {
+                Token::RPAREN => return Ok(list),
+                Token::EOF => return Err(RispError::Expr("Unbalanced parenthesis".to_string())),
+                token => list.push(self.parse_token(token, evaluator.as_deref_mut())?),
+            }
         }
     }
 }
@@ -61,6 +187,9 @@ mod tests {
             "(+ 1 2 (* 1 3))",
             "t",
             "nil",
+            "(LIST 1 NIL 2)",
+            "'(A B)",
+            "#(1 (2) #())",
         ];
         for test in tests {
             let lexer = Lexer::new(String::from(test)).unwrap();
@@ -69,4 +198,37 @@ mod tests {
             assert_eq!(expr.to_string(), test.to_uppercase());
         }
     }
+
+    #[test]
+    fn parse_quote() {
+        let lexer = Lexer::new(String::from("'(a 'b ())")).unwrap();
+        let mut parser = Parser::new(lexer);
+        let expr = parser.parse().unwrap();
+        assert_eq!(expr.to_string(), "'(A 'B NIL)");
+
+        let lexer = Lexer::new(String::from("(mapcar #'car #'(lambda (x) x))")).unwrap();
+        let mut parser = Parser::new(lexer);
+        let expr = parser.parse().unwrap();
+        assert_eq!(expr.to_string(), "(MAPCAR #'CAR #'(LAMBDA (X) X))");
+    }
+
+    #[test]
+    fn parse_sequence() {
+        let lexer = Lexer::new(String::from("(a)\n nil ; end\n")).unwrap();
+        let mut parser = Parser::new(lexer);
+        assert_eq!(parser.parse_next().unwrap().unwrap().to_string(), "(A)");
+        assert_eq!(parser.parse_next().unwrap().unwrap().to_string(), "NIL");
+        assert!(parser.parse_next().unwrap().is_none());
+        let lexer = Lexer::new(String::from("a)")).unwrap();
+        let mut parser = Parser::new(lexer);
+        parser.parse_next().unwrap();
+        assert!(parser.parse_next().is_err());
+    }
+
+    #[test]
+    fn parse_unbalanced() {
+        let lexer = Lexer::new(String::from("(* 10 (+ 2 40)")).unwrap();
+        let mut parser = Parser::new(lexer);
+        assert!(parser.parse().is_err());
+    }
 }
@@ -0,0 +1,171 @@
+use super::array::Array;
+use super::ast::Expr;
+use super::stream::Stream;
+
+/// The readable (PRIN1 style) representation of a value: strings are quoted
+/// and escaped, and characters are written with the `#\` syntax, so that the
+/// output can be read back. `Display` writes the PRINC representation.
+///
+/// ```
+/// let mut interp = risp::Interpreter::new();
+/// let value = interp.eval_str("(vector \"a\" #\\b)").unwrap();
+/// assert_eq!(risp::prin1_to_string(&value), "#(\"a\" #\\b)");
+/// assert_eq!(value.to_string(), "#(a b)");
+/// ```
+pub fn prin1_to_string(expr: &Expr) -> String {
+    write_expr(expr, true)
+}
+
+// Human oriented (PRINC style) representation, where strings and characters
+// are written as their raw contents.
+pub fn princ_to_string(expr: &Expr) -> String {
+    write_expr(expr, false)
+}
+
+fn write_expr(expr: &Expr, escape: bool) -> String {
+    match expr {
+        Expr::List(exprs) => match exprs.as_ref() {
+            [Expr::Symbol(sym), quoted] if &**sym == "QUOTE" => {
+                format!("'{}", write_expr(quoted, escape))
+            }
+            [Expr::Symbol(sym), function] if &**sym == "FUNCTION" => {
+                format!("#'{}", write_expr(function, escape))
+            }
+            _ => {
+                let xs = exprs
+                    .iter()
+                    .map(|x| write_expr(x, escape))
+                    .collect::<Vec<String>>();
+                format!("({})", xs.join(" "))
+            }
+        },
+        Expr::Number(num) => num.to_string(),
+        Expr::String(s) if escape => escape_string(s),
+        Expr::String(s) => s.to_string(),
+        Expr::Char(ch) if escape => format!("#\\{}", char_name(*ch)),
+        Expr::Char(ch) => ch.to_string(),
+        Expr::Symbol(sym) => sym.to_string(),
+        Expr::Nil => "NIL".to_string(),
+        Expr::True => "T".to_string(),
+        Expr::Func(native) => format!("#<SYSTEM-FUNCTION {}>", native.name),
+        Expr::Lambda(lambda) => match &lambda.name {
+            Some(name) => format!("#<FUNCTION {}>", name),
+            None => "#<FUNCTION LAMBDA>".to_string(),
+        },
+        Expr::Stream(Stream::Stdout) => "#<STANDARD-OUTPUT>".to_string(),
+        Expr::Stream(Stream::StringOutput(_)) => "#<STRING-OUTPUT-STREAM>".to_string(),
+        Expr::HashTable(table) => {
+            let table = table.borrow();
+            format!(
+                "#<HASH-TABLE :TEST {} :COUNT {}>",
+                table.test.name(),
+                table.len()
+            )
+        }
+        Expr::Vector(array) => {
+            let xs = array
+                .borrow()
+                .elements()
+                .iter()
+                .map(|x| write_expr(x, escape))
+                .collect::<Vec<String>>();
+            format!("#({})", xs.join(" "))
+        }
+        Expr::Struct(structure) => {
+            let structure = structure.borrow();
+            let mut xs = vec![structure.ty.name.clone()];
+            for (slot, value) in structure.ty.slots.iter().zip(&structure.values) {
+                xs.push(format!(":{}", slot.name));
+                xs.push(write_expr(value, escape));
+            }
+            format!("#S({})", xs.join(" "))
+        }
+        Expr::Instance(instance) => format!("#<{}>", instance.borrow().class.name),
+        Expr::Package(package) => format!("#<PACKAGE \"{}\">", package.borrow().name),
+        Expr::Array(array) => {
+            let array = array.borrow();
+            format!(
+                "#{}A{}",
+                array.rank(),
+                write_array(&array, &array.dimensions, 0, escape)
+            )
+        }
+    }
+}
+
+// Writes the elements of an array as nested lists, one level per dimension,
+// starting from the row-major index `offset`.
+fn write_array(array: &Array, dimensions: &[usize], offset: usize, escape: bool) -> String {
+    let (dimension, rest) = match dimensions.split_first() {
+        Some(split) => split,
+        None => return write_expr(&array.elements()[offset], escape),
+    };
+    let stride = rest.iter().product::<usize>();
+    let xs = (0..*dimension)
+        .map(|i| write_array(array, rest, offset + i * stride, escape))
+        .collect::<Vec<String>>();
+    format!("({})", xs.join(" "))
+}
+
+fn escape_string(s: &str) -> String {
+    let mut escaped = String::from('"');
+    for ch in s.chars() {
+        if ch == '"' || ch == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn char_name(ch: char) -> String {
+    match ch {
+        ' ' => "Space".to_string(),
+        '\n' => "Newline".to_string(),
+        '\t' => "Tab".to_string(),
+        '\r' => "Return".to_string(),
+        '\0' => "Nul".to_string(),
+        _ => ch.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prin1() {
+        let tests = vec![
+            (Expr::String(r#"say "hi""#.into()), r#""say \"hi\"""#),
+            (Expr::Char('a'), r"#\a"),
+            (Expr::Char(' '), r"#\Space"),
+            (
+                Expr::List(vec![Expr::Symbol("A".into()), Expr::String("b".into())].into()),
+                r#"(A "b")"#,
+            ),
+            (
+                Expr::List(vec![Expr::Symbol("QUOTE".into()), Expr::String("b".into())].into()),
+                r#"'"b""#,
+            ),
+        ];
+        for (expr, want) in tests {
+            assert_eq!(prin1_to_string(&expr), want);
+        }
+    }
+
+    #[test]
+    fn princ() {
+        let tests = vec![
+            (Expr::String(r#"say "hi""#.into()), r#"say "hi""#),
+            (Expr::Char('a'), "a"),
+            (
+                Expr::List(vec![Expr::Symbol("QUOTE".into()), Expr::Symbol("A".into())].into()),
+                "'A",
+            ),
+        ];
+        for (expr, want) in tests {
+            assert_eq!(princ_to_string(&expr), want);
+        }
+    }
+}
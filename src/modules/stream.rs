@@ -0,0 +1,44 @@
+use super::error::RispError;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+// Output streams which FORMAT and the printing builtins can write to.
+#[derive(Clone)]
+pub enum Stream {
+    Stdout,
+    StringOutput(Rc<RefCell<String>>),
+}
+
+impl Stream {
+    pub fn string_output() -> Self {
+        Stream::StringOutput(Rc::new(RefCell::new(String::new())))
+    }
+
+    pub fn write_str(&self, s: &str) -> Result<(), RispError> {
+        match self {
+            Stream::Stdout => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(s.as_bytes())?;
+                stdout.flush()?;
+            }
+            Stream::StringOutput(buffer) => buffer.borrow_mut().push_str(s),
+        }
+        Ok(())
+    }
+
+    pub fn identity(&self) -> usize {
+        match self {
+            Stream::Stdout => 0,
+            Stream::StringOutput(buffer) => Rc::as_ptr(buffer) as usize,
+        }
+    }
+
+    // Returns the contents written to a string output stream and clears it.
+    pub fn take_string(&self) -> Option<String> {
+        match self {
+            Stream::Stdout => None,
+            Stream::StringOutput(buffer) => Some(buffer.take()),
+        }
+    }
+}
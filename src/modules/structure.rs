@@ -0,0 +1,84 @@
+use super::ast::Expr;
+use super::error::RispError;
+use super::gc::Trace;
+use super::package::{sibling_symbol, symbol_name};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub struct Slot {
+    // The name of the slot without its package, as its keyword is named.
+    pub name: String,
+    // Unevaluated form giving the initial value when the constructor is not
+    // passed the slot.
+    pub default: Expr,
+}
+
+// A record type defined by DEFSTRUCT.
+pub struct StructType {
+    pub name: String,
+    pub slots: Vec<Slot>,
+    // The prefix of the accessor names, NAME- unless given by :CONC-NAME.
+    pub conc_name: String,
+    // The functions DEFSTRUCT defines, unless their option is NIL.
+    pub constructor: Option<String>,
+    pub copier: Option<String>,
+    pub predicate: Option<String>,
+}
+
+impl StructType {
+    // A structure type with the functions named after it.
+    pub fn new(name: &str, slots: Vec<Slot>) -> Self {
+        let mut ty = Self {
+            name: name.to_string(),
+            slots,
+            conc_name: format!("{}-", symbol_name(name)),
+            constructor: None,
+            copier: None,
+            predicate: None,
+        };
+        ty.constructor = Some(ty.function_name(|name| format!("MAKE-{}", name)));
+        ty.copier = Some(ty.function_name(|name| format!("COPY-{}", name)));
+        ty.predicate = Some(ty.function_name(|name| format!("{}-P", name)));
+        ty
+    }
+
+    // A function named after the structure, such as MAKE-NAME, in the
+    // package of the structure name.
+    pub fn function_name(&self, format: impl Fn(&str) -> String) -> String {
+        sibling_symbol(&self.name, &format(symbol_name(&self.name)))
+    }
+
+    pub fn accessor_name(&self, slot: &Slot) -> String {
+        sibling_symbol(&self.name, &format!("{}{}", self.conc_name, slot.name))
+    }
+}
+
+pub struct Structure {
+    pub ty: Rc<StructType>,
+    pub values: Vec<Expr>,
+}
+
+impl Trace for Structure {
+    fn trace(&self, visit: &mut dyn FnMut(&Expr)) {
+        self.values.iter().for_each(visit);
+    }
+
+    fn clear_references(&mut self) {
+        self.values.fill(Expr::Nil);
+    }
+
+    fn kind(&self) -> &'static str {
+        "structures"
+    }
+}
+
+// Returns the structure if it is an instance of the given type.
+pub fn expect_structure(
+    x: &Expr,
+    ty: &Rc<StructType>,
+) -> Result<Rc<RefCell<Structure>>, RispError> {
+    match x {
+        Expr::Struct(structure) if Rc::ptr_eq(&structure.borrow().ty, ty) => Ok(structure.clone()),
+        _ => Err(RispError::Expr(format!("{} is not {}", x, ty.name))),
+    }
+}
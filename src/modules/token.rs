@@ -1,3 +1,6 @@
+use super::number::Number;
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq)]
 pub enum Token {
     PLUS,
@@ -6,13 +9,19 @@ pub enum Token {
     SLASH,
     LPAREN,
     RPAREN,
+    VECTOR,
+    STRUCT,
+    QUOTE,
+    FUNCTION,
     EOF,
     TRUE,
     NIL,
     ILLEGAL(String),
-    NUMBER(f64),
+    NUMBER(Number),
+    CHAR(char),
     STRING(String),
     LITERAL(String),
+    UNINTERNED(String),
 }
 
 impl std::fmt::Display for Token {
@@ -24,13 +33,19 @@ impl std::fmt::Display for Token {
             Self::SLASH => "/".to_string(),
             Self::LPAREN => "(".to_string(),
             Self::RPAREN => ")".to_string(),
+            Self::VECTOR => "#(".to_string(),
+            Self::STRUCT => "#S(".to_string(),
+            Self::QUOTE => "'".to_string(),
+            Self::FUNCTION => "#'".to_string(),
             Self::EOF => "EOF".to_string(),
             Self::TRUE => "T".to_string(),
             Self::NIL => "NIL".to_string(),
             Self::ILLEGAL(s) => format!("ILLEGAL({})", s),
             Self::NUMBER(num) => num.to_string(),
+            Self::CHAR(ch) => format!("#\\{}", ch),
             Self::STRING(s) => String::from(s),
             Self::LITERAL(s) => String::from(s),
+            Self::UNINTERNED(s) => format!("#:{}", s),
         };
         write!(f, "{}", s)
     }
@@ -0,0 +1,283 @@
+use super::ast::Expr;
+use super::equality::eql;
+use super::error::RispError;
+use super::number::Number;
+use super::printer::prin1_to_string;
+use std::cmp::Ordering;
+
+// Names of the classes of a value from the most to the least specific. They
+// are the atomic types the value belongs to, besides ATOM and BOOLEAN, and
+// select the methods of generic functions.
+pub fn class_precedence_list(x: &Expr) -> Vec<String> {
+    let names: &[&str] = match x {
+        Expr::Instance(instance) => return instance.borrow().class.precedence_list(),
+        Expr::Struct(structure) => {
+            let name = structure.borrow().ty.name.clone();
+            return vec![name, "STRUCTURE-OBJECT".to_string(), "T".to_string()];
+        }
+        Expr::Number(Number::Fixnum(_)) => &["FIXNUM", "INTEGER", "RATIONAL", "REAL", "NUMBER"],
+        Expr::Number(Number::Bignum(_)) => &["BIGNUM", "INTEGER", "RATIONAL", "REAL", "NUMBER"],
+        Expr::Number(Number::Ratio(_)) => &["RATIO", "RATIONAL", "REAL", "NUMBER"],
+        Expr::Number(Number::Float(_)) => &["DOUBLE-FLOAT", "FLOAT", "REAL", "NUMBER"],
+        Expr::String(_) => &["STRING", "VECTOR", "ARRAY", "SEQUENCE"],
+        Expr::Char(_) => &["CHARACTER"],
+        Expr::Symbol(sym) if sym.starts_with(':') => &["KEYWORD", "SYMBOL"],
+        Expr::Symbol(_) | Expr::True => &["SYMBOL"],
+        Expr::Nil => &["NULL", "SYMBOL", "LIST", "SEQUENCE"],
+        Expr::List(_) => &["CONS", "LIST", "SEQUENCE"],
+        Expr::Vector(_) => &["VECTOR", "ARRAY", "SEQUENCE"],
+        Expr::Array(_) => &["ARRAY"],
+        Expr::Func(_) | Expr::Lambda(_) => &["FUNCTION"],
+        Expr::Stream(_) => &["STREAM"],
+        Expr::HashTable(_) => &["HASH-TABLE"],
+        Expr::Package(_) => &["PACKAGE"],
+    };
+    names
+        .iter()
+        .chain(&["T"])
+        .map(|name| name.to_string())
+        .collect()
+}
+
+// The most specific type name of a value, as returned by TYPE-OF.
+pub fn type_of(x: &Expr) -> String {
+    match x {
+        Expr::True => "BOOLEAN".to_string(),
+        _ => class_precedence_list(x).remove(0),
+    }
+}
+
+// The atomic types known besides structures and classes.
+const BUILTIN_TYPES: [&str; 29] = [
+    "T",
+    "NIL",
+    "ATOM",
+    "BOOLEAN",
+    "NUMBER",
+    "REAL",
+    "RATIONAL",
+    "INTEGER",
+    "FIXNUM",
+    "BIGNUM",
+    "RATIO",
+    "FLOAT",
+    "DOUBLE-FLOAT",
+    "CHARACTER",
+    "SYMBOL",
+    "KEYWORD",
+    "NULL",
+    "LIST",
+    "CONS",
+    "SEQUENCE",
+    "STRING",
+    "VECTOR",
+    "ARRAY",
+    "FUNCTION",
+    "STREAM",
+    "HASH-TABLE",
+    "PACKAGE",
+    "STRUCTURE-OBJECT",
+    "STANDARD-OBJECT",
+];
+
+pub fn is_builtin_type(name: &str) -> bool {
+    BUILTIN_TYPES.contains(&name)
+}
+
+// Whether a value belongs to an atomic type.
+pub fn is_a(x: &Expr, name: &str) -> bool {
+    match name {
+        "ATOM" => !matches!(x, Expr::List(_)),
+        "BOOLEAN" => matches!(x, Expr::True | Expr::Nil),
+        "NIL" => false,
+        _ => class_precedence_list(x).iter().any(|class| class == name),
+    }
+}
+
+pub enum Bound {
+    Unbounded,
+    Inclusive(Number),
+    Exclusive(Number),
+}
+
+impl Bound {
+    fn parse(x: Option<&Expr>) -> Result<Self, RispError> {
+        match x {
+            None => Ok(Bound::Unbounded),
+            Some(Expr::Symbol(sym)) if &**sym == "*" => Ok(Bound::Unbounded),
+            Some(Expr::Number(num)) => Ok(Bound::Inclusive(num.clone())),
+            Some(Expr::List(bound)) => match bound.as_ref() {
+                [Expr::Number(num)] => Ok(Bound::Exclusive(num.clone())),
+                _ => Err(RispError::Expr(format!(
+                    "Invalid type bound {}",
+                    x.unwrap()
+                ))),
+            },
+            Some(x) => Err(RispError::Expr(format!("Invalid type bound {}", x))),
+        }
+    }
+
+    // Whether the number lies on the allowed side of the bound, where
+    // `outside` is the ordering of numbers beyond it.
+    fn admits(&self, num: &Number, outside: Ordering) -> bool {
+        match self {
+            Bound::Unbounded => true,
+            Bound::Inclusive(bound) => num.num_cmp(bound).is_some_and(|ord| ord != outside),
+            Bound::Exclusive(bound) => num
+                .num_cmp(bound)
+                .is_some_and(|ord| ord != outside && ord != Ordering::Equal),
+        }
+    }
+}
+
+// A parsed type specifier such as `string`, `(or number string)` or
+// `(integer 0 10)`.
+pub enum TypeSpec {
+    Named(String),
+    Or(Vec<TypeSpec>),
+    And(Vec<TypeSpec>),
+    Not(Box<TypeSpec>),
+    Member(Vec<Expr>),
+    Range {
+        name: String,
+        low: Bound,
+        high: Bound,
+    },
+    Satisfies(String),
+}
+
+impl TypeSpec {
+    pub fn parse(x: &Expr) -> Result<Self, RispError> {
+        let list = match x {
+            Expr::Symbol(name) => return Ok(TypeSpec::Named(name.to_string())),
+            Expr::True => return Ok(TypeSpec::Named("T".to_string())),
+            Expr::Nil => return Ok(TypeSpec::Named("NIL".to_string())),
+            Expr::List(list) => list,
+            _ => return Err(RispError::Expr(format!("Invalid type specifier {}", x))),
+        };
+        let parse_all = |specs: &[Expr]| {
+            specs
+                .iter()
+                .map(TypeSpec::parse)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        match list.as_ref() {
+            [Expr::Symbol(op), specs @ ..] if &**op == "OR" => Ok(TypeSpec::Or(parse_all(specs)?)),
+            [Expr::Symbol(op), specs @ ..] if &**op == "AND" => {
+                Ok(TypeSpec::And(parse_all(specs)?))
+            }
+            [Expr::Symbol(op), spec] if &**op == "NOT" => {
+                Ok(TypeSpec::Not(Box::new(TypeSpec::parse(spec)?)))
+            }
+            [Expr::Symbol(op), items @ ..] if &**op == "MEMBER" => {
+                Ok(TypeSpec::Member(items.to_vec()))
+            }
+            [Expr::Symbol(op), item] if &**op == "EQL" => Ok(TypeSpec::Member(vec![item.clone()])),
+            [Expr::Symbol(op), Expr::Symbol(predicate)] if &**op == "SATISFIES" => {
+                Ok(TypeSpec::Satisfies(predicate.to_string()))
+            }
+            [Expr::Symbol(name), bounds @ ..]
+                if bounds.len() <= 2
+                    && ["INTEGER", "RATIONAL", "FLOAT", "REAL", "NUMBER"].contains(&&**name) =>
+            {
+                Ok(TypeSpec::Range {
+                    name: name.to_string(),
+                    low: Bound::parse(bounds.first())?,
+                    high: Bound::parse(bounds.get(1))?,
+                })
+            }
+            _ => Err(RispError::Expr(format!(
+                "Invalid type specifier {}",
+                prin1_to_string(x)
+            ))),
+        }
+    }
+
+    // The atomic type names the specifier refers to.
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            TypeSpec::Named(name) | TypeSpec::Range { name, .. } => vec![name],
+            TypeSpec::Or(specs) | TypeSpec::And(specs) => {
+                specs.iter().flat_map(TypeSpec::names).collect()
+            }
+            TypeSpec::Not(spec) => spec.names(),
+            TypeSpec::Member(_) | TypeSpec::Satisfies(_) => vec![],
+        }
+    }
+
+    // Whether the value belongs to the type. SATISFIES types call back
+    // `satisfies` with the predicate name.
+    pub fn matches(
+        &self,
+        x: &Expr,
+        satisfies: &mut dyn FnMut(&str, &Expr) -> Result<bool, RispError>,
+    ) -> Result<bool, RispError> {
+        match self {
+            TypeSpec::Named(name) => Ok(is_a(x, name)),
+            TypeSpec::Or(specs) => {
+                for spec in specs {
+                    if spec.matches(x, satisfies)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            TypeSpec::And(specs) => {
+                for spec in specs {
+                    if !spec.matches(x, satisfies)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            TypeSpec::Not(spec) => Ok(!spec.matches(x, satisfies)?),
+            TypeSpec::Member(items) => Ok(items.iter().any(|item| eql(item, x))),
+            TypeSpec::Range { name, low, high } => Ok(match x {
+                Expr::Number(num) => {
+                    is_a(x, name)
+                        && low.admits(num, Ordering::Less)
+                        && high.admits(num, Ordering::Greater)
+                }
+                _ => false,
+            }),
+            TypeSpec::Satisfies(predicate) => satisfies(predicate, x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::lexer::Lexer;
+    use crate::modules::parser::Parser;
+
+    fn read(s: &str) -> Expr {
+        Parser::new(Lexer::new(s.to_string()).unwrap())
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn type_specifiers() {
+        let tests = vec![
+            ("1", "integer", true),
+            ("1", "(integer 0 10)", true),
+            ("10", "(integer 0 (10))", false),
+            ("-1", "(integer 0 *)", false),
+            ("1.5", "(real 1 2)", true),
+            ("1.5", "(integer 1 2)", false),
+            ("\"a\"", "(or number string)", true),
+            ("a", "(or number string)", false),
+            ("nil", "(and list (not cons))", true),
+            ("(1)", "atom", false),
+            (":b", "(member :a :b)", true),
+            ("t", "boolean", true),
+            ("t", "nil", false),
+        ];
+        for (value, spec, want) in tests {
+            let spec = TypeSpec::parse(&read(spec)).unwrap();
+            let got = spec.matches(&read(value), &mut |_, _| Ok(false)).unwrap();
+            assert_eq!(got, want, "{} {}", value, prin1_to_string(&read(value)));
+        }
+    }
+}